@@ -1,12 +1,21 @@
 use clap::{Parser, Subcommand};
 use config::Config;
 use console::{style, Emoji};
-use grpcio::{ChannelBuilder, EnvBuilder};
+use grpcio::{ChannelBuilder, ChannelCredentialsBuilder, EnvBuilder};
 use indicatif::ProgressStyle;
 use serde::Deserialize;
 use std::{
-    env::current_dir, error::Error, ffi::OsStr, fs::File, io::Read, path::PathBuf, process::exit,
-    str::FromStr, sync::Arc, time::Duration,
+    collections::HashSet,
+    env::current_dir,
+    error::Error,
+    ffi::OsStr,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    process::exit,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
 use tokio::{self, fs::create_dir_all};
 
@@ -20,6 +29,15 @@ struct Args {
     proto_path: String,
     #[clap(short, long, default_value = "localhost:50121")]
     daemon_address: String,
+    #[clap(
+        long,
+        help = "CA certificate to verify the daemon with, connecting over TLS"
+    )]
+    ca: Option<PathBuf>,
+    #[clap(long, help = "Client certificate, when the daemon requires one")]
+    cert: Option<PathBuf>,
+    #[clap(long, help = "Client certificate key")]
+    key: Option<PathBuf>,
     #[clap(
         long,
         default_value = "https://raw.githubusercontent.com/ericm/pandit-packages/main/index.json"
@@ -77,7 +95,16 @@ enum K8s {
 async fn main() {
     let app: Args = Parser::parse();
     let env = Arc::new(EnvBuilder::new().build());
-    let ch = ChannelBuilder::new(env).connect(app.daemon_address.as_str());
+    let ch = match &app.ca {
+        Some(ca) => {
+            let mut creds = ChannelCredentialsBuilder::new().root_cert(std::fs::read(ca).unwrap());
+            if let (Some(cert), Some(key)) = (&app.cert, &app.key) {
+                creds = creds.cert(std::fs::read(cert).unwrap(), std::fs::read(key).unwrap());
+            }
+            ChannelBuilder::new(env).secure_connect(app.daemon_address.as_str(), creds.build())
+        }
+        None => ChannelBuilder::new(env).connect(app.daemon_address.as_str()),
+    };
     let client = api_proto::api_grpc::ApiClient::new(ch);
     let proto_path = {
         let mut path = PathBuf::from(app.proto_path.clone());
@@ -128,15 +155,16 @@ async fn main() {
                 style(path.to_str().unwrap()).green()
             );
 
-            let mut proto_path = proto_path.join(&cfg.metadata.proto);
-            proto_path.set_extension("proto");
+            let mut service_path = proto_path.join(&cfg.metadata.proto);
+            service_path.set_extension("proto");
 
             let mut proto = Vec::<u8>::new();
-            let mut panditfile = File::open(&proto_path).unwrap();
+            let mut panditfile = File::open(&service_path).unwrap();
             panditfile.read_to_end(&mut proto).unwrap();
 
             let mut req = api_proto::api::StartServiceRequest::new();
-            req.set_proto(proto);
+            let name = format!("{}.proto", cfg.metadata.proto);
+            req.set_files(read_protos(&proto_path, &name, proto).into());
             req.set_port(cfg.metadata.port);
             req.set_name(cfg.metadata.name.clone());
             match cfg.docker {
@@ -155,13 +183,13 @@ async fn main() {
             let pb = indicatif::ProgressBar::new_spinner();
             pb.set_message("Awaiting response from pandit...");
             pb.enable_steady_tick(20);
-            let _resp = client.start_service(&req).unwrap();
+            let resp = client.start_service(&req).unwrap();
             pb.finish_and_clear();
             println!(
-                "{} {}Successfully created service '{}' with proto '{}'...",
+                "{} {}Successfully created services '{}' with proto '{}'...",
                 style("[3/3]").bold().dim(),
                 Emoji("✅ ", ""),
-                style(cfg.metadata.name).green(),
+                style(resp.get_services().join("', '")).green(),
                 style(cfg.metadata.proto).green()
             );
         }
@@ -213,3 +241,42 @@ fn error(info: &str, err: Box<dyn Error>) {
         style(err).red().bold(),
     );
 }
+
+/// Collects a proto and the imports found in the proto library, keyed by import path.
+///
+/// Imports missing from the library, such as `pandit.proto`, are provided by the daemon.
+fn read_protos(root: &Path, name: &str, content: Vec<u8>) -> Vec<api_proto::api::ProtoFile> {
+    let mut seen = HashSet::new();
+    let mut pending = vec![(name.to_string(), content)];
+    let mut files = Vec::new();
+    while let Some((name, content)) = pending.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        let imports: Vec<String> = String::from_utf8_lossy(&content)
+            .lines()
+            .filter_map(import_path)
+            .collect();
+        for import in imports {
+            if let Ok(content) = std::fs::read(root.join(&import)) {
+                pending.push((import, content));
+            }
+        }
+        let mut file = api_proto::api::ProtoFile::new();
+        file.set_name(name);
+        file.set_content(content);
+        files.push(file);
+    }
+    files
+}
+
+/// Path of an `import "path";` statement.
+fn import_path(line: &str) -> Option<String> {
+    let line = line.trim().strip_prefix("import")?;
+    if !line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let start = line.find('"')? + 1;
+    let end = start + line[start..].find('"')?;
+    Some(line[start..end].to_string())
+}
@@ -3,8 +3,9 @@ use std::convert::{TryFrom, TryInto};
 use std::env::current_dir;
 use std::error::Error;
 use std::fs::create_dir;
+use std::fs::create_dir_all;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -31,30 +32,37 @@ use k8s_openapi::api::core::v1::{Node, Pod, Service as K8sService};
 use kube::api::ListParams;
 use kube::runtime::utils::try_flatten_applied;
 use kube::runtime::watcher;
+use protobuf::ProtobufEnum;
 use std::io::prelude::*;
 use tempfile::tempdir;
+use tokio::runtime::Handle;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 
 use crate::broker::Broker;
 use crate::server::IntraServer;
+use crate::services::template::EnvAllowList;
 use crate::services::Service;
 use crate::services::ServiceError;
 use crate::services::ServiceResult;
-use crate::writers::writer_from_proto;
+use crate::writers::writer_from_service;
 
 pub struct ApiServer {
     broker: Arc<Broker>,
     server: Arc<IntraServer>,
     network: Option<Arc<dyn NetworkRuntime>>,
     k8s_handler: Option<Arc<K8sHandler>>,
+    /// Environment variables header templates may read.
+    header_env: EnvAllowList,
+    /// Runtime services are registered on, since registering reads Redis.
+    runtime: Handle,
 }
 
 impl api_grpc::Api for ApiServer {
     fn start_service(
         &mut self,
-        ctx: grpcio::RpcContext,
+        _ctx: grpcio::RpcContext,
         req: api::StartServiceRequest,
         sink: grpcio::UnarySink<api::StartServiceReply>,
     ) {
@@ -123,41 +131,34 @@ impl api_grpc::Api for ApiServer {
                 None => default_host,
             },
         };
-        match self.handle_start_service(&ctx, req.clone(), hosts, req.get_port()) {
-            Ok(_) => {
-                let mut save = serde_json::json!({
-                    "name": req.get_name(),
-                    "proto": req.get_proto(),
-                    "port": req.get_port(),
-                    "docker_id": "",
-                    "k8s_pod": "",
-                    "k8s_service": "",
-                    "delegated": true, // Delegated true means it will not be sent to other nodes.
-                });
-                if req.has_docker_id() {
-                    *save.get_mut("docker_id").unwrap() = serde_json::json!(req.get_docker_id());
-                } else if req.has_k8s_pod() {
-                    *save.get_mut("k8s_pod").unwrap() = serde_json::json!(req.get_k8s_pod());
-                } else if req.has_k8s_service() {
-                    *save.get_mut("k8s_service").unwrap() =
-                        serde_json::json!(req.get_k8s_service());
-                }
-                let save = serde_json::to_vec(&save).unwrap();
-                let mut save_file_path = current_dir().unwrap().join(req.get_name());
-                save_file_path.set_extension("pandit_service");
-                let mut save_file = File::create(save_file_path).unwrap();
-                save_file.write_all(&save[..]).unwrap();
-
-                sink.success(api::StartServiceReply::new());
-            }
+        let services = match self.handle_start_service(req.clone(), hosts, req.get_port()) {
+            Ok(services) => services,
             Err(err) => {
-                sink.fail(RpcStatus::with_message(
-                    RpcStatusCode::INTERNAL,
-                    format!("an internal error occurred: {}", err),
-                ));
+                sink.fail(start_failure(&err));
                 return;
             }
         };
+        let broker = self.broker.clone();
+        let server = self.server.clone();
+        self.runtime.spawn(async move {
+            let names: Vec<String> = services.iter().map(|(name, _)| name.clone()).collect();
+            // k8s services run on every node hosting their pods, so only this node is checked.
+            let unique = !req.has_k8s_pod() && !req.has_k8s_service();
+            if let Err(err) = server.add_services(services, unique).await {
+                sink.fail(start_failure(&err));
+                return;
+            }
+            if req.has_k8s_pod() {
+                let pod = req.get_k8s_pod();
+                log::info!("Adding pod to watch: {}", pod);
+                broker.add_pod_to_watch(&pod.to_string(), &req.name, &names[..]);
+            }
+            save_request(&req);
+
+            let mut reply = api::StartServiceReply::new();
+            reply.set_services(names.into());
+            sink.success(reply);
+        });
     }
 }
 
@@ -168,6 +169,8 @@ impl Clone for ApiServer {
             server: self.server.clone(),
             network: self.network.clone(),
             k8s_handler: self.k8s_handler.clone(),
+            header_env: self.header_env.clone(),
+            runtime: self.runtime.clone(),
         }
     }
 }
@@ -178,70 +181,147 @@ impl ApiServer {
         server: Arc<IntraServer>,
         network: Option<Arc<dyn NetworkRuntime>>,
         k8s_handler: Option<Arc<K8sHandler>>,
+        header_env: EnvAllowList,
+        runtime: Handle,
     ) -> Self {
         Self {
             broker,
             server,
             network,
             k8s_handler,
+            header_env,
+            runtime,
         }
     }
 
+    /// Loads the services of a request, which are registered once checked against those
+    /// already running.
     #[inline(always)]
     fn handle_start_service(
         &mut self,
-        ctx: &grpcio::RpcContext,
         req: api::StartServiceRequest,
         hosts: HashSet<String>,
         port: i32,
-    ) -> ServiceResult<()> {
+    ) -> ServiceResult<Vec<(String, Service)>> {
+        check_service_name(&req.name)?;
         let proto_dir = tempdir()?;
         create_dir(proto_dir.path().join("format"))?;
 
-        let proto_path = proto_dir.path().join("api.proto");
-        {
+        // The first file holds the services, the rest are written at their import paths.
+        let mut proto_paths = Vec::with_capacity(req.files.len());
+        for (name, content) in proto_files(&req) {
+            let proto_path = proto_dir.path().join(proto_file_path(&name)?);
+            if let Some(parent) = proto_path.parent() {
+                create_dir_all(parent)?;
+            }
             let mut proto_file = File::create(proto_path.clone())?;
-            proto_file.write_all(&req.proto[..])?;
+            proto_file.write_all(content)?;
+            proto_paths.push(proto_path);
         }
+        let proto_path = proto_paths
+            .into_iter()
+            .next()
+            .ok_or(ServiceError::InvalidArgument("no proto files".to_string()))?;
         for (name, file) in proto_libraries() {
             let mut proto_path = proto_dir.path().join(name);
             proto_path.set_extension("proto");
             let mut proto_file = File::create(proto_path.clone())?;
             proto_file.write_all(file)?;
         }
-        let service = Service::from_file(
+        let services = Service::from_file(
             proto_path.to_str().unwrap_or_default(),
             &[proto_dir.path().to_str().unwrap_or_default()],
-            writer_from_proto(
-                proto_path.clone(),
-                &[proto_dir.path().to_path_buf()],
-                hosts,
-                port,
-            )?,
+            |service| writer_from_service(service, hosts.clone(), port),
             self.broker.clone(),
+            &self.header_env,
         )?;
+        // Services are registered under their name in the proto, which calls are routed by.
+        Ok(services
+            .into_iter()
+            .map(|s| (s.proto_name.clone(), s))
+            .collect())
+    }
+}
 
-        let broker = self.broker.clone();
-        let server = self.server.clone();
-        let name = req.name.clone();
-        ctx.spawn(async move {
-            {
-                broker.publish_service(&name, &service).unwrap();
-                if req.has_k8s_pod() {
-                    let pod = req.get_k8s_pod();
-                    log::info!("Adding pod to watch: {}", pod);
-                    broker.add_pod_to_watch(&pod.to_string(), &name);
-                }
-            }
-            log::info!("broker service published: {}", &name);
-            {
-                server.add_servivce(name, service).await;
-            }
-        });
-        Ok(())
+fn start_failure(err: &ServiceError) -> RpcStatus {
+    RpcStatus::with_message(
+        RpcStatusCode::from(err.status().value()),
+        format!("an error occurred starting the service: {}", err),
+    )
+}
+
+/// Saves a started service next to the daemon, so it is started again on restart.
+fn save_request(req: &api::StartServiceRequest) {
+    let mut save = serde_json::json!({
+        "name": req.get_name(),
+        "proto": req.get_proto(),
+        "files": req
+            .get_files()
+            .iter()
+            .map(|file| serde_json::json!({
+                "name": file.get_name(),
+                "content": file.get_content(),
+            }))
+            .collect::<Vec<_>>(),
+        "port": req.get_port(),
+        "docker_id": "",
+        "k8s_pod": "",
+        "k8s_service": "",
+        "delegated": true, // Delegated true means it will not be sent to other nodes.
+    });
+    if req.has_docker_id() {
+        *save.get_mut("docker_id").unwrap() = serde_json::json!(req.get_docker_id());
+    } else if req.has_k8s_pod() {
+        *save.get_mut("k8s_pod").unwrap() = serde_json::json!(req.get_k8s_pod());
+    } else if req.has_k8s_service() {
+        *save.get_mut("k8s_service").unwrap() = serde_json::json!(req.get_k8s_service());
+    }
+    let save = serde_json::to_vec(&save).unwrap();
+    let mut save_file_path = current_dir().unwrap().join(req.get_name());
+    save_file_path.set_extension("pandit_service");
+    let mut save_file = File::create(save_file_path).unwrap();
+    save_file.write_all(&save[..]).unwrap();
+}
+
+/// Files of a request by import path, falling back to the single `proto` file named after
+/// the service so files stay distinct in reflection.
+fn proto_files(req: &api::StartServiceRequest) -> Vec<(String, &[u8])> {
+    if req.files.is_empty() {
+        return vec![(format!("{}.proto", req.name), &req.proto[..])];
+    }
+    req.files
+        .iter()
+        .map(|file| (file.name.clone(), &file.content[..]))
+        .collect()
+}
+
+/// Checks a service name is a single path component, as it also names its proto file.
+fn check_service_name(name: &str) -> ServiceResult<()> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.contains('/') => Ok(()),
+        _ => Err(ServiceError::InvalidArgument(format!(
+            "invalid service name: {}",
+            name
+        ))),
     }
 }
 
+/// Checks an import path stays inside the directory protos are written to.
+fn proto_file_path(name: &str) -> ServiceResult<PathBuf> {
+    let path = Path::new(name);
+    let relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if name.is_empty() || !relative {
+        return Err(ServiceError::InvalidArgument(format!(
+            "invalid proto file name: {}",
+            name
+        )));
+    }
+    Ok(path.to_path_buf())
+}
+
 fn proto_libraries() -> [(&'static str, &'static [u8]); 4] {
     [
         ("pandit", include_bytes!("../proto/pandit.proto")),
@@ -284,7 +364,7 @@ pub async fn add_service_from_file(
     if k8s_pod != "" {
         match broker {
             Some(broker) => {
-                broker.add_pod_to_watch(&k8s_pod, &name);
+                broker.add_pod_to_watch(&k8s_pod, &name, &[]);
             }
             None => {}
         }
@@ -304,16 +384,25 @@ pub async fn add_service_from_file(
         .unwrap()
         .try_into()
         .unwrap();
-    let proto: Vec<u8> = save
-        .get("proto")
-        .unwrap()
-        .as_array()
-        .unwrap()
-        .iter()
-        .map(|v| u8::try_from(v.as_u64().unwrap()).unwrap())
-        .collect();
+    let bytes = |value: &serde_json::Value| -> Vec<u8> {
+        value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| u8::try_from(v.as_u64().unwrap()).unwrap())
+            .collect()
+    };
+    let proto = bytes(save.get("proto").unwrap());
     let mut req = api_proto::api::StartServiceRequest::new();
     req.set_proto(proto);
+    // Saved before multi-file requests, older services have no files.
+    let files = save.get("files").and_then(|v| v.as_array());
+    for file in files.into_iter().flatten() {
+        let mut proto_file = api::ProtoFile::new();
+        proto_file.set_name(file.get("name").unwrap().as_str().unwrap().to_string());
+        proto_file.set_content(bytes(file.get("content").unwrap()));
+        req.mut_files().push(proto_file);
+    }
     req.set_port(port);
     req.set_name(name);
     if k8s_pod != "" {
@@ -358,10 +447,9 @@ impl K8sHandler {
         tx.blocking_send(req.clone())?;
         let mut hostrx = self.hostrx.blocking_write();
         log::info!("k8s_grpcio: call handle if external");
-        match hostrx
-            .blocking_recv()
-            .ok_or("error receiving from k8s handler runtime")?
-        {
+        match hostrx.blocking_recv().ok_or(ServiceError::new(
+            "error receiving from k8s handler runtime",
+        ))? {
             Ok(host) => Ok(host),
             Err(err) => Err(ServiceError::new(err.as_str())),
         }
@@ -412,13 +500,13 @@ impl K8sHandler {
                     .spec
                     .unwrap_or_default()
                     .node_name
-                    .ok_or("no node name for pod")?;
+                    .ok_or(ServiceError::new("no node name for pod"))?;
                 if node.eq(current_node) {
                     let ip = pod
                         .status
-                        .ok_or("no pod status")?
+                        .ok_or(ServiceError::new("no pod status"))?
                         .pod_ip
-                        .ok_or("no pod ip")?;
+                        .ok_or(ServiceError::new("no pod ip"))?;
                     ips.insert(ip); // Current node ips only.
                 }
                 nodes.insert(node);
@@ -430,32 +518,38 @@ impl K8sHandler {
         let client = kube::Client::try_default().await?;
         let current_node = std::env::var("NODE_NAME")?;
         log::info!("k8s: current_node: {}", current_node);
-        let container = req.container.as_ref().ok_or("no container in req")?;
+        let container = req
+            .container
+            .as_ref()
+            .ok_or(ServiceError::new("no container in req"))?;
         let mut ips = HashSet::<String>::new();
         let mut pod_nodes = match container {
             k8s_pod(id) => {
                 let pods: kube::Api<Pod> = kube::Api::default_namespaced(client.clone());
                 log::info!("k8s: connected to default namespace pod api");
-                let pod: Pod = pods.get_opt(id.as_str()).await?.ok_or("no pod found")?;
+                let pod: Pod = pods
+                    .get_opt(id.as_str())
+                    .await?
+                    .ok_or(ServiceError::new("no pod found"))?;
                 log::info!("k8s: found pod with id: {}", id);
-                let spec = pod.spec.ok_or("no pod spec")?;
+                let spec = pod.spec.ok_or(ServiceError::new("no pod spec"))?;
                 let ip = pod
                     .status
-                    .ok_or("no pod status")?
+                    .ok_or(ServiceError::new("no pod status"))?
                     .pod_ip
-                    .ok_or("no pod ip")?;
+                    .ok_or(ServiceError::new("no pod ip"))?;
                 log::info!("k8s: found pod's ip: {}", &ip);
                 ips.insert(ip);
                 let mut nodes = HashSet::<String>::new();
-                nodes.insert(spec.node_name.ok_or("no node name")?);
+                nodes.insert(spec.node_name.ok_or(ServiceError::new("no node name"))?);
                 nodes
             }
             k8s_service(id) => {
                 let services: kube::Api<K8sService> = kube::Api::default_namespaced(client.clone());
                 let service: K8sService = services.get(id.as_str()).await?;
-                let spec = service.spec.ok_or("no service spec")?;
+                let spec = service.spec.ok_or(ServiceError::new("no service spec"))?;
 
-                let ip = spec.cluster_ip.ok_or("no cluster ip")?;
+                let ip = spec.cluster_ip.ok_or(ServiceError::new("no cluster ip"))?;
                 log::info!("k8s: found service '{}' with ip: {}", style(id).green(), ip);
                 ips.insert(ip);
 
@@ -465,7 +559,7 @@ impl K8sHandler {
             k8s_replica_set(id) => {
                 let sets: kube::Api<ReplicaSet> = kube::Api::default_namespaced(client.clone());
                 let set = sets.get(id.as_str()).await?;
-                let spec = set.spec.ok_or("no set spec")?;
+                let spec = set.spec.ok_or(ServiceError::new("no set spec"))?;
 
                 let selectors = spec.selector.match_labels.unwrap_or_default();
                 pods_from_labels(selectors, &mut ips, &current_node).await?
@@ -473,7 +567,7 @@ impl K8sHandler {
             k8s_stateful_set(id) => {
                 let sets: kube::Api<StatefulSet> = kube::Api::default_namespaced(client.clone());
                 let set = sets.get(id.as_str()).await?;
-                let spec = set.spec.ok_or("no set spec")?;
+                let spec = set.spec.ok_or(ServiceError::new("no set spec"))?;
 
                 let selectors = spec.selector.match_labels.unwrap_or_default();
                 pods_from_labels(selectors, &mut ips, &current_node).await?
@@ -491,15 +585,22 @@ impl K8sHandler {
         if ips.len() == 0 {
             for pod in &pod_nodes {
                 let pods: kube::Api<Pod> = kube::Api::default_namespaced(client.clone());
-                let pod: Pod = pods.get_opt(pod.as_str()).await?.ok_or("no pod found")?;
-                let spec = pod.spec.ok_or("no pod spec")?;
-                let pod_node = spec.node_name.ok_or("no node name")?;
+                let pod: Pod = pods
+                    .get_opt(pod.as_str())
+                    .await?
+                    .ok_or(ServiceError::new("no pod found"))?;
+                let spec = pod.spec.ok_or(ServiceError::new("no pod spec"))?;
+                let pod_node = spec.node_name.ok_or(ServiceError::new("no node name"))?;
                 let node_ip = {
                     let nodes: kube::Api<Node> = kube::Api::default_namespaced(client.clone());
                     let node = nodes.get(pod_node.as_str()).await?;
-                    let status = node.status.ok_or("no node status")?;
-                    let addresses = status.addresses.ok_or("no node addresses")?;
-                    let addr = addresses.first().ok_or("no available node addresses")?;
+                    let status = node.status.ok_or(ServiceError::new("no node status"))?;
+                    let addresses = status
+                        .addresses
+                        .ok_or(ServiceError::new("no node addresses"))?;
+                    let addr = addresses
+                        .first()
+                        .ok_or(ServiceError::new("no available node addresses"))?;
                     addr.address.clone()
                 };
                 ips.insert(node_ip);
@@ -517,9 +618,13 @@ impl K8sHandler {
             let node_ip = {
                 let nodes: kube::Api<Node> = kube::Api::all(client.clone());
                 let node = nodes.get(pod_node.as_str()).await?;
-                let status = node.status.ok_or("no node status")?;
-                let addresses = status.addresses.ok_or("no node addresses")?;
-                let addr = addresses.first().ok_or("no available node addresses")?;
+                let status = node.status.ok_or(ServiceError::new("no node status"))?;
+                let addresses = status
+                    .addresses
+                    .ok_or(ServiceError::new("no node addresses"))?;
+                let addr = addresses
+                    .first()
+                    .ok_or(ServiceError::new("no available node addresses"))?;
                 addr.address.clone()
             };
             log::info!("k8s: node ip: {}", node_ip);
@@ -540,8 +645,8 @@ impl K8sHandler {
         let pod_node = {
             let pods: kube::Api<Pod> = kube::Api::default_namespaced(client);
             let pod: Pod = pods.get(pod.as_str()).await?;
-            let spec = pod.spec.ok_or("no pod spec")?;
-            spec.node_name.ok_or("no node name")?
+            let spec = pod.spec.ok_or(ServiceError::new("no pod spec"))?;
+            spec.node_name.ok_or(ServiceError::new("no node name"))?
         };
         Ok(pod_node == current_node)
     }
@@ -654,7 +759,9 @@ impl DockerNetworkRuntime {
     ) -> ServiceResult<()> {
         let id = hostname::get()?.to_str().unwrap_or_default().to_string();
         let container_info = self.client.inspect_container(id.as_str(), None).await?;
-        let pandit_name = container_info.name.ok_or("no name for pandit container")?;
+        let pandit_name = container_info
+            .name
+            .ok_or(ServiceError::new("no name for pandit container"))?;
 
         // Disconnect networks just in case.
         let mut cfg = DisconnectNetworkOptions::<String>::default();
@@ -711,8 +818,12 @@ impl DockerNetworkRuntime {
                 .client
                 .inspect_container(container_id.as_str(), None)
                 .await?;
-            let container_id = container_info.id.ok_or("container has no id")?;
-            let container_name = container_info.name.ok_or("container has no id")?;
+            let container_id = container_info
+                .id
+                .ok_or(ServiceError::new("container has no id"))?;
+            let container_name = container_info
+                .name
+                .ok_or(ServiceError::new("container has no id"))?;
             let containers = network
                 .containers
                 .as_ref()
@@ -30,6 +30,7 @@ pub struct StartServiceRequest {
     pub proto: ::std::vec::Vec<u8>,
     pub port: i32,
     pub delegated: bool,
+    pub files: ::protobuf::RepeatedField<ProtoFile>,
     // message oneof groups
     pub container: ::std::option::Option<StartServiceRequest_oneof_container>,
     // special fields
@@ -383,10 +384,40 @@ impl StartServiceRequest {
     pub fn set_delegated(&mut self, v: bool) {
         self.delegated = v;
     }
+
+    // repeated .api.ProtoFile files = 10;
+
+
+    pub fn get_files(&self) -> &[ProtoFile] {
+        &self.files
+    }
+    pub fn clear_files(&mut self) {
+        self.files.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_files(&mut self, v: ::protobuf::RepeatedField<ProtoFile>) {
+        self.files = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_files(&mut self) -> &mut ::protobuf::RepeatedField<ProtoFile> {
+        &mut self.files
+    }
+
+    // Take field
+    pub fn take_files(&mut self) -> ::protobuf::RepeatedField<ProtoFile> {
+        ::std::mem::replace(&mut self.files, ::protobuf::RepeatedField::new())
+    }
 }
 
 impl ::protobuf::Message for StartServiceRequest {
     fn is_initialized(&self) -> bool {
+        for v in &self.files {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -444,6 +475,9 @@ impl ::protobuf::Message for StartServiceRequest {
                     let tmp = is.read_bool()?;
                     self.delegated = tmp;
                 },
+                10 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.files)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -468,6 +502,10 @@ impl ::protobuf::Message for StartServiceRequest {
         if self.delegated != false {
             my_size += 2;
         }
+        for value in &self.files {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
         if let ::std::option::Option::Some(ref v) = self.container {
             match v {
                 &StartServiceRequest_oneof_container::docker_id(ref v) => {
@@ -505,6 +543,11 @@ impl ::protobuf::Message for StartServiceRequest {
         if self.delegated != false {
             os.write_bool(9, self.delegated)?;
         }
+        for v in &self.files {
+            os.write_tag(10, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
         if let ::std::option::Option::Some(ref v) = self.container {
             match v {
                 &StartServiceRequest_oneof_container::docker_id(ref v) => {
@@ -607,6 +650,11 @@ impl ::protobuf::Message for StartServiceRequest {
                 |m: &StartServiceRequest| { &m.delegated },
                 |m: &mut StartServiceRequest| { &mut m.delegated },
             ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<ProtoFile>>(
+                "files",
+                |m: &StartServiceRequest| { &m.files },
+                |m: &mut StartServiceRequest| { &mut m.files },
+            ));
             ::protobuf::reflect::MessageDescriptor::new_pb_name::<StartServiceRequest>(
                 "StartServiceRequest",
                 fields,
@@ -632,6 +680,7 @@ impl ::protobuf::Clear for StartServiceRequest {
         self.container = ::std::option::Option::None;
         self.container = ::std::option::Option::None;
         self.delegated = false;
+        self.files.clear();
         self.unknown_fields.clear();
     }
 }
@@ -650,6 +699,8 @@ impl ::protobuf::reflect::ProtobufValue for StartServiceRequest {
 
 #[derive(PartialEq,Clone,Default)]
 pub struct StartServiceReply {
+    // message fields
+    pub services: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -665,6 +716,31 @@ impl StartServiceReply {
     pub fn new() -> StartServiceReply {
         ::std::default::Default::default()
     }
+
+    // repeated string services = 1;
+
+
+    pub fn get_services(&self) -> &[::std::string::String] {
+        &self.services
+    }
+    pub fn clear_services(&mut self) {
+        self.services.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_services(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.services = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_services(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.services
+    }
+
+    // Take field
+    pub fn take_services(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.services, ::protobuf::RepeatedField::new())
+    }
 }
 
 impl ::protobuf::Message for StartServiceReply {
@@ -676,6 +752,9 @@ impl ::protobuf::Message for StartServiceReply {
         while !is.eof()? {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.services)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -688,12 +767,18 @@ impl ::protobuf::Message for StartServiceReply {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
+        for value in &self.services {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.services {
+            os.write_string(1, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -731,7 +816,12 @@ impl ::protobuf::Message for StartServiceReply {
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
-            let fields = ::std::vec::Vec::new();
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "services",
+                |m: &StartServiceReply| { &m.services },
+                |m: &mut StartServiceReply| { &mut m.services },
+            ));
             ::protobuf::reflect::MessageDescriptor::new_pb_name::<StartServiceReply>(
                 "StartServiceReply",
                 fields,
@@ -748,6 +838,7 @@ impl ::protobuf::Message for StartServiceReply {
 
 impl ::protobuf::Clear for StartServiceReply {
     fn clear(&mut self) {
+        self.services.clear();
         self.unknown_fields.clear();
     }
 }
@@ -764,17 +855,222 @@ impl ::protobuf::reflect::ProtobufValue for StartServiceReply {
     }
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct ProtoFile {
+    // message fields
+    pub name: ::std::string::String,
+    pub content: ::std::vec::Vec<u8>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ProtoFile {
+    fn default() -> &'a ProtoFile {
+        <ProtoFile as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ProtoFile {
+    pub fn new() -> ProtoFile {
+        ::std::default::Default::default()
+    }
+
+    // string name = 1;
+
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn clear_name(&mut self) {
+        self.name.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_name(&mut self, v: ::std::string::String) {
+        self.name = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_name(&mut self) -> &mut ::std::string::String {
+        &mut self.name
+    }
+
+    // Take field
+    pub fn take_name(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.name, ::std::string::String::new())
+    }
+
+    // bytes content = 2;
+
+
+    pub fn get_content(&self) -> &[u8] {
+        &self.content
+    }
+    pub fn clear_content(&mut self) {
+        self.content.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_content(&mut self, v: ::std::vec::Vec<u8>) {
+        self.content = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_content(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.content
+    }
+
+    // Take field
+    pub fn take_content(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.content, ::std::vec::Vec::new())
+    }
+}
+
+impl ::protobuf::Message for ProtoFile {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.name)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.content)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        if !self.content.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(2, &self.content);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        if !self.content.is_empty() {
+            os.write_bytes(2, &self.content)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ProtoFile {
+        ProtoFile::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "name",
+                |m: &ProtoFile| { &m.name },
+                |m: &mut ProtoFile| { &mut m.name },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                "content",
+                |m: &ProtoFile| { &m.content },
+                |m: &mut ProtoFile| { &mut m.content },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ProtoFile>(
+                "ProtoFile",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static ProtoFile {
+        static instance: ::protobuf::rt::LazyV2<ProtoFile> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ProtoFile::new)
+    }
+}
+
+impl ::protobuf::Clear for ProtoFile {
+    fn clear(&mut self) {
+        self.name.clear();
+        self.content.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for ProtoFile {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ProtoFile {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
-    \n\tapi.proto\x12\x03api\"\xb1\x02\n\x13StartServiceRequest\x12\x12\n\
+    \n\tapi.proto\x12\x03api\"\xd7\x02\n\x13StartServiceRequest\x12\x12\n\
     \x04name\x18\x01\x20\x01(\tR\x04name\x12\x14\n\x05proto\x18\x02\x20\x01(\
     \x0cR\x05proto\x12\x12\n\x04port\x18\x03\x20\x01(\x05R\x04port\x12\x1d\n\
     \tdocker_id\x18\x04\x20\x01(\tH\0R\x08dockerId\x12\x19\n\x07k8s_pod\x18\
     \x05\x20\x01(\tH\0R\x06k8sPod\x12!\n\x0bk8s_service\x18\x06\x20\x01(\tH\
     \0R\nk8sService\x12(\n\x0fk8s_replica_set\x18\x07\x20\x01(\tH\0R\rk8sRep\
     licaSet\x12*\n\x10k8s_stateful_set\x18\x08\x20\x01(\tH\0R\x0ek8sStateful\
-    Set\x12\x1c\n\tdelegated\x18\t\x20\x01(\x08R\tdelegatedB\x0b\n\tcontaine\
-    r\"\x13\n\x11StartServiceReply2I\n\x03API\x12B\n\x0cStartService\x12\x18\
-    .api.StartServiceRequest\x1a\x16.api.StartServiceReply\"\0b\x06proto3\
+    Set\x12\x1c\n\tdelegated\x18\t\x20\x01(\x08R\tdelegated\x12$\n\x05files\
+    \x18\n\x20\x03(\x0b2\x0e.api.ProtoFileR\x05filesB\x0b\n\tcontainer\"/\n\
+    \x11StartServiceReply\x12\x1a\n\x08services\x18\x01\x20\x03(\tR\x08servi\
+    ces\"9\n\tProtoFile\x12\x12\n\x04name\x18\x01\x20\x01(\tR\x04name\x12\
+    \x18\n\x07content\x18\x02\x20\x01(\x0cR\x07content2I\n\x03API\x12B\n\x0c\
+    StartService\x12\x18.api.StartServiceRequest\x1a\x16.api.StartServiceRep\
+    ly\"\0b\x06proto3\
 ";
 
 static file_descriptor_proto_lazy: ::protobuf::rt::LazyV2<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::rt::LazyV2::INIT;
@@ -13,18 +13,24 @@ use hyper::body::HttpBody;
 use k8s_openapi::api::core::v1::Pod;
 use kube::runtime::utils::try_flatten_applied;
 use kube::runtime::watcher;
+use protobuf::descriptor::FileDescriptorProto;
 use protobuf::well_known_types::Field;
 use redis::cluster::ClusterClient;
 use redis::{Client, Commands, Connection, Msg, PubSubCommands};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 use tokio::sync::{mpsc, RwLock};
 use tokio::time::sleep;
 
 use crate::api::{add_service_from_file, K8sHandler};
-use crate::server::IntraServer;
-use crate::services::base::CacheOptions;
+use crate::server::deadline;
+use crate::server::frame::{self, Encoding, FrameDecoder};
+use crate::server::tls::ClusterConfig;
+use crate::server::{decode_status_message, IntraServer, DELEGATED_HEADER};
+use crate::services::base::{CacheOptions, StatusCode};
 use crate::services::value::Value;
-use crate::services::{message::Message, Fields, ServiceResult};
+use crate::services::{message::Message, Fields, MessageStream, ServiceResult};
+use crate::services::{stream_with_deadline, with_deadline, Deadline, Metadata};
 use crate::services::{FieldsMap, Method, Sender, Service, ServiceError};
 
 struct CachedFields {
@@ -33,12 +39,22 @@ struct CachedFields {
     timestamp: SystemTime,
 }
 
+/// Services started for a watched pod.
+#[derive(Clone)]
+struct PodServices {
+    /// Name the start request was saved under, to start the services again from.
+    request: String,
+    services: Vec<String>,
+}
+
 #[derive(Clone)]
 struct CachedMessage {
     message: Message,
     cache: Option<CacheOptions>,
     fields_for_key: Arc<DashMap<Value, CachedFields>>,
     primary_key: String,
+    /// Whether the method streams in both directions.
+    bidi: bool,
 }
 
 impl CachedMessage {
@@ -82,49 +98,41 @@ impl CachedMessage {
 pub struct RemoteSender {
     addr: String,
     method_fields_map: Arc<DashMap<String, CachedMessage>>,
+    compression: Option<Encoding>,
+    cluster: Option<Arc<ClusterConfig>>,
 }
 
 #[async_trait]
 impl Sender for RemoteSender {
     async fn send(
-        &mut self,
+        &self,
         service_name: &String,
         method: &String,
-        data: &[u8],
-    ) -> ServiceResult<bytes::Bytes> {
-        use h2::*;
-        log::info!(
-            "Delegating request for {}_{} to {}",
-            service_name,
-            method,
-            self.addr
+        data: &[bytes::Bytes],
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream> {
+        let data = futures::stream::iter(data.to_vec().into_iter().map(Ok)).boxed();
+        let resp = with_deadline(
+            deadline,
+            self.delegate(service_name, method, data, deadline, metadata),
         );
-        let tcp = TcpStream::connect(&self.addr).await?;
-        let (mut client, connection) = client::handshake(tcp).await?;
-        tokio::spawn(async move {
-            connection.await.unwrap();
-        });
-        let req = http::Request::builder()
-            .uri(format!("http://{}/{}/{}", self.addr, service_name, method))
-            .version(http::Version::HTTP_2)
-            .method("POST")
-            .body(())?;
-        let (response, mut send) = client.send_request(req, false)?;
-        send.send_data(bytes::Bytes::copy_from_slice(data), true)?;
-        let (parts, mut body) = response.await?.into_parts();
-        let body = match body.data().await {
-            Some(body) => body,
-            None => {
-                return Err(ServiceError::new(
-                    format!(
-                        "error on other node: {:?}",
-                        parts.headers.get("grpc-message")
-                    )
-                    .as_str(),
-                ))
-            }
-        };
-        Ok(body?)
+        Ok(stream_with_deadline(resp.await?, deadline))
+    }
+
+    async fn send_stream(
+        &self,
+        service_name: &String,
+        method: &String,
+        data: MessageStream,
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream> {
+        let resp = with_deadline(
+            deadline,
+            self.delegate(service_name, method, data, deadline, metadata),
+        );
+        Ok(stream_with_deadline(resp.await?, deadline))
     }
 
     async fn probe_cache(
@@ -134,22 +142,189 @@ impl Sender for RemoteSender {
         data: &[u8],
     ) -> ServiceResult<Option<bytes::Bytes>> {
         let name = format!("{}_{}", service_name, method);
-        let message = self
-            .method_fields_map
-            .get(&name)
-            .ok_or(format!("no entry for service method: {}", &name))?;
+        let message = self.method_fields_map.get(&name).ok_or(ServiceError::new(
+            format!("no entry for service method: {}", &name).as_str(),
+        ))?;
         let fields = message.message.fields_from_bytes(data)?;
 
-        let primary_key = fields.map.get(&message.primary_key).ok_or(format!(
-            "no field for primary key: {}",
-            &message.primary_key
-        ))?;
-        let primary_key = primary_key.as_ref().ok_or("no value for primary key")?;
+        let primary_key = fields
+            .map
+            .get(&message.primary_key)
+            .ok_or(ServiceError::new(
+                format!("no field for primary key: {}", &message.primary_key).as_str(),
+            ))?;
+        let primary_key = primary_key
+            .as_ref()
+            .ok_or(ServiceError::new("no value for primary key"))?;
 
         message.check_cache(primary_key)
     }
 }
 
+impl RemoteSender {
+    async fn delegate(
+        &self,
+        service_name: &String,
+        method: &String,
+        data: MessageStream,
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream> {
+        log::info!(
+            "Delegating request for {}_{} to {}",
+            service_name,
+            method,
+            self.addr
+        );
+        let tcp = TcpStream::connect(&self.addr)
+            .await
+            .map_err(|err| ServiceError::Unavailable(err.to_string()))?;
+        let (mut client, scheme) = match &self.cluster {
+            Some(cluster) => (handshake(cluster.connect(tcp).await?).await?, "https"),
+            None => (handshake(tcp).await?, "http"),
+        };
+        let mut req = http::Request::builder()
+            .uri(format!(
+                "{}://{}/{}/{}",
+                scheme, self.addr, service_name, method
+            ))
+            .version(http::Version::HTTP_2)
+            .method("POST")
+            .header(DELEGATED_HEADER, "1");
+        // The other node picks what to forward to its backend.
+        for (name, value) in metadata.forwardable() {
+            req = req.header(name, value);
+        }
+        // Pass the remaining budget on to the other node.
+        if let Some(deadline) = deadline {
+            req = req.header("grpc-timeout", deadline::encode_timeout(deadline));
+        }
+        if let Some(encoding) = self.compression {
+            req = req
+                .header("grpc-encoding", encoding.name())
+                .header("grpc-accept-encoding", encoding.name());
+        }
+        let req = req.body(())?;
+        let (response, send) = client.send_request(req, false)?;
+        // Messages are passed on as they arrive, while responses may already be coming back.
+        tokio::spawn(send_messages(send, data, self.compression));
+        let (parts, body) = response.await?.into_parts();
+        if let Some(err) = status_from_headers(&parts.headers) {
+            return Err(err);
+        }
+
+        // Pass messages through as they arrive from the other node.
+        let decoder = FrameDecoder::new(frame::DEFAULT_MAX_MESSAGE_SIZE)
+            .with_encoding(Encoding::from_headers(&parts.headers)?);
+        let metadata = metadata.clone();
+        let body = futures::stream::unfold(
+            (body, decoder, false),
+            move |(mut body, mut decoder, done)| {
+                let metadata = metadata.clone();
+                async move {
+                    if done {
+                        return None;
+                    }
+                    loop {
+                        match decoder.next_message() {
+                            Ok(Some(message)) => {
+                                return Some((Ok(message), (body, decoder, false)))
+                            }
+                            Ok(None) => {}
+                            Err(err) => return Some((Err(err), (body, decoder, true))),
+                        }
+                        match body.data().await {
+                            Some(Ok(data)) => {
+                                let _ = body.flow_control().release_capacity(data.len());
+                                decoder.push(&data[..]);
+                            }
+                            Some(Err(err)) => {
+                                let err = ServiceError::Unavailable(format!(
+                                    "error on other node: {}",
+                                    err
+                                ));
+                                return Some((Err(err), (body, decoder, true)));
+                            }
+                            None => break,
+                        }
+                    }
+                    if let Err(err) = decoder.finish() {
+                        return Some((Err(err), (body, decoder, true)));
+                    }
+                    // Relay the status and backend headers reported by the other node.
+                    match body.trailers().await {
+                        Ok(Some(trailers)) => {
+                            metadata.extend_response(&trailers);
+                            status_from_headers(&trailers)
+                                .map(|err| (Err(err), (body, decoder, true)))
+                        }
+                        Ok(None) => None,
+                        Err(err) => {
+                            let err =
+                                ServiceError::Unavailable(format!("error on other node: {}", err));
+                            Some((Err(err), (body, decoder, true)))
+                        }
+                    }
+                }
+            },
+        );
+        Ok(body.boxed())
+    }
+}
+
+/// Sends the messages of a delegated call, resetting the stream if the client's fails.
+async fn send_messages(
+    mut send: h2::SendStream<bytes::Bytes>,
+    mut data: MessageStream,
+    compression: Option<Encoding>,
+) {
+    while let Some(message) = data.next().await {
+        let message = message.and_then(|message| frame::compress(message, compression));
+        let result = match message {
+            Ok(message) => send.send_data(message, false),
+            Err(err) => {
+                log::error!("error reading delegated request: {}", err);
+                send.send_reset(h2::Reason::CANCEL);
+                return;
+            }
+        };
+        if let Err(err) = result {
+            log::error!("error sending delegated request: {}", err);
+            return;
+        }
+    }
+    let _ = send.send_data(bytes::Bytes::new(), true);
+}
+
+/// Runs the HTTP/2 handshake with another node, driving the connection on its own task.
+async fn handshake<T>(io: T) -> ServiceResult<h2::client::SendRequest<bytes::Bytes>>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (client, connection) = h2::client::handshake(io).await?;
+    tokio::spawn(async move {
+        connection.await.unwrap();
+    });
+    Ok(client)
+}
+
+/// Reads a non-OK gRPC status from response headers or trailers.
+fn status_from_headers(headers: &http::HeaderMap) -> Option<ServiceError> {
+    use protobuf::ProtobufEnum;
+    let code: i32 = headers.get("grpc-status")?.to_str().ok()?.parse().ok()?;
+    if code == 0 {
+        return None;
+    }
+    let message = headers
+        .get("grpc-message")
+        .map(|message| decode_status_message(message.as_bytes()))
+        .unwrap_or_default();
+    Some(ServiceError::Status {
+        code: StatusCode::from_i32(code).unwrap_or(StatusCode::UNKNOWN),
+        message: format!("error on other node: {}", message),
+    })
+}
+
 pub struct Broker {
     client: Client,
     method_fields_map: Arc<DashMap<String, CachedMessage>>,
@@ -157,7 +332,9 @@ pub struct Broker {
     host_addr: String,
     subbed_tx: mpsc::Sender<()>,
     subbed_rx: Arc<RwLock<mpsc::Receiver<()>>>,
-    pods: Arc<DashMap<String, String>>,
+    pods: Arc<DashMap<String, PodServices>>,
+    compression: Option<Encoding>,
+    cluster: Option<Arc<ClusterConfig>>,
 }
 
 impl Broker {
@@ -171,6 +348,10 @@ impl Broker {
             pubsub.subscribe("services")?;
         }
         conn.rpush("hosts", &host_addr)?;
+        // Compression used for requests delegated to other nodes.
+        let compression = Encoding::from_name(&cfg.get_str("broker.compression")?)?;
+        // Delegated requests go over mutual TLS once a cluster CA is configured.
+        let cluster = ClusterConfig::from_config(&cfg)?.map(Arc::new);
         Ok(Self {
             host_addr,
             client,
@@ -179,6 +360,8 @@ impl Broker {
             subbed_rx: Arc::new(RwLock::new(subbed_rx)),
             subbed_tx,
             pods: Arc::new(DashMap::new()),
+            compression,
+            cluster,
         })
     }
 
@@ -193,6 +376,15 @@ impl Broker {
             .contains(&format!("service_{}_{}", service_name, method_name))
     }
 
+    /// Whether a subscribed method streams in both directions.
+    pub fn is_bidi(&self, service_name: &String, method_name: &String) -> bool {
+        let name = format!("{}_{}", service_name, method_name);
+        self.method_fields_map
+            .get(&name)
+            .map(|message| message.bidi)
+            .unwrap_or_default()
+    }
+
     pub fn probe_cache(
         &self,
         service_name: &String,
@@ -229,9 +421,45 @@ impl Broker {
             let value = serde_json::to_vec(message.value())?;
             conn.set(key, value)?;
         }
+        {
+            let key = format!("descriptors_{}", name);
+            let mut files = Vec::with_capacity(service.descriptors.len());
+            for file in service.descriptors.iter() {
+                files.push(protobuf::Message::write_to_bytes(file)?);
+            }
+            conn.set(key, serde_json::to_vec(&files)?)?;
+        }
         Ok(())
     }
 
+    /// File descriptors of every service published to the cluster, by service name.
+    pub async fn service_descriptors(
+        &self,
+    ) -> ServiceResult<Vec<(String, Vec<FileDescriptorProto>)>> {
+        use redis::AsyncCommands;
+        let mut conn = self.client.get_async_connection().await?;
+        // SCAN, unlike KEYS, doesn't hold up Redis while every key is walked.
+        let mut keys: Vec<String> = Vec::new();
+        {
+            let mut iter: redis::AsyncIter<String> = conn.scan_match("descriptors_*").await?;
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+        }
+        let mut out = Vec::with_capacity(keys.len());
+        for key in keys {
+            let rv: Vec<u8> = conn.get(&key).await?;
+            let files: Vec<Vec<u8>> = serde_json::from_slice(&rv[..])?;
+            let mut descriptors = Vec::with_capacity(files.len());
+            for file in files {
+                descriptors.push(protobuf::Message::parse_from_bytes(&file[..])?);
+            }
+            let name = key.trim_start_matches("descriptors_").to_string();
+            out.push((name, descriptors));
+        }
+        Ok(out)
+    }
+
     pub async fn remove_service(&self, name: &String, pod_name: &String) -> ServiceResult<()> {
         use redis::AsyncCommands;
         let mut conn = self.client.get_async_connection().await?;
@@ -243,15 +471,34 @@ impl Broker {
             let key = format!("host_{}", name);
             conn.del(key).await?;
         }
+        {
+            let key = format!("descriptors_{}", name);
+            conn.del(key).await?;
+        }
         Ok(())
     }
 
+    /// Whether another node has published a service under this name.
+    pub async fn is_served_elsewhere(&self, name: &String) -> ServiceResult<bool> {
+        use redis::AsyncCommands;
+        let mut conn = self.client.get_async_connection().await?;
+        let host: Option<String> = conn.get(format!("host_{}", name)).await?;
+        // A host of this node is left over from before a restart.
+        Ok(host.map_or(false, |host| host != self.host_addr))
+    }
+
     pub fn get_remote_sender(&self, service_name: &String) -> ServiceResult<RemoteSender> {
         let mut conn = self.client.get_connection()?;
-        let addr: String = conn.get(format!("host_{}", service_name))?;
+        let addr: Option<String> = conn.get(format!("host_{}", service_name))?;
+        let addr = addr.ok_or(ServiceError::Unimplemented(format!(
+            "unknown service: {}",
+            service_name
+        )))?;
         Ok(RemoteSender {
             addr,
             method_fields_map: self.method_fields_map.clone(),
+            compression: self.compression,
+            cluster: self.cluster.clone(),
         })
     }
 
@@ -282,22 +529,23 @@ impl Broker {
         );
         let message = {
             let parents = {
-                let keys: Vec<String> = conn.keys(format!("message_{}_*", service_name)).await?;
+                let prefix = format!("message_{}_", service_name);
+                let keys: Vec<String> = conn.keys(format!("{}*", prefix)).await?;
                 let out = Arc::new(DashMap::<String, Message>::new());
                 for key in keys {
                     let rv: Vec<u8> = conn.get(&key).await?;
                     let mut message: Message = serde_json::from_slice(&rv[..])?;
                     message.parent = out.clone();
+                    // Full names may contain underscores, so only the prefix is stripped.
                     let key = key
-                        .rsplit("_")
-                        .next()
-                        .ok_or("could not parse message name")?;
+                        .strip_prefix(&prefix)
+                        .ok_or(ServiceError::new("could not parse message name"))?;
                     out.insert(key.to_string(), message);
                 }
                 out
             };
             let val = parents.get(&method.output_message);
-            let val = val.ok_or("no output message")?;
+            let val = val.ok_or(ServiceError::new("no output message"))?;
             val.value().clone()
         };
 
@@ -318,6 +566,7 @@ impl Broker {
                     .to_owned()
                     .ok_or(ServiceError::new(format!("no primary key").as_str()))?,
                 fields_for_key: Arc::new(DashMap::new()),
+                bidi: method.client_streaming && method.server_streaming,
             },
         );
         self.subbed.insert(sub_name.clone());
@@ -391,8 +640,8 @@ impl Broker {
             }
             match value {
                 Value::Message(fields) => {
-                    let message_type = field_proto.descriptor.get_type_name().to_string();
-                    let cached = cached.parent.get(&message_type).ok_or(ServiceError::new(
+                    let message_type = field_proto.descriptor.get_type_name();
+                    let cached = cached.message_type(message_type).ok_or(ServiceError::new(
                         format!("no message found for type: {}", entry.key()).as_str(),
                     ))?;
                     map.insert(
@@ -456,10 +705,19 @@ impl Broker {
         Ok(())
     }
 
-    pub fn add_pod_to_watch(&self, pod_name: &String, service_name: &String) {
-        if !self.pods.contains_key(pod_name) {
+    /// Watches a pod, `request` naming the saved request its services are started from.
+    /// Watches a pod for the services started from `request`. A saved request is watched
+    /// before its services are parsed, so an empty list is filled in once they are known.
+    pub fn add_pod_to_watch(&self, pod_name: &String, request: &String, services: &[String]) {
+        let mut watched = self.pods.entry(pod_name.clone()).or_insert_with(|| {
             log::info!("k8s: now watching pod '{}'", pod_name);
-            self.pods.insert(pod_name.clone(), service_name.clone());
+            PodServices {
+                request: request.clone(),
+                services: Vec::new(),
+            }
+        });
+        if watched.services.is_empty() {
+            watched.services = services.to_vec();
         }
     }
 
@@ -486,8 +744,8 @@ impl Broker {
                     return Ok(());
                 }
                 // log::warn!("k8s: change detected in pod '{:?}'", &p);
-                let service = pods.get(name).unwrap();
-                let spec = p.spec.ok_or("no pod spec").unwrap();
+                let watched = pods.get(name).unwrap().value().clone();
+                let spec = p.spec.ok_or(ServiceError::new("no pod spec")).unwrap();
                 let pod_node = match spec.node_name {
                     Some(v) => v,
                     None => return Ok(()),
@@ -499,19 +757,27 @@ impl Broker {
                 if phase == "Failed" || phase == "Succeeded" || deletion.is_some()  {
                     // Remove from broker and server
                     log::warn!(
-                        "k8s: pod '{}', linked to service '{}' has been removed/evicted, announcing global listen...",
+                        "k8s: pod '{}', linked to services {:?} has been removed/evicted, announcing global listen...",
                         name,
-                        service.value()
+                        watched.services
                     );
-                    self.remove_service(service.value(), name).await.unwrap();
-                    {
-                        server.remove_service(name).await;
+                    for service in watched.services.iter() {
+                        self.remove_service(service, name).await.unwrap();
+                        server.remove_service(service).await;
                     }
                 } else if current_node == pod_node && phase == "Running" && status.pod_ip.is_some() {
-                    log::warn!("k8s: pod '{}' is now on this node, adding service '{}'", name, service.value());
+                    // Status updates of a pod already served here need nothing more.
+                    let mut running = !watched.services.is_empty();
+                    for service in watched.services.iter() {
+                        running &= server.has_service(service).await;
+                    }
+                    if running {
+                        return Ok(());
+                    }
+                    log::warn!("k8s: pod '{}' is now on this node, adding services {:?}", name, watched.services);
                     // Add service on this node
                     {
-                        let mut path = current_dir().unwrap().join(service.value());
+                        let mut path = current_dir().unwrap().join(&watched.request);
                         path.set_extension("pandit_service");
                         let client = {
                             let env = Arc::new(EnvBuilder::new().build());
@@ -563,6 +829,7 @@ impl Broker {
     fn set_default(cfg: &mut config::Config) -> ServiceResult<()> {
         cfg.set_default("redis.address", "redis://127.0.0.1/".to_string())?;
         cfg.set_default("cluster.addresses", vec!["redis://127.0.0.1/"])?;
+        cfg.set_default("broker.compression", "identity".to_string())?;
         Ok(())
     }
 }
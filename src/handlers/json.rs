@@ -7,7 +7,7 @@ use crate::services::format::http;
 use access_json::JSONQuery;
 use async_trait::async_trait;
 
-use crate::services::{Fields, Handler, ServiceError, ServiceResult};
+use crate::services::{value::Value, Fields, FieldsMap, Handler, ServiceError, ServiceResult};
 
 pub struct JsonHandler {
     prog: JSONQuery,
@@ -27,13 +27,56 @@ impl Handler for JsonHandler {
         use bytes::Buf;
         log::info!("{}", String::from_utf8(buf.to_vec()).unwrap());
         let json: serde_json::Value = serde_json::from_reader(buf.reader())?;
-        let pr = self.prog.execute(&json)?;
+        let pr = self
+            .prog
+            .execute(&json)
+            .map_err(|err| ServiceError::new(&err.to_string()))?;
         let result = pr.ok_or(ServiceError::new("no result"))?;
         Ok(serde_json::value::from_value(result)?)
     }
 
+    /// A response without content, such as a 204, decodes to an empty message.
+    fn from_response(
+        &self,
+        status: Option<::http::StatusCode>,
+        _headers: Option<&::http::HeaderMap>,
+        buf: bytes::Bytes,
+    ) -> ServiceResult<Fields> {
+        if buf.is_empty() || status == Some(::http::StatusCode::NO_CONTENT) {
+            return Ok(Fields::new(FieldsMap::new()));
+        }
+        self.from_payload(buf)
+    }
+
+    fn from_payload_stream(&self, buf: bytes::Bytes) -> ServiceResult<Vec<Fields>> {
+        use bytes::Buf;
+        let json: serde_json::Value = match serde_json::from_reader(buf.clone().reader()) {
+            Ok(json) => json,
+            Err(_) => {
+                // Newline delimited JSON, one message per line.
+                return buf
+                    .split(|byte| *byte == b'\n')
+                    .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+                    .map(|line| self.from_payload(bytes::Bytes::copy_from_slice(line)))
+                    .collect();
+            }
+        };
+        let pr = self
+            .prog
+            .execute(&json)
+            .map_err(|err| ServiceError::new(&err.to_string()))?;
+        let result = pr.ok_or(ServiceError::new("no result"))?;
+        match result {
+            serde_json::Value::Array(items) => items
+                .into_iter()
+                .map(|item| Ok(serde_json::value::from_value(item)?))
+                .collect(),
+            result => Ok(vec![serde_json::value::from_value(result)?]),
+        }
+    }
+
     async fn to_payload(&self, fields: &Fields) -> ServiceResult<bytes::Bytes> {
-        match serde_json::to_vec(fields) {
+        match serde_json::to_vec(&to_json(fields)?) {
             Ok(data) => Ok(bytes::Bytes::from_iter(data)),
 
             Err(e) => Err(ServiceError::new(
@@ -41,4 +84,115 @@ impl Handler for JsonHandler {
             )),
         }
     }
+
+    async fn to_payload_batch(&self, fields: &[Fields]) -> ServiceResult<bytes::Bytes> {
+        let fields = fields
+            .iter()
+            .map(to_json)
+            .collect::<ServiceResult<Vec<_>>>()?;
+        match serde_json::to_vec(&fields) {
+            Ok(data) => Ok(bytes::Bytes::from_iter(data)),
+
+            Err(e) => Err(ServiceError::new(
+                format!("to_payload_batch json failed: {}", e.to_string()).as_str(),
+            )),
+        }
+    }
+}
+
+/// Converts fields to JSON, writing the member set in a oneof as an object tagged with the
+/// member name, `{"result": {"ok": ...}}`.
+fn to_json(fields: &Fields) -> ServiceResult<serde_json::Value> {
+    let mut object = serde_json::Map::with_capacity(fields.map.len());
+    for kv in fields.map.iter() {
+        let value = match kv.value() {
+            Some(value) => value_to_json(value)?,
+            None => continue,
+        };
+        let oneof = fields.oneofs.iter().find(|oneof| oneof.value() == kv.key());
+        match oneof {
+            Some(oneof) => {
+                let mut tagged = serde_json::Map::with_capacity(1);
+                tagged.insert(kv.key().clone(), value);
+                object.insert(oneof.key().clone(), serde_json::Value::Object(tagged));
+            }
+            None => {
+                object.insert(kv.key().clone(), value);
+            }
+        }
+    }
+    Ok(serde_json::Value::Object(object))
+}
+
+fn value_to_json(value: &Value) -> ServiceResult<serde_json::Value> {
+    Ok(match value {
+        Value::Message(fields) => to_json(fields)?,
+        Value::Array(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .map(value_to_json)
+                .collect::<ServiceResult<_>>()?,
+        ),
+        value => serde_json::to_value(value)?,
+    })
+}
+
+mod tests {
+    #[test]
+    fn test_from_payload_stream() {
+        use super::*;
+        let handler = JsonHandler::new(".items".to_string());
+        let fields = handler
+            .from_payload_stream(bytes::Bytes::from_static(
+                b"{\"items\": [{\"id\": 1}, {\"id\": 2}]}",
+            ))
+            .unwrap();
+        assert_eq!(fields.len(), 2);
+
+        let handler = JsonHandler::new(".".to_string());
+        let fields = handler
+            .from_payload_stream(bytes::Bytes::from_static(b"{\"id\": 1}\n{\"id\": 2}\n"))
+            .unwrap();
+        assert_eq!(fields.len(), 2);
+    }
+
+    #[test]
+    fn test_from_response() {
+        use super::*;
+        let handler = JsonHandler::new(".".to_string());
+        let fields = handler
+            .from_response(
+                Some(::http::StatusCode::NO_CONTENT),
+                None,
+                bytes::Bytes::new(),
+            )
+            .unwrap();
+        assert!(fields.map.is_empty());
+        let fields = handler
+            .from_response(
+                Some(::http::StatusCode::OK),
+                None,
+                bytes::Bytes::from_static(b"{\"id\": 1}"),
+            )
+            .unwrap();
+        assert!(fields.map.contains_key("id"));
+    }
+
+    #[tokio::test]
+    async fn test_to_payload_oneof() {
+        use super::*;
+        let fields = Fields::new(Default::default());
+        fields
+            .map
+            .insert("id".to_string(), Some(Value::from_int(1)));
+        fields.map.insert(
+            "ok".to_string(),
+            Some(Value::from_string("yes".to_string())),
+        );
+        fields.oneofs.insert("result".to_string(), "ok".to_string());
+        let handler = JsonHandler::new(".".to_string());
+        let payload = handler.to_payload(&fields).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&payload[..]).unwrap();
+        assert_eq!(json, serde_json::json!({"id": 1, "result": {"ok": "yes"}}));
+    }
 }
@@ -22,8 +22,9 @@ use tokio_postgres::{self, Client};
 use crate::{
     proto,
     services::{
-        message::{Field, Message},
-        value::Value,
+        message::{find_message, Field, Message},
+        value::{Floating, Integer, Value},
+        well_known::{self, WellKnown},
         FieldsMap, Handler, ServiceError,
     },
 };
@@ -53,8 +54,10 @@ impl Value {
         match self {
             Value::String(v) => sea_query::Value::String(Some(Box::new(v))),
             Value::Bytes(v) => sea_query::Value::Bytes(Some(Box::new(v))),
-            Value::Int(v) => sea_query::Value::Int(Some(v.to_i32())),
-            Value::Float(v) => sea_query::Value::Float(Some(v.to_f32())),
+            Value::Int(Integer::Signed(v)) => sea_query::Value::BigInt(Some(v)),
+            Value::Int(Integer::Unsigned(v)) => sea_query::Value::BigUnsigned(Some(v)),
+            Value::Float(Floating::F32(v)) => sea_query::Value::Float(Some(v)),
+            Value::Float(Floating::F64(v)) => sea_query::Value::Double(Some(v)),
             Value::Bool(v) => sea_query::Value::Bool(Some(v)),
             Value::Enum(v) => sea_query::Value::Int(Some(protobuf::ProtobufEnum::value(&v))),
             Value::Array(vals) => {
@@ -65,6 +68,7 @@ impl Value {
                 sea_query::Value::Array(Some(Box::new(out)))
             }
             Value::Message(v) => sea_query::Value::Int(None),
+            Value::Map(v) => sea_query::Value::Json(serde_json::to_value(v).ok().map(Box::new)),
             Value::None => sea_query::Value::Int(None),
         }
     }
@@ -109,6 +113,26 @@ impl SQLHandler {
     }
 }
 
+/// Seconds from the Unix epoch to 2000-01-01, which Postgres counts timestamps from.
+const POSTGRES_EPOCH: i64 = 946_684_800;
+
+/// Whether a field's message type is a map entry.
+fn is_map(messages: &DashMap<String, Message>, type_name: &str) -> bool {
+    find_message(messages, type_name).map_or(false, |message| message.is_map_entry())
+}
+
+/// Whether a field's message type is stored as a `jsonb` column: maps, and well-known types
+/// mapped to free-form JSON.
+fn is_json(messages: &DashMap<String, Message>, type_name: &str) -> bool {
+    WellKnown::from_type_name(type_name).map_or(false, |kind| kind.is_json())
+        || is_map(messages, type_name)
+}
+
+/// Encodes a value as `jsonb`, such as a map field arriving as an object from a JSON backend.
+fn json_value<T: Serialize>(value: &T) -> sea_query::Value {
+    sea_query::Value::Json(serde_json::to_value(value).ok().map(Box::new))
+}
+
 macro_rules! handle_err {
     ($value:expr) => {
         match $value {
@@ -122,6 +146,82 @@ macro_rules! handle_err {
     };
 }
 
+/// Decodes the column of a scalar field.
+fn scalar_from_sql(
+    field_type: protobuf::descriptor::field_descriptor_proto::Type,
+    raw: &[u8],
+) -> ServiceResult<Value> {
+    use protobuf::descriptor::field_descriptor_proto::Type::*;
+    Ok(match field_type {
+        TYPE_DOUBLE => Value::from_float(handle_err!(<f64>::from_sql(&Type::FLOAT8, raw))),
+        TYPE_FLOAT => Value::from_float(handle_err!(<f32>::from_sql(&Type::FLOAT4, raw))),
+        TYPE_BOOL => Value::Bool(handle_err!(<bool>::from_sql(&Type::BOOL, raw))),
+        TYPE_STRING => Value::from_string(handle_err!(<String>::from_sql(&Type::TEXT, raw))),
+        TYPE_BYTES => Value::Bytes(handle_err!(<Vec<u8>>::from_sql(&Type::BYTEA, raw))),
+        TYPE_INT64 | TYPE_UINT64 | TYPE_INT32 | TYPE_UINT32 | TYPE_FIXED64 | TYPE_FIXED32
+        | TYPE_SFIXED32 | TYPE_SFIXED64 | TYPE_SINT32 | TYPE_SINT64 | TYPE_ENUM => {
+            Value::from_int(handle_err!(<i64>::from_sql(&Type::INT8, raw)))
+        }
+        _ => return Err(ServiceError::new("unsupported proto field type")),
+    })
+}
+
+fn json_from_sql(raw: &[u8]) -> ServiceResult<serde_json::Value> {
+    Ok(handle_err!(<serde_json::Value>::from_sql(
+        &Type::JSONB,
+        raw
+    )))
+}
+
+/// Decodes the column of a well-known type into its JSON mapping: `timestamptz` for a
+/// Timestamp, `interval` for a Duration, the wrapped type for wrappers and `jsonb` otherwise.
+fn well_known_from_sql(
+    messages: &DashMap<String, Message>,
+    kind: WellKnown,
+    type_name: &str,
+    raw: &[u8],
+) -> ServiceResult<Value> {
+    Ok(match kind {
+        WellKnown::Timestamp => {
+            let micros = handle_err!(postgres_protocol::types::timestamp_from_sql(raw));
+            let seconds = POSTGRES_EPOCH + micros.div_euclid(1_000_000);
+            let nanos = micros.rem_euclid(1_000_000) * 1000;
+            Value::String(well_known::format_timestamp(seconds, nanos as i32)?)
+        }
+        WellKnown::Duration => {
+            let micros = interval_from_sql(raw)?;
+            let nanos = micros % 1_000_000 * 1000;
+            Value::String(well_known::format_duration(
+                micros / 1_000_000,
+                nanos as i32,
+            ))
+        }
+        WellKnown::Wrapper => {
+            let message =
+                find_message(messages, type_name).ok_or(ServiceError::new("no message found"))?;
+            let field = message
+                .fields_by_name
+                .get("value")
+                .ok_or(ServiceError::new("no field"))?;
+            scalar_from_sql(field.descriptor.get_field_type(), raw)?
+        }
+        _ => serde_json::from_value(json_from_sql(raw)?)?,
+    })
+}
+
+/// Reads an `interval` as microseconds.
+fn interval_from_sql(raw: &[u8]) -> ServiceResult<i64> {
+    use std::convert::TryInto;
+    if raw.len() != 16 {
+        return Err(ServiceError::new("invalid interval"));
+    }
+    let micros = i64::from_be_bytes(raw[0..8].try_into()?);
+    let days = i64::from(i32::from_be_bytes(raw[8..12].try_into()?));
+    let months = i64::from(i32::from_be_bytes(raw[12..16].try_into()?));
+    // Postgres itself counts a month as 30 days when justifying intervals.
+    Ok(micros + (days + months * 30) * 86_400_000_000)
+}
+
 #[async_trait]
 impl Handler for SQLHandler {
     fn from_payload(&self, buf: bytes::Bytes) -> ServiceResult<Fields> {
@@ -129,17 +229,17 @@ impl Handler for SQLHandler {
         let buf = &buf.to_vec()[..];
         let rows: Vec<(String, HashMap<String, SQLValue>)> = serde_json::from_slice(buf)?;
 
-        // Not all queries return data.
-        if self.output_message == "Empty" {
-            return Ok(Fields::new(Default::default()));
-        }
-
         let message = {
             self.messages
                 .get(&self.output_message)
-                .ok_or("output message not found")?
+                .ok_or(ServiceError::new("output message not found"))?
         };
 
+        // Not all queries return data.
+        if message.fields_by_name.is_empty() {
+            return Ok(Fields::new(Default::default()));
+        }
+
         let mut main_table: String = Default::default();
         let mut table_fields_map = HashMap::<String, Fields>::with_capacity(rows.len());
         let mut wanted_table_refs = HashMap::<String, (String, String)>::new();
@@ -147,43 +247,37 @@ impl Handler for SQLHandler {
         for (table_name, map) in rows {
             let fields = FieldsMap::default();
             for (name, value) in map {
-                let value = match message
+                let field_type = message
                     .fields_by_name
                     .get(&name)
-                    .ok_or("no field")?
+                    .ok_or(ServiceError::new("no field"))?
                     .descriptor
-                    .get_field_type()
-                {
-                    TYPE_DOUBLE => {
-                        Value::from_float(handle_err!(<f64>::from_sql(&Type::FLOAT8, &value.0[..])))
-                    }
-                    TYPE_FLOAT => {
-                        Value::from_float(handle_err!(<f32>::from_sql(&Type::FLOAT4, &value.0[..])))
-                    }
-                    TYPE_BOOL => {
-                        Value::Bool(handle_err!(<bool>::from_sql(&Type::BOOL, &value.0[..])))
-                    }
-                    TYPE_STRING => Value::from_string(handle_err!(<String>::from_sql(
-                        &Type::TEXT,
-                        &value.0[..]
-                    ))),
-                    TYPE_BYTES => {
-                        Value::Bytes(handle_err!(<Vec<u8>>::from_sql(&Type::BYTEA, &value.0[..])))
-                    }
+                    .get_field_type();
+                let value = match field_type {
                     TYPE_MESSAGE => {
                         let other_table = message.fields_by_name.get(&name);
-                        let other_table_name = other_table.ok_or("no field")?;
+                        let other_table_name = other_table.ok_or(ServiceError::new("no field"))?;
                         let other_table_name = other_table_name.descriptor.get_type_name();
-                        wanted_table_refs
-                            .insert(table_name.clone(), (name, other_table_name.to_string()));
-                        continue;
-                    }
-                    TYPE_INT64 | TYPE_UINT64 | TYPE_INT32 | TYPE_UINT32 | TYPE_FIXED64
-                    | TYPE_FIXED32 | TYPE_SFIXED32 | TYPE_SFIXED64 | TYPE_SINT32 | TYPE_SINT64
-                    | TYPE_ENUM => {
-                        Value::from_int(handle_err!(<i64>::from_sql(&Type::INT8, &value.0[..])))
+                        match WellKnown::from_type_name(other_table_name) {
+                            Some(kind) => well_known_from_sql(
+                                &self.messages,
+                                kind,
+                                other_table_name,
+                                &value.0[..],
+                            )?,
+                            None if is_map(&self.messages, other_table_name) => {
+                                Value::Map(serde_json::from_value(json_from_sql(&value.0[..])?)?)
+                            }
+                            None => {
+                                wanted_table_refs.insert(
+                                    table_name.clone(),
+                                    (name, other_table_name.to_string()),
+                                );
+                                continue;
+                            }
+                        }
                     }
-                    _ => return Err(ServiceError::new("unsupported proto field type")),
+                    field_type => scalar_from_sql(field_type, &value.0[..])?,
                 };
                 fields.insert(name, Some(value));
             }
@@ -194,14 +288,14 @@ impl Handler for SQLHandler {
         for (table, (other_field_name, other_table_name)) in wanted_table_refs {
             let other_fields = table_fields_map
                 .get(&other_table_name)
-                .ok_or("other table")?;
-            let fields = table_fields_map.get(&table).ok_or("table")?;
+                .ok_or(ServiceError::new("other table"))?;
+            let fields = table_fields_map.get(&table).ok_or(ServiceError::new("table"))?;
             fields
                 .map
                 .insert(other_field_name, Some(Value::Message(other_fields.clone())));
         }
 
-        let fields = table_fields_map.get(&main_table).ok_or("table")?;
+        let fields = table_fields_map.get(&main_table).ok_or(ServiceError::new("table"))?;
         Ok(fields.clone())
     }
 
@@ -210,14 +304,124 @@ impl Handler for SQLHandler {
         let message = {
             self.messages
                 .get(&self.input_message)
-                .ok_or("no input message")?
+                .ok_or(ServiceError::new("no input message"))?
         };
         self._to_payload(message, &mut cmds, fields)?;
         Ok(bytes::Bytes::from(serde_json::to_string(&cmds)?))
     }
+
+    async fn to_payload_batch(&self, fields: &[Fields]) -> ServiceResult<bytes::Bytes> {
+        let mut cmds = Vec::<(String, String)>::with_capacity(fields.len());
+        match self.opts.command.enum_value().unwrap_or_default() {
+            PostgresCommand::INSERT => {
+                let message = {
+                    self.messages
+                        .get(&self.input_message)
+                        .ok_or(ServiceError::new("no input message"))?
+                };
+                self._to_payload_batch_insert(message, &mut cmds, fields)?;
+            }
+            _ => {
+                for fields in fields {
+                    let message = {
+                        self.messages
+                            .get(&self.input_message)
+                            .ok_or(ServiceError::new("no input message"))?
+                    };
+                    self._to_payload(message, &mut cmds, fields)?;
+                }
+            }
+        }
+        Ok(bytes::Bytes::from(serde_json::to_string(&cmds)?))
+    }
 }
 
 impl SQLHandler {
+    /// Inserts every set of fields as a row, with one INSERT statement per set of columns
+    /// the rows have so that columns a row leaves out keep their default.
+    fn _to_payload_batch_insert(
+        &self,
+        message: Ref<String, Message>,
+        cmds: &mut Vec<(String, String)>,
+        rows: &[Fields],
+    ) -> ServiceResult<()> {
+        // Columns follow the message declaration so every row lines up.
+        let declared: Vec<Field> = message
+            .message
+            .field
+            .iter()
+            .filter_map(|field| message.fields_by_name.get(field.get_name()))
+            .map(|field| field.value().clone())
+            .collect();
+        let mut groups: Vec<(Vec<bool>, Vec<&Fields>)> = Vec::new();
+        for fields in rows {
+            let set: Vec<bool> = declared
+                .iter()
+                .map(|col| fields.map.contains_key(col.descriptor.get_name()))
+                .collect();
+            match groups.iter_mut().find(|(cols, _)| *cols == set) {
+                Some((_, group)) => group.push(fields),
+                None => groups.push((set, vec![fields])),
+            }
+        }
+        for (set, rows) in groups {
+            let cols: Vec<Field> = declared
+                .iter()
+                .zip(set.iter())
+                .filter(|(_, set)| **set)
+                .map(|(col, _)| col.clone())
+                .collect();
+            self._to_payload_insert_rows(&message, cmds, cols, &rows[..])?;
+        }
+        Ok(())
+    }
+
+    /// Inserts rows which all set the same columns in a single INSERT statement.
+    fn _to_payload_insert_rows(
+        &self,
+        message: &Ref<String, Message>,
+        cmds: &mut Vec<(String, String)>,
+        cols: Vec<Field>,
+        rows: &[&Fields],
+    ) -> ServiceResult<()> {
+        let mut query = Query::insert();
+        query
+            .into_table(message.value().clone())
+            .columns(cols.clone());
+        for fields in rows {
+            let mut vals = Vec::with_capacity(cols.len());
+            for col in cols.iter() {
+                let value = fields
+                    .map
+                    .get(col.descriptor.get_name())
+                    .and_then(|entry| entry.value().clone());
+                vals.push(match value {
+                    Some(value) if is_json(&self.messages, col.descriptor.get_type_name()) => {
+                        json_value(&value)
+                    }
+                    Some(Value::Message(other_fields)) => {
+                        let message_name = col.descriptor.get_type_name().to_string();
+                        let other_message = self
+                            .messages
+                            .get(&message_name)
+                            .ok_or(ServiceError::new("no message found"))?;
+                        self._to_payload(other_message, cmds, &other_fields)?
+                    }
+                    Some(value) => value.into_value(),
+                    None => sea_query::Value::Int(None),
+                });
+            }
+            query
+                .values(vals)
+                .map_err(|err| ServiceError::new(&err.to_string()))?;
+        }
+        cmds.push((
+            message.key().clone(),
+            query.to_string(sea_query::PostgresQueryBuilder),
+        ));
+        Ok(())
+    }
+
     fn _to_payload(
         &self,
         message: Ref<String, Message>,
@@ -228,28 +432,26 @@ impl SQLHandler {
         let mut cols = Vec::<Field>::with_capacity(fields.map.len());
         let mut primary_key: Option<sea_query::Value> = None;
         for entry in fields.map.iter() {
+            let col = {
+                let m = message.fields_by_name.get(entry.key());
+                let m = m.ok_or(ServiceError::new("no field error"))?;
+                m.value().clone()
+            };
+            let message_name = col.descriptor.get_type_name().to_string();
             vals.push(match entry.value() {
+                Some(value) if is_json(&self.messages, &message_name) => json_value(value),
                 Some(value) => match value {
                     Value::Message(other_fields) => {
-                        let field = {
-                            let m = message.fields_by_name.get(entry.key());
-                            let m = m.ok_or("no field error")?;
-                            m.value().clone()
-                        };
-                        let message_name = field.descriptor.get_type_name().to_string();
-                        let other_message =
-                            self.messages.get(&message_name).ok_or("no message found")?;
+                        let other_message = self
+                            .messages
+                            .get(&message_name)
+                            .ok_or(ServiceError::new("no message found"))?;
                         self._to_payload(other_message, cmds, other_fields)?
                     }
                     _ => value.clone().into_value(),
                 },
                 None => sea_query::Value::Int(None),
             });
-            let col = {
-                let m = message.fields_by_name.get(entry.key());
-                let m = m.ok_or("no field error")?;
-                m.value().clone()
-            };
             if match postgres_field.get(col.descriptor.options.as_ref().unwrap_or_default()) {
                 Some(field_opts) => field_opts.key,
                 None => false,
@@ -268,7 +470,8 @@ impl SQLHandler {
                 let query = query
                     .into_table(message.value().clone())
                     .columns(cols)
-                    .values(vals)?;
+                    .values(vals)
+                    .map_err(|err| ServiceError::new(&err.to_string()))?;
                 cmds.push((
                     message.key().clone(),
                     query.to_string(sea_query::PostgresQueryBuilder),
@@ -483,4 +686,100 @@ mod tests {
         assert_eq!(table, "input_message");
         assert_eq!(query, "INSERT INTO \"\" (\"str\") VALUES ('str_value')");
     }
+
+    #[tokio::test]
+    async fn test_to_payload_batch_insert() {
+        use super::*;
+        let messages = Arc::new(DashMap::new());
+        {
+            let mut s = protobuf::descriptor::FieldDescriptorProto::new();
+            s.set_name("str".to_string());
+            s.set_field_type(protobuf::descriptor::field_descriptor_proto::Type::TYPE_STRING);
+            let mut message = protobuf::descriptor::DescriptorProto::new();
+            message.field.push(s);
+            messages.insert(
+                "input_message".to_string(),
+                Message::new(message, "".to_string(), Arc::new(Default::default())),
+            );
+        }
+        let opts = Postgres {
+            command: ProtobufEnumOrUnknown::new(PostgresCommand::INSERT),
+            unknown_fields: Default::default(),
+            cached_size: Default::default(),
+        };
+        let handler = SQLHandler::new(
+            messages,
+            "input_message".to_string(),
+            Default::default(),
+            opts,
+        );
+        let rows: Vec<Fields> = ["a", "b"]
+            .iter()
+            .map(|v| {
+                let fields = FieldsMap::new();
+                fields.insert("str".to_string(), Some(Value::String(v.to_string())));
+                Fields::new(fields)
+            })
+            .collect();
+        let output = handler.to_payload_batch(&rows[..]).await.unwrap();
+        use bytes::Buf;
+        let queries: Vec<(String, String)> = serde_json::from_reader(output.reader()).unwrap();
+        assert_eq!(queries.len(), 1);
+        let (table, query) = &queries[0];
+        assert_eq!(table, "input_message");
+        assert_eq!(query, "INSERT INTO \"\" (\"str\") VALUES ('a'), ('b')");
+    }
+
+    #[tokio::test]
+    async fn test_to_payload_batch_insert_absent_columns() {
+        use super::*;
+        let messages = Arc::new(DashMap::new());
+        {
+            let mut message = protobuf::descriptor::DescriptorProto::new();
+            for name in ["str", "other"].iter() {
+                let mut s = protobuf::descriptor::FieldDescriptorProto::new();
+                s.set_name(name.to_string());
+                s.set_field_type(protobuf::descriptor::field_descriptor_proto::Type::TYPE_STRING);
+                message.field.push(s);
+            }
+            messages.insert(
+                "input_message".to_string(),
+                Message::new(message, "".to_string(), Arc::new(Default::default())),
+            );
+        }
+        let opts = Postgres {
+            command: ProtobufEnumOrUnknown::new(PostgresCommand::INSERT),
+            unknown_fields: Default::default(),
+            cached_size: Default::default(),
+        };
+        let handler = SQLHandler::new(
+            messages,
+            "input_message".to_string(),
+            Default::default(),
+            opts,
+        );
+        let rows: Vec<Fields> = [("a", Some("x")), ("b", None), ("c", Some("y"))]
+            .iter()
+            .map(|(v, other)| {
+                let fields = FieldsMap::new();
+                fields.insert("str".to_string(), Some(Value::String(v.to_string())));
+                if let Some(other) = other {
+                    fields.insert("other".to_string(), Some(Value::String(other.to_string())));
+                }
+                Fields::new(fields)
+            })
+            .collect();
+        let output = handler.to_payload_batch(&rows[..]).await.unwrap();
+        use bytes::Buf;
+        let queries: Vec<(String, String)> = serde_json::from_reader(output.reader()).unwrap();
+        let queries: Vec<&str> = queries.iter().map(|(_, query)| query.as_str()).collect();
+        // The row without "other" leaves it to the column default rather than NULL.
+        assert_eq!(
+            queries,
+            vec![
+                "INSERT INTO \"\" (\"str\", \"other\") VALUES ('a', 'x'), ('c', 'y')",
+                "INSERT INTO \"\" (\"str\") VALUES ('b')",
+            ]
+        );
+    }
 }
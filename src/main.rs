@@ -28,6 +28,7 @@ use log::Record;
 use std::convert::TryInto;
 use std::env::current_dir;
 use std::fs::read_dir;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio;
@@ -43,8 +44,11 @@ use crate::api::DockerNetworkRuntime;
 use crate::api::K8sHandler;
 use crate::api::NetworkRuntime;
 use crate::broker::Broker;
+use crate::server::tls::TlsConfig;
 use crate::server::IntraServer;
+use crate::server::web;
 use crate::server::Server;
+use crate::services::template::EnvAllowList;
 
 struct SimpleLogger;
 
@@ -188,9 +192,10 @@ async fn main() {
     let broker = Broker::connect(cfg.clone(), address.clone()).unwrap();
     let broker = Arc::new(broker);
 
-    let server_cancel: JoinHandle<()>;
+    let mut server_cancel: JoinHandle<()>;
+    let mut web_cancel: Option<JoinHandle<()>> = None;
     let intra_server = {
-        let server = IntraServer::new(broker.clone());
+        let server = IntraServer::new(broker.clone(), &cfg).unwrap();
         let server = Arc::new(server);
         let addr = cfg
             .get_str("server.address")
@@ -201,6 +206,18 @@ async fn main() {
                 server.run(addr).await.unwrap();
             });
         }
+        let web_addr = cfg
+            .get_str("server.web_address")
+            .unwrap_or(web::DEFAULT_ADDRESS.to_string());
+        if web::enabled(&cfg, &server) {
+            let server = server.clone();
+            let origins = web::AllowedOrigins::from_config(&cfg);
+            web_cancel = Some(threaded_rt.spawn(async move {
+                web::run(server, web_addr, origins).await.unwrap();
+            }));
+        } else {
+            log::info!("gRPC-Web server disabled, set server.web.enabled to serve it without TLS");
+        }
         server
     };
 
@@ -209,20 +226,30 @@ async fn main() {
         intra_server.clone(),
         network_runtime,
         k8s_handler.clone(),
+        EnvAllowList::from_config(&cfg),
+        threaded_rt.handle().clone(),
     ));
 
     let env = Arc::new(Environment::new(1));
     let quota = ResourceQuota::new(Some("ApiServerQuota")).resize_memory(1024 * 1024);
     let ch_builder = ChannelBuilder::new(env.clone()).set_resource_quota(quota);
-    let mut server = ServerBuilder::new(env)
-        .register_service(api_service)
-        .bind(
-            "0.0.0.0",
-            cfg.get_int("admin.port")
-                .unwrap_or(50121)
-                .try_into()
-                .unwrap(),
-        )
+    let admin_port: u16 = cfg
+        .get_int("admin.port")
+        .unwrap_or(50121)
+        .try_into()
+        .unwrap();
+    let builder = ServerBuilder::new(env).register_service(api_service);
+    // The admin API is served with the same certificate and client CA as the gRPC port.
+    let builder = match TlsConfig::from_config(&cfg, "server.tls").unwrap() {
+        Some(tls) => {
+            builder.bind_with_cred("0.0.0.0", admin_port, tls.admin_credentials().unwrap())
+        }
+        None => {
+            log::warn!("server.tls is not set, serving the admin API in plaintext");
+            builder.bind("0.0.0.0", admin_port)
+        }
+    };
+    let mut server = builder
         .channel_args(ch_builder.build_args())
         .build()
         .unwrap();
@@ -248,7 +275,18 @@ async fn main() {
     log::info!("Hit Ctrl-C to quit");
 
     ctrl_c().await.unwrap();
+    // Both servers stop on Ctrl-C too, give their in-flight streams time to finish.
+    let drained = async {
+        let _ = (&mut server_cancel).await;
+        if let Some(web_cancel) = &mut web_cancel {
+            let _ = web_cancel.await;
+        }
+    };
+    let _ = tokio::time::timeout(intra_server.drain_timeout(), drained).await;
     server_cancel.abort();
+    if let Some(web_cancel) = web_cancel {
+        web_cancel.abort();
+    }
 }
 
 async fn start_services(
@@ -260,7 +298,19 @@ async fn start_services(
 ) {
     let addr = format!("0.0.0.0:{}", cfg.get_int("admin.port").unwrap_or(50121));
     let env = Arc::new(EnvBuilder::new().build());
-    let ch = ChannelBuilder::new(env).connect(addr.as_str());
+    let ch = match TlsConfig::from_config(cfg, "server.tls").unwrap() {
+        Some(tls) => {
+            let ca = cfg.get_str("admin.tls.ca").ok().map(PathBuf::from);
+            let creds = tls.admin_client_credentials(ca.as_ref()).unwrap();
+            let server_name = cfg
+                .get_str("admin.tls.server_name")
+                .unwrap_or("localhost".to_string());
+            ChannelBuilder::new(env)
+                .override_ssl_target(server_name)
+                .secure_connect(addr.as_str(), creds)
+        }
+        None => ChannelBuilder::new(env).connect(addr.as_str()),
+    };
     let client = api_proto::api_grpc::ApiClient::new(ch);
     let paths = read_dir(current_dir().unwrap()).unwrap();
 
@@ -27,6 +27,10 @@ const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_0_0_ALPHA_2;
 
 #[derive(PartialEq,Clone,Default)]
 pub struct HTTP {
+    // message fields
+    pub headers: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    pub fields: ::protobuf::ProtobufEnumOrUnknown<HTTPFieldEncoding>,
+    pub success_status: ::std::string::String,
     // message oneof groups
     pub pattern: ::std::option::Option<http::Pattern>,
     // special fields
@@ -292,6 +296,11 @@ impl HTTP {
 
     fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
         let mut fields = ::std::vec::Vec::new();
+        fields.push(::protobuf::reflect::rt::v2::make_map_accessor::<_, ::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(
+            "headers",
+            |m: &HTTP| { &m.headers },
+            |m: &mut HTTP| { &mut m.headers },
+        ));
         fields.push(::protobuf::reflect::rt::v2::make_oneof_deref_has_get_set_simpler_accessor::<_, _>(
             "get",
             HTTP::has_get,
@@ -322,6 +331,16 @@ impl HTTP {
             HTTP::get_patch,
             HTTP::set_patch,
         ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "fields",
+            |m: &HTTP| { &m.fields },
+            |m: &mut HTTP| { &mut m.fields },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "success_status",
+            |m: &HTTP| { &m.success_status },
+            |m: &mut HTTP| { &mut m.success_status },
+        ));
         ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HTTP>(
             "HTTP",
             0,
@@ -339,6 +358,9 @@ impl ::protobuf::Message for HTTP {
         while !is.eof()? {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
+                50000 => {
+                    ::protobuf::rt::read_map_into::<::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(wire_type, is, &mut self.headers)?;
+                },
                 50001 => {
                     if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
@@ -369,6 +391,18 @@ impl ::protobuf::Message for HTTP {
                     }
                     self.pattern = ::std::option::Option::Some(http::Pattern::patch(is.read_string()?));
                 },
+                50008 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.fields = is.read_enum_or_unknown()?;
+                },
+                50014 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.success_status = is.read_string()?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -381,6 +415,13 @@ impl ::protobuf::Message for HTTP {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
+        my_size += ::protobuf::rt::compute_map_size::<::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(50000, &self.headers);
+        if self.fields != ::protobuf::ProtobufEnumOrUnknown::new(HTTPFieldEncoding::FIELDS_DEFAULT) {
+            my_size += ::protobuf::rt::enum_or_unknown_size(50008, self.fields);
+        }
+        if !self.success_status.is_empty() {
+            my_size += ::protobuf::rt::string_size(50014, &self.success_status);
+        }
         if let ::std::option::Option::Some(ref v) = self.pattern {
             match v {
                 &http::Pattern::get(ref v) => {
@@ -406,6 +447,13 @@ impl ::protobuf::Message for HTTP {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        ::protobuf::rt::write_map_with_cached_sizes::<::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(50000, &self.headers, os)?;
+        if self.fields != ::protobuf::ProtobufEnumOrUnknown::new(HTTPFieldEncoding::FIELDS_DEFAULT) {
+            os.write_enum(50008, ::protobuf::ProtobufEnumOrUnknown::value(&self.fields))?;
+        }
+        if !self.success_status.is_empty() {
+            os.write_string(50014, &self.success_status)?;
+        }
         if let ::std::option::Option::Some(ref v) = self.pattern {
             match v {
                 &http::Pattern::get(ref v) => {
@@ -450,22 +498,21 @@ impl ::protobuf::Message for HTTP {
     }
 
     fn default_instance() -> &'static HTTP {
-        static instance: HTTP = HTTP {
-            pattern: ::std::option::Option::None,
-            unknown_fields: ::protobuf::UnknownFields::new(),
-            cached_size: ::protobuf::rt::CachedSize::new(),
-        };
-        &instance
+        static instance: ::protobuf::rt::LazyV2<HTTP> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(HTTP::new)
     }
 }
 
 impl ::protobuf::Clear for HTTP {
     fn clear(&mut self) {
+        self.headers.clear();
         self.pattern = ::std::option::Option::None;
         self.pattern = ::std::option::Option::None;
         self.pattern = ::std::option::Option::None;
         self.pattern = ::std::option::Option::None;
         self.pattern = ::std::option::Option::None;
+        self.fields = ::protobuf::ProtobufEnumOrUnknown::new(HTTPFieldEncoding::FIELDS_DEFAULT);
+        self.success_status.clear();
         self.unknown_fields.clear();
     }
 }
@@ -501,6 +548,9 @@ pub struct HTTPService {
     // message fields
     pub hostname: ::std::string::String,
     pub version: ::protobuf::ProtobufEnumOrUnknown<HTTPVersion>,
+    pub headers: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    pub max_response_size: u32,
+    pub tls: ::protobuf::MessageField<HTTPTLS>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::rt::CachedSize,
@@ -529,6 +579,21 @@ impl HTTPService {
             |m: &HTTPService| { &m.version },
             |m: &mut HTTPService| { &mut m.version },
         ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_accessor::<_, ::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(
+            "headers",
+            |m: &HTTPService| { &m.headers },
+            |m: &mut HTTPService| { &mut m.headers },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "max_response_size",
+            |m: &HTTPService| { &m.max_response_size },
+            |m: &mut HTTPService| { &mut m.max_response_size },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, HTTPTLS>(
+            "tls",
+            |m: &HTTPService| { &m.tls },
+            |m: &mut HTTPService| { &mut m.tls },
+        ));
         ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HTTPService>(
             "HTTPService",
             1,
@@ -558,6 +623,18 @@ impl ::protobuf::Message for HTTPService {
                     }
                     self.version = is.read_enum_or_unknown()?;
                 },
+                50009 => {
+                    ::protobuf::rt::read_map_into::<::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(wire_type, is, &mut self.headers)?;
+                },
+                50013 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.max_response_size = is.read_uint32()?;
+                },
+                50015 => {
+                    ::protobuf::rt::read_singular_message_into_field(wire_type, is, &mut self.tls)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -576,6 +653,14 @@ impl ::protobuf::Message for HTTPService {
         if self.version != ::protobuf::ProtobufEnumOrUnknown::new(HTTPVersion::VERSION_1_0) {
             my_size += ::protobuf::rt::enum_or_unknown_size(50007, self.version);
         }
+        my_size += ::protobuf::rt::compute_map_size::<::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(50009, &self.headers);
+        if self.max_response_size != 0 {
+            my_size += ::protobuf::rt::value_size(50013, self.max_response_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.tls.as_ref() {
+            let len = v.compute_size();
+            my_size += 3 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -588,6 +673,13 @@ impl ::protobuf::Message for HTTPService {
         if self.version != ::protobuf::ProtobufEnumOrUnknown::new(HTTPVersion::VERSION_1_0) {
             os.write_enum(50007, ::protobuf::ProtobufEnumOrUnknown::value(&self.version))?;
         }
+        ::protobuf::rt::write_map_with_cached_sizes::<::protobuf::reflect::types::ProtobufTypeString, ::protobuf::reflect::types::ProtobufTypeString>(50009, &self.headers, os)?;
+        if self.max_response_size != 0 {
+            os.write_uint32(50013, self.max_response_size)?;
+        }
+        if let Some(v) = self.tls.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(50015, v, os)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -613,13 +705,8 @@ impl ::protobuf::Message for HTTPService {
     }
 
     fn default_instance() -> &'static HTTPService {
-        static instance: HTTPService = HTTPService {
-            hostname: ::std::string::String::new(),
-            version: ::protobuf::ProtobufEnumOrUnknown::from_i32(0),
-            unknown_fields: ::protobuf::UnknownFields::new(),
-            cached_size: ::protobuf::rt::CachedSize::new(),
-        };
-        &instance
+        static instance: ::protobuf::rt::LazyV2<HTTPService> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(HTTPService::new)
     }
 }
 
@@ -627,6 +714,9 @@ impl ::protobuf::Clear for HTTPService {
     fn clear(&mut self) {
         self.hostname.clear();
         self.version = ::protobuf::ProtobufEnumOrUnknown::new(HTTPVersion::VERSION_1_0);
+        self.headers.clear();
+        self.max_response_size = 0;
+        self.tls.clear();
         self.unknown_fields.clear();
     }
 }
@@ -641,6 +731,211 @@ impl ::protobuf::reflect::ProtobufValue for HTTPService {
     type RuntimeType = ::protobuf::reflect::runtime_types::RuntimeTypeMessage<Self>;
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct HTTPTLS {
+    // message fields
+    pub enable: bool,
+    pub server_name: ::std::string::String,
+    pub ca: ::std::string::String,
+    pub cert: ::std::string::String,
+    pub key: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::rt::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a HTTPTLS {
+    fn default() -> &'a HTTPTLS {
+        <HTTPTLS as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl HTTPTLS {
+    pub fn new() -> HTTPTLS {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::new();
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "enable",
+            |m: &HTTPTLS| { &m.enable },
+            |m: &mut HTTPTLS| { &mut m.enable },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "server_name",
+            |m: &HTTPTLS| { &m.server_name },
+            |m: &mut HTTPTLS| { &mut m.server_name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ca",
+            |m: &HTTPTLS| { &m.ca },
+            |m: &mut HTTPTLS| { &mut m.ca },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "cert",
+            |m: &HTTPTLS| { &m.cert },
+            |m: &mut HTTPTLS| { &mut m.cert },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "key",
+            |m: &HTTPTLS| { &m.key },
+            |m: &mut HTTPTLS| { &mut m.key },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HTTPTLS>(
+            "HTTPTLS",
+            2,
+            fields,
+        )
+    }
+}
+
+impl ::protobuf::Message for HTTPTLS {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                50016 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.enable = is.read_bool()?;
+                },
+                50017 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.server_name = is.read_string()?;
+                },
+                50018 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.ca = is.read_string()?;
+                },
+                50019 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.cert = is.read_string()?;
+                },
+                50020 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.key = is.read_string()?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.enable != false {
+            my_size += 4;
+        }
+        if !self.server_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(50017, &self.server_name);
+        }
+        if !self.ca.is_empty() {
+            my_size += ::protobuf::rt::string_size(50018, &self.ca);
+        }
+        if !self.cert.is_empty() {
+            my_size += ::protobuf::rt::string_size(50019, &self.cert);
+        }
+        if !self.key.is_empty() {
+            my_size += ::protobuf::rt::string_size(50020, &self.key);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.enable != false {
+            os.write_bool(50016, self.enable)?;
+        }
+        if !self.server_name.is_empty() {
+            os.write_string(50017, &self.server_name)?;
+        }
+        if !self.ca.is_empty() {
+            os.write_string(50018, &self.ca)?;
+        }
+        if !self.cert.is_empty() {
+            os.write_string(50019, &self.cert)?;
+        }
+        if !self.key.is_empty() {
+            os.write_string(50020, &self.key)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn new() -> HTTPTLS {
+        HTTPTLS::new()
+    }
+
+    fn descriptor_static() -> ::protobuf::reflect::MessageDescriptor {
+        ::protobuf::reflect::MessageDescriptor::new_generated_2(file_descriptor(), 2)
+    }
+
+    fn default_instance() -> &'static HTTPTLS {
+        static instance: HTTPTLS = HTTPTLS {
+            enable: false,
+            server_name: ::std::string::String::new(),
+            ca: ::std::string::String::new(),
+            cert: ::std::string::String::new(),
+            key: ::std::string::String::new(),
+            unknown_fields: ::protobuf::UnknownFields::new(),
+            cached_size: ::protobuf::rt::CachedSize::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::Clear for HTTPTLS {
+    fn clear(&mut self) {
+        self.enable = false;
+        self.server_name.clear();
+        self.ca.clear();
+        self.cert.clear();
+        self.key.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for HTTPTLS {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HTTPTLS {
+    type RuntimeType = ::protobuf::reflect::runtime_types::RuntimeTypeMessage<Self>;
+}
+
 #[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
 pub enum HTTPVersion {
     VERSION_1_0 = 0,
@@ -692,6 +987,57 @@ impl HTTPVersion {
     }
 }
 
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+pub enum HTTPFieldEncoding {
+    FIELDS_DEFAULT = 0,
+    FIELDS_BODY = 1,
+    FIELDS_QUERY = 2,
+}
+
+impl ::protobuf::ProtobufEnum for HTTPFieldEncoding {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<HTTPFieldEncoding> {
+        match value {
+            0 => ::std::option::Option::Some(HTTPFieldEncoding::FIELDS_DEFAULT),
+            1 => ::std::option::Option::Some(HTTPFieldEncoding::FIELDS_BODY),
+            2 => ::std::option::Option::Some(HTTPFieldEncoding::FIELDS_QUERY),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [HTTPFieldEncoding] = &[
+            HTTPFieldEncoding::FIELDS_DEFAULT,
+            HTTPFieldEncoding::FIELDS_BODY,
+            HTTPFieldEncoding::FIELDS_QUERY,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> ::protobuf::reflect::EnumDescriptor {
+        ::protobuf::reflect::EnumDescriptor::new_generated_2(file_descriptor(), 1)
+    }
+}
+
+impl ::std::default::Default for HTTPFieldEncoding {
+    fn default() -> Self {
+        HTTPFieldEncoding::FIELDS_DEFAULT
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HTTPFieldEncoding {
+    type RuntimeType = ::protobuf::reflect::runtime_types::RuntimeTypeEnum<Self>;
+}
+
+impl HTTPFieldEncoding {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new_2::<HTTPFieldEncoding>("HTTPFieldEncoding", 1)
+    }
+}
+
 /// Extension fields
 pub mod exts {
 
@@ -702,63 +1048,137 @@ pub mod exts {
 
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\nhttp.proto\x12\rpandit.format\x1a\x20google/protobuf/descriptor.prot\
-    o\"\x8b\x01\n\x04HTTP\x12\x14\n\x03get\x18\xd1\x86\x03\x20\x01(\tH\0R\
-    \x03get\x12\x14\n\x03put\x18\xd2\x86\x03\x20\x01(\tH\0R\x03put\x12\x16\n\
-    \x04post\x18\xd3\x86\x03\x20\x01(\tH\0R\x04post\x12\x1a\n\x06delete\x18\
-    \xd4\x86\x03\x20\x01(\tH\0R\x06delete\x12\x18\n\x05patch\x18\xd5\x86\x03\
-    \x20\x01(\tH\0R\x05patchB\t\n\x07pattern\"c\n\x0bHTTPService\x12\x1c\n\
-    \x08hostname\x18\xd6\x86\x03\x20\x01(\tR\x08hostname\x126\n\x07version\
-    \x18\xd7\x86\x03\x20\x01(\x0e2\x1a.pandit.format.HTTPVersionR\x07version\
-    *@\n\x0bHTTPVersion\x12\x0f\n\x0bVERSION_1_0\x10\0\x12\x0f\n\x0bVERSION_\
-    1_1\x10\x01\x12\x0f\n\x0bVERSION_2_0\x10\x02:I\n\x04http\x18\xdb\x86\x03\
-    \x20\x01(\x0b2\x13.pandit.format.HTTP\x12\x1e.google.protobuf.MethodOpti\
-    onsR\x04http:`\n\x0chttp_service\x18\xdc\x86\x03\x20\x01(\x0b2\x1a.pandi\
-    t.format.HTTPService\x12\x1f.google.protobuf.ServiceOptionsR\x0bhttpServ\
-    iceJ\xf4\x08\n\x06\x12\x04\0\0'K\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\t\n\
-    \x02\x03\0\x12\x03\x01\0*\n\x08\n\x01\x02\x12\x03\x03\0\x16\n\n\n\x02\
-    \x04\0\x12\x04\x05\0\x18\x01\n\n\n\x03\x04\0\x01\x12\x03\x05\x08\x0c\n4\
-    \n\x04\x04\0\x08\0\x12\x04\x07\x02\x17\x03\x1a&\x20map<string,\x20string\
-    >\x20headers\x20=\x2050000;\n\n\x0c\n\x05\x04\0\x08\0\x01\x12\x03\x07\
-    \x08\x0f\n[\n\x04\x04\0\x02\0\x12\x03\n\x04\x17\x1aN\x20Maps\x20to\x20HT\
-    TP\x20GET.\x20Used\x20for\x20listing\x20and\x20getting\x20information\
-    \x20about\n\x20resources.\n\n\x0c\n\x05\x04\0\x02\0\x05\x12\x03\n\x04\n\
-    \n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\n\x0b\x0e\n\x0c\n\x05\x04\0\x02\0\
-    \x03\x12\x03\n\x11\x16\n?\n\x04\x04\0\x02\x01\x12\x03\r\x04\x17\x1a2\x20\
-    Maps\x20to\x20HTTP\x20PUT.\x20Used\x20for\x20replacing\x20a\x20resource.\
-    \n\n\x0c\n\x05\x04\0\x02\x01\x05\x12\x03\r\x04\n\n\x0c\n\x05\x04\0\x02\
-    \x01\x01\x12\x03\r\x0b\x0e\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\r\x11\
-    \x16\nW\n\x04\x04\0\x02\x02\x12\x03\x10\x04\x18\x1aJ\x20Maps\x20to\x20HT\
-    TP\x20POST.\x20Used\x20for\x20creating\x20a\x20resource\x20or\x20perform\
-    ing\x20an\x20action.\n\n\x0c\n\x05\x04\0\x02\x02\x05\x12\x03\x10\x04\n\n\
-    \x0c\n\x05\x04\0\x02\x02\x01\x12\x03\x10\x0b\x0f\n\x0c\n\x05\x04\0\x02\
-    \x02\x03\x12\x03\x10\x12\x17\nA\n\x04\x04\0\x02\x03\x12\x03\x13\x04\x1a\
-    \x1a4\x20Maps\x20to\x20HTTP\x20DELETE.\x20Used\x20for\x20deleting\x20a\
-    \x20resource.\n\n\x0c\n\x05\x04\0\x02\x03\x05\x12\x03\x13\x04\n\n\x0c\n\
-    \x05\x04\0\x02\x03\x01\x12\x03\x13\x0b\x11\n\x0c\n\x05\x04\0\x02\x03\x03\
-    \x12\x03\x13\x14\x19\n@\n\x04\x04\0\x02\x04\x12\x03\x16\x04\x19\x1a3\x20\
-    Maps\x20to\x20HTTP\x20PATCH.\x20Used\x20for\x20updating\x20a\x20resource\
-    .\n\n\x0c\n\x05\x04\0\x02\x04\x05\x12\x03\x16\x04\n\n\x0c\n\x05\x04\0\
-    \x02\x04\x01\x12\x03\x16\x0b\x10\n\x0c\n\x05\x04\0\x02\x04\x03\x12\x03\
-    \x16\x13\x18\n\n\n\x02\x05\0\x12\x04\x1a\0\x1e\x01\n\n\n\x03\x05\0\x01\
-    \x12\x03\x1a\x05\x10\n\x0b\n\x04\x05\0\x02\0\x12\x03\x1b\x02\x12\n\x0c\n\
-    \x05\x05\0\x02\0\x01\x12\x03\x1b\x02\r\n\x0c\n\x05\x05\0\x02\0\x02\x12\
-    \x03\x1b\x10\x11\n\x0b\n\x04\x05\0\x02\x01\x12\x03\x1c\x02\x12\n\x0c\n\
-    \x05\x05\0\x02\x01\x01\x12\x03\x1c\x02\r\n\x0c\n\x05\x05\0\x02\x01\x02\
-    \x12\x03\x1c\x10\x11\n\x0b\n\x04\x05\0\x02\x02\x12\x03\x1d\x02\x12\n\x0c\
-    \n\x05\x05\0\x02\x02\x01\x12\x03\x1d\x02\r\n\x0c\n\x05\x05\0\x02\x02\x02\
-    \x12\x03\x1d\x10\x11\n\n\n\x02\x04\x01\x12\x04\x20\0#\x01\n\n\n\x03\x04\
-    \x01\x01\x12\x03\x20\x08\x13\n\x0b\n\x04\x04\x01\x02\0\x12\x03!\x02\x1a\
-    \n\x0c\n\x05\x04\x01\x02\0\x05\x12\x03!\x02\x08\n\x0c\n\x05\x04\x01\x02\
-    \0\x01\x12\x03!\t\x11\n\x0c\n\x05\x04\x01\x02\0\x03\x12\x03!\x14\x19\n\
-    \x0b\n\x04\x04\x01\x02\x01\x12\x03\"\x02\x1e\n\x0c\n\x05\x04\x01\x02\x01\
-    \x06\x12\x03\"\x02\r\n\x0c\n\x05\x04\x01\x02\x01\x01\x12\x03\"\x0e\x15\n\
-    \x0c\n\x05\x04\x01\x02\x01\x03\x12\x03\"\x18\x1d\n\x08\n\x01\x07\x12\x03\
-    %\0;\n\t\n\x02\x07\0\x12\x03%'9\n\n\n\x03\x07\0\x02\x12\x03%\x07$\n\n\n\
-    \x03\x07\0\x06\x12\x03%'+\n\n\n\x03\x07\0\x01\x12\x03%,0\n\n\n\x03\x07\0\
-    \x03\x12\x03%38\n\x08\n\x01\x07\x12\x03'\0K\n\t\n\x02\x07\x01\x12\x03'(I\
-    \n\n\n\x03\x07\x01\x02\x12\x03'\x07%\n\n\n\x03\x07\x01\x06\x12\x03'(3\n\
-    \n\n\x03\x07\x01\x01\x12\x03'4@\n\n\n\x03\x07\x01\x03\x12\x03'CHb\x06pro\
-    to3\
+    o\"\xea\x02\n\x04HTTP\x12<\n\x07headers\x18\xd0\x86\x03\x20\x03(\x0b2\
+    \x20.pandit.format.HTTP.HeadersEntryR\x07headers\x12\x14\n\x03get\x18\
+    \xd1\x86\x03\x20\x01(\tH\0R\x03get\x12\x14\n\x03put\x18\xd2\x86\x03\x20\
+    \x01(\tH\0R\x03put\x12\x16\n\x04post\x18\xd3\x86\x03\x20\x01(\tH\0R\x04p\
+    ost\x12\x1a\n\x06delete\x18\xd4\x86\x03\x20\x01(\tH\0R\x06delete\x12\x18\
+    \n\x05patch\x18\xd5\x86\x03\x20\x01(\tH\0R\x05patch\x12:\n\x06fields\x18\
+    \xd8\x86\x03\x20\x01(\x0e2\x20.pandit.format.HTTPFieldEncodingR\x06field\
+    s\x12'\n\x0esuccess_status\x18\xde\x86\x03\x20\x01(\tR\rsuccessStatus\
+    \x1a:\n\x0cHeadersEntry\x12\x10\n\x03key\x18\x01\x20\x01(\tR\x03key\x12\
+    \x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\x028\x01B\t\n\x07pattern\"\
+    \xbe\x02\n\x0bHTTPService\x12\x1c\n\x08hostname\x18\xd6\x86\x03\x20\x01(\
+    \tR\x08hostname\x126\n\x07version\x18\xd7\x86\x03\x20\x01(\x0e2\x1a.pand\
+    it.format.HTTPVersionR\x07version\x12C\n\x07headers\x18\xd9\x86\x03\x20\
+    \x03(\x0b2'.pandit.format.HTTPService.HeadersEntryR\x07headers\x12,\n\
+    \x11max_response_size\x18\xdd\x86\x03\x20\x01(\rR\x0fmaxResponseSize\x12\
+    *\n\x03tls\x18\xdf\x86\x03\x20\x01(\x0b2\x16.pandit.format.HTTPTLSR\x03t\
+    ls\x1a:\n\x0cHeadersEntry\x12\x10\n\x03key\x18\x01\x20\x01(\tR\x03key\
+    \x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\x028\x01\"\x82\x01\n\
+    \x07HTTPTLS\x12\x18\n\x06enable\x18\xe0\x86\x03\x20\x01(\x08R\x06enable\
+    \x12!\n\x0bserver_name\x18\xe1\x86\x03\x20\x01(\tR\nserverName\x12\x10\n\
+    \x02ca\x18\xe2\x86\x03\x20\x01(\tR\x02ca\x12\x14\n\x04cert\x18\xe3\x86\
+    \x03\x20\x01(\tR\x04cert\x12\x12\n\x03key\x18\xe4\x86\x03\x20\x01(\tR\
+    \x03key*@\n\x0bHTTPVersion\x12\x0f\n\x0bVERSION_1_0\x10\0\x12\x0f\n\x0bV\
+    ERSION_1_1\x10\x01\x12\x0f\n\x0bVERSION_2_0\x10\x02*J\n\x11HTTPFieldEnco\
+    ding\x12\x12\n\x0eFIELDS_DEFAULT\x10\0\x12\x0f\n\x0bFIELDS_BODY\x10\x01\
+    \x12\x10\n\x0cFIELDS_QUERY\x10\x02:I\n\x04http\x18\xdb\x86\x03\x20\x01(\
+    \x0b2\x13.pandit.format.HTTP\x12\x1e.google.protobuf.MethodOptionsR\x04h\
+    ttp:`\n\x0chttp_service\x18\xdc\x86\x03\x20\x01(\x0b2\x1a.pandit.format.\
+    HTTPService\x12\x1f.google.protobuf.ServiceOptionsR\x0bhttpServiceJ\xdf\
+    \x16\n\x06\x12\x04\0\0LK\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\t\n\x02\x03\0\
+    \x12\x03\x01\0*\n\x08\n\x01\x02\x12\x03\x03\0\x16\n\n\n\x02\x04\0\x12\
+    \x04\x05\0#\x01\n\n\n\x03\x04\0\x01\x12\x03\x05\x08\x0c\n\xdd\x01\n\x04\
+    \x04\0\x02\0\x12\x03\t\x02&\x1a\xcf\x01\x20Headers\x20sent\x20with\x20th\
+    e\x20request,\x20added\x20to\x20and\x20overriding\x20those\x20of\x20the\
+    \x20service.\n\x20Values\x20may\x20read\x20request\x20fields\x20as\x20`{\
+    field}`\x20and\x20environment\x20variables\x20as\n\x20`${NAME}`;\x20a\
+    \x20header\x20whose\x20field\x20is\x20unset\x20is\x20left\x20out.\n\n\
+    \x0c\n\x05\x04\0\x02\0\x06\x12\x03\t\x02\x15\n\x0c\n\x05\x04\0\x02\0\x01\
+    \x12\x03\t\x16\x1d\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\t\x20%\n\x0c\n\
+    \x04\x04\0\x08\0\x12\x04\n\x02\x1a\x03\n\x0c\n\x05\x04\0\x08\0\x01\x12\
+    \x03\n\x08\x0f\n[\n\x04\x04\0\x02\x01\x12\x03\r\x04\x17\x1aN\x20Maps\x20\
+    to\x20HTTP\x20GET.\x20Used\x20for\x20listing\x20and\x20getting\x20inform\
+    ation\x20about\n\x20resources.\n\n\x0c\n\x05\x04\0\x02\x01\x05\x12\x03\r\
+    \x04\n\n\x0c\n\x05\x04\0\x02\x01\x01\x12\x03\r\x0b\x0e\n\x0c\n\x05\x04\0\
+    \x02\x01\x03\x12\x03\r\x11\x16\n?\n\x04\x04\0\x02\x02\x12\x03\x10\x04\
+    \x17\x1a2\x20Maps\x20to\x20HTTP\x20PUT.\x20Used\x20for\x20replacing\x20a\
+    \x20resource.\n\n\x0c\n\x05\x04\0\x02\x02\x05\x12\x03\x10\x04\n\n\x0c\n\
+    \x05\x04\0\x02\x02\x01\x12\x03\x10\x0b\x0e\n\x0c\n\x05\x04\0\x02\x02\x03\
+    \x12\x03\x10\x11\x16\nW\n\x04\x04\0\x02\x03\x12\x03\x13\x04\x18\x1aJ\x20\
+    Maps\x20to\x20HTTP\x20POST.\x20Used\x20for\x20creating\x20a\x20resource\
+    \x20or\x20performing\x20an\x20action.\n\n\x0c\n\x05\x04\0\x02\x03\x05\
+    \x12\x03\x13\x04\n\n\x0c\n\x05\x04\0\x02\x03\x01\x12\x03\x13\x0b\x0f\n\
+    \x0c\n\x05\x04\0\x02\x03\x03\x12\x03\x13\x12\x17\nA\n\x04\x04\0\x02\x04\
+    \x12\x03\x16\x04\x1a\x1a4\x20Maps\x20to\x20HTTP\x20DELETE.\x20Used\x20fo\
+    r\x20deleting\x20a\x20resource.\n\n\x0c\n\x05\x04\0\x02\x04\x05\x12\x03\
+    \x16\x04\n\n\x0c\n\x05\x04\0\x02\x04\x01\x12\x03\x16\x0b\x11\n\x0c\n\x05\
+    \x04\0\x02\x04\x03\x12\x03\x16\x14\x19\n@\n\x04\x04\0\x02\x05\x12\x03\
+    \x19\x04\x19\x1a3\x20Maps\x20to\x20HTTP\x20PATCH.\x20Used\x20for\x20upda\
+    ting\x20a\x20resource.\n\n\x0c\n\x05\x04\0\x02\x05\x05\x12\x03\x19\x04\n\
+    \n\x0c\n\x05\x04\0\x02\x05\x01\x12\x03\x19\x0b\x10\n\x0c\n\x05\x04\0\x02\
+    \x05\x03\x12\x03\x19\x13\x18\nB\n\x04\x04\0\x02\x06\x12\x03\x1d\x02#\x1a\
+    5\x20Where\x20request\x20fields\x20not\x20used\x20by\x20the\x20path\x20a\
+    re\x20sent.\n\n\x0c\n\x05\x04\0\x02\x06\x06\x12\x03\x1d\x02\x13\n\x0c\n\
+    \x05\x04\0\x02\x06\x01\x12\x03\x1d\x14\x1a\n\x0c\n\x05\x04\0\x02\x06\x03\
+    \x12\x03\x1d\x1d\"\n\xbc\x01\n\x04\x04\0\x02\x07\x12\x03\"\x02\x20\x1a\
+    \xae\x01\x20Response\x20statuses\x20treated\x20as\x20success,\x20such\
+    \x20as\x20\"200,204\"\x20or\x20\"2xx,404\".\x20Defaults\n\x20to\x20any\
+    \x202xx\x20status;\x20other\x20responses\x20fail\x20the\x20call\x20with\
+    \x20the\x20status\x20as\x20the\x20backend\n\x20error\x20code.\n\n\x0c\n\
+    \x05\x04\0\x02\x07\x05\x12\x03\"\x02\x08\n\x0c\n\x05\x04\0\x02\x07\x01\
+    \x12\x03\"\t\x17\n\x0c\n\x05\x04\0\x02\x07\x03\x12\x03\"\x1a\x1f\n\n\n\
+    \x02\x05\0\x12\x04%\0)\x01\n\n\n\x03\x05\0\x01\x12\x03%\x05\x10\n\x0b\n\
+    \x04\x05\0\x02\0\x12\x03&\x02\x12\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03&\
+    \x02\r\n\x0c\n\x05\x05\0\x02\0\x02\x12\x03&\x10\x11\n\x0b\n\x04\x05\0\
+    \x02\x01\x12\x03'\x02\x12\n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03'\x02\r\n\
+    \x0c\n\x05\x05\0\x02\x01\x02\x12\x03'\x10\x11\n\x0b\n\x04\x05\0\x02\x02\
+    \x12\x03(\x02\x12\n\x0c\n\x05\x05\0\x02\x02\x01\x12\x03(\x02\r\n\x0c\n\
+    \x05\x05\0\x02\x02\x02\x12\x03(\x10\x11\n\n\n\x02\x05\x01\x12\x04+\01\
+    \x01\n\n\n\x03\x05\x01\x01\x12\x03+\x05\x16\nG\n\x04\x05\x01\x02\0\x12\
+    \x03-\x02\x15\x1a:\x20The\x20query\x20string\x20for\x20GET\x20and\x20DEL\
+    ETE,\x20the\x20body\x20otherwise.\n\n\x0c\n\x05\x05\x01\x02\0\x01\x12\
+    \x03-\x02\x10\n\x0c\n\x05\x05\x01\x02\0\x02\x12\x03-\x13\x14\n\x0b\n\x04\
+    \x05\x01\x02\x01\x12\x03.\x02\x12\n\x0c\n\x05\x05\x01\x02\x01\x01\x12\
+    \x03.\x02\r\n\x0c\n\x05\x05\x01\x02\x01\x02\x12\x03.\x10\x11\nS\n\x04\
+    \x05\x01\x02\x02\x12\x030\x02\x13\x1aF\x20Repeated\x20fields\x20repeat\
+    \x20their\x20key\x20and\x20nested\x20fields\x20use\x20dotted\x20names.\n\
+    \n\x0c\n\x05\x05\x01\x02\x02\x01\x12\x030\x02\x0e\n\x0c\n\x05\x05\x01\
+    \x02\x02\x02\x12\x030\x11\x12\n\n\n\x02\x04\x01\x12\x043\0;\x01\n\n\n\
+    \x03\x04\x01\x01\x12\x033\x08\x13\n\x0b\n\x04\x04\x01\x02\0\x12\x034\x02\
+    \x1a\n\x0c\n\x05\x04\x01\x02\0\x05\x12\x034\x02\x08\n\x0c\n\x05\x04\x01\
+    \x02\0\x01\x12\x034\t\x11\n\x0c\n\x05\x04\x01\x02\0\x03\x12\x034\x14\x19\
+    \n\x0b\n\x04\x04\x01\x02\x01\x12\x035\x02\x1e\n\x0c\n\x05\x04\x01\x02\
+    \x01\x06\x12\x035\x02\r\n\x0c\n\x05\x04\x01\x02\x01\x01\x12\x035\x0e\x15\
+    \n\x0c\n\x05\x04\x01\x02\x01\x03\x12\x035\x18\x1d\n>\n\x04\x04\x01\x02\
+    \x02\x12\x037\x02&\x1a1\x20Headers\x20sent\x20with\x20every\x20request\
+    \x20to\x20the\x20service.\n\n\x0c\n\x05\x04\x01\x02\x02\x06\x12\x037\x02\
+    \x15\n\x0c\n\x05\x04\x01\x02\x02\x01\x12\x037\x16\x1d\n\x0c\n\x05\x04\
+    \x01\x02\x02\x03\x12\x037\x20%\nX\n\x04\x04\x01\x02\x03\x12\x039\x02#\
+    \x1aK\x20Largest\x20response\x20body\x20read\x20from\x20the\x20service,\
+    \x20in\x20bytes.\x20Defaults\x20to\x204\x20MiB.\n\n\x0c\n\x05\x04\x01\
+    \x02\x03\x05\x12\x039\x02\x08\n\x0c\n\x05\x04\x01\x02\x03\x01\x12\x039\t\
+    \x1a\n\x0c\n\x05\x04\x01\x02\x03\x03\x12\x039\x1d\"\n\x0b\n\x04\x04\x01\
+    \x02\x04\x12\x03:\x02\x16\n\x0c\n\x05\x04\x01\x02\x04\x06\x12\x03:\x02\t\
+    \n\x0c\n\x05\x04\x01\x02\x04\x01\x12\x03:\n\r\n\x0c\n\x05\x04\x01\x02\
+    \x04\x03\x12\x03:\x10\x15\n]\n\x02\x04\x02\x12\x04>\0H\x01\x1aQ\x20Conne\
+    cts\x20to\x20the\x20service\x20over\x20TLS,\x20negotiating\x20HTTP/2\x20\
+    with\x20ALPN\x20for\x20VERSION_2_0.\n\n\n\n\x03\x04\x02\x01\x12\x03>\x08\
+    \x0f\n\x0b\n\x04\x04\x02\x02\0\x12\x03?\x02\x16\n\x0c\n\x05\x04\x02\x02\
+    \0\x05\x12\x03?\x02\x06\n\x0c\n\x05\x04\x02\x02\0\x01\x12\x03?\x07\r\n\
+    \x0c\n\x05\x04\x02\x02\0\x03\x12\x03?\x10\x15\ni\n\x04\x04\x02\x02\x01\
+    \x12\x03B\x02\x1d\x1a\\\x20Name\x20sent\x20in\x20SNI\x20and\x20checked\
+    \x20against\x20the\x20certificate,\x20defaulting\x20to\x20the\n\x20servi\
+    ce\x20hostname.\n\n\x0c\n\x05\x04\x02\x02\x01\x05\x12\x03B\x02\x08\n\x0c\
+    \n\x05\x04\x02\x02\x01\x01\x12\x03B\t\x14\n\x0c\n\x05\x04\x02\x02\x01\
+    \x03\x12\x03B\x17\x1c\nY\n\x04\x04\x02\x02\x02\x12\x03D\x02\x14\x1aL\x20\
+    PEM\x20bundle\x20of\x20CAs\x20trusted\x20for\x20the\x20service,\x20defau\
+    lting\x20to\x20the\x20system\x20roots.\n\n\x0c\n\x05\x04\x02\x02\x02\x05\
+    \x12\x03D\x02\x08\n\x0c\n\x05\x04\x02\x02\x02\x01\x12\x03D\t\x0b\n\x0c\n\
+    \x05\x04\x02\x02\x02\x03\x12\x03D\x0e\x13\nW\n\x04\x04\x02\x02\x03\x12\
+    \x03F\x02\x16\x1aJ\x20PEM\x20client\x20certificate\x20chain\x20and\x20ke\
+    y,\x20for\x20services\x20requiring\x20mutual\x20TLS.\n\n\x0c\n\x05\x04\
+    \x02\x02\x03\x05\x12\x03F\x02\x08\n\x0c\n\x05\x04\x02\x02\x03\x01\x12\
+    \x03F\t\r\n\x0c\n\x05\x04\x02\x02\x03\x03\x12\x03F\x10\x15\n\x0b\n\x04\
+    \x04\x02\x02\x04\x12\x03G\x02\x15\n\x0c\n\x05\x04\x02\x02\x04\x05\x12\
+    \x03G\x02\x08\n\x0c\n\x05\x04\x02\x02\x04\x01\x12\x03G\t\x0c\n\x0c\n\x05\
+    \x04\x02\x02\x04\x03\x12\x03G\x0f\x14\n\x08\n\x01\x07\x12\x03J\0;\n\t\n\
+    \x02\x07\0\x12\x03J'9\n\n\n\x03\x07\0\x02\x12\x03J\x07$\n\n\n\x03\x07\0\
+    \x06\x12\x03J'+\n\n\n\x03\x07\0\x01\x12\x03J,0\n\n\n\x03\x07\0\x03\x12\
+    \x03J38\n\x08\n\x01\x07\x12\x03L\0K\n\t\n\x02\x07\x01\x12\x03L(I\n\n\n\
+    \x03\x07\x01\x02\x12\x03L\x07%\n\n\n\x03\x07\x01\x06\x12\x03L(3\n\n\n\
+    \x03\x07\x01\x01\x12\x03L4@\n\n\n\x03\x07\x01\x03\x12\x03LCHb\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -778,8 +1198,10 @@ pub fn file_descriptor() -> ::protobuf::reflect::FileDescriptor {
         let mut messages = ::std::vec::Vec::new();
         messages.push(HTTP::generated_message_descriptor_data());
         messages.push(HTTPService::generated_message_descriptor_data());
+        messages.push(HTTPTLS::generated_message_descriptor_data());
         let mut enums = ::std::vec::Vec::new();
         enums.push(HTTPVersion::generated_enum_descriptor_data());
+        enums.push(HTTPFieldEncoding::generated_enum_descriptor_data());
         ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
             file_descriptor_proto(),
             deps,
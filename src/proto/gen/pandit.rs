@@ -170,6 +170,244 @@ impl ::protobuf::reflect::ProtobufValue for CacheOptions {
     type RuntimeType = ::protobuf::reflect::runtime_types::RuntimeTypeMessage<Self>;
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct ErrorMapping {
+    // message fields
+    pub backend_code: ::std::string::String,
+    pub status: ::protobuf::ProtobufEnumOrUnknown<StatusCode>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::rt::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ErrorMapping {
+    fn default() -> &'a ErrorMapping {
+        <ErrorMapping as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ErrorMapping {
+    pub fn new() -> ErrorMapping {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::new();
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "backend_code",
+            |m: &ErrorMapping| { &m.backend_code },
+            |m: &mut ErrorMapping| { &mut m.backend_code },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "status",
+            |m: &ErrorMapping| { &m.status },
+            |m: &mut ErrorMapping| { &mut m.status },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ErrorMapping>(
+            "ErrorMapping",
+            1,
+            fields,
+        )
+    }
+}
+
+impl ::protobuf::Message for ErrorMapping {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                60033 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.backend_code = is.read_string()?;
+                },
+                60034 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.status = is.read_enum_or_unknown()?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.backend_code.is_empty() {
+            my_size += ::protobuf::rt::string_size(60033, &self.backend_code);
+        }
+        if self.status != ::protobuf::ProtobufEnumOrUnknown::new(StatusCode::OK) {
+            my_size += ::protobuf::rt::enum_or_unknown_size(60034, self.status);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.backend_code.is_empty() {
+            os.write_string(60033, &self.backend_code)?;
+        }
+        if self.status != ::protobuf::ProtobufEnumOrUnknown::new(StatusCode::OK) {
+            os.write_enum(60034, ::protobuf::ProtobufEnumOrUnknown::value(&self.status))?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn new() -> ErrorMapping {
+        ErrorMapping::new()
+    }
+
+    fn descriptor_static() -> ::protobuf::reflect::MessageDescriptor {
+        ::protobuf::reflect::MessageDescriptor::new_generated_2(file_descriptor(), 1)
+    }
+
+    fn default_instance() -> &'static ErrorMapping {
+        static instance: ErrorMapping = ErrorMapping {
+            backend_code: ::std::string::String::new(),
+            status: ::protobuf::ProtobufEnumOrUnknown::from_i32(0),
+            unknown_fields: ::protobuf::UnknownFields::new(),
+            cached_size: ::protobuf::rt::CachedSize::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::Clear for ErrorMapping {
+    fn clear(&mut self) {
+        self.backend_code.clear();
+        self.status = ::protobuf::ProtobufEnumOrUnknown::new(StatusCode::OK);
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for ErrorMapping {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ErrorMapping {
+    type RuntimeType = ::protobuf::reflect::runtime_types::RuntimeTypeMessage<Self>;
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+pub enum StatusCode {
+    OK = 0,
+    CANCELLED = 1,
+    UNKNOWN = 2,
+    INVALID_ARGUMENT = 3,
+    DEADLINE_EXCEEDED = 4,
+    NOT_FOUND = 5,
+    ALREADY_EXISTS = 6,
+    PERMISSION_DENIED = 7,
+    RESOURCE_EXHAUSTED = 8,
+    FAILED_PRECONDITION = 9,
+    ABORTED = 10,
+    OUT_OF_RANGE = 11,
+    UNIMPLEMENTED = 12,
+    INTERNAL = 13,
+    UNAVAILABLE = 14,
+    DATA_LOSS = 15,
+    UNAUTHENTICATED = 16,
+}
+
+impl ::protobuf::ProtobufEnum for StatusCode {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<StatusCode> {
+        match value {
+            0 => ::std::option::Option::Some(StatusCode::OK),
+            1 => ::std::option::Option::Some(StatusCode::CANCELLED),
+            2 => ::std::option::Option::Some(StatusCode::UNKNOWN),
+            3 => ::std::option::Option::Some(StatusCode::INVALID_ARGUMENT),
+            4 => ::std::option::Option::Some(StatusCode::DEADLINE_EXCEEDED),
+            5 => ::std::option::Option::Some(StatusCode::NOT_FOUND),
+            6 => ::std::option::Option::Some(StatusCode::ALREADY_EXISTS),
+            7 => ::std::option::Option::Some(StatusCode::PERMISSION_DENIED),
+            8 => ::std::option::Option::Some(StatusCode::RESOURCE_EXHAUSTED),
+            9 => ::std::option::Option::Some(StatusCode::FAILED_PRECONDITION),
+            10 => ::std::option::Option::Some(StatusCode::ABORTED),
+            11 => ::std::option::Option::Some(StatusCode::OUT_OF_RANGE),
+            12 => ::std::option::Option::Some(StatusCode::UNIMPLEMENTED),
+            13 => ::std::option::Option::Some(StatusCode::INTERNAL),
+            14 => ::std::option::Option::Some(StatusCode::UNAVAILABLE),
+            15 => ::std::option::Option::Some(StatusCode::DATA_LOSS),
+            16 => ::std::option::Option::Some(StatusCode::UNAUTHENTICATED),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [StatusCode] = &[
+            StatusCode::OK,
+            StatusCode::CANCELLED,
+            StatusCode::UNKNOWN,
+            StatusCode::INVALID_ARGUMENT,
+            StatusCode::DEADLINE_EXCEEDED,
+            StatusCode::NOT_FOUND,
+            StatusCode::ALREADY_EXISTS,
+            StatusCode::PERMISSION_DENIED,
+            StatusCode::RESOURCE_EXHAUSTED,
+            StatusCode::FAILED_PRECONDITION,
+            StatusCode::ABORTED,
+            StatusCode::OUT_OF_RANGE,
+            StatusCode::UNIMPLEMENTED,
+            StatusCode::INTERNAL,
+            StatusCode::UNAVAILABLE,
+            StatusCode::DATA_LOSS,
+            StatusCode::UNAUTHENTICATED,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> ::protobuf::reflect::EnumDescriptor {
+        ::protobuf::reflect::EnumDescriptor::new_generated_2(file_descriptor(), 0)
+    }
+}
+
+impl ::std::default::Default for StatusCode {
+    fn default() -> Self {
+        StatusCode::OK
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StatusCode {
+    type RuntimeType = ::protobuf::reflect::runtime_types::RuntimeTypeEnum<Self>;
+}
+
+impl StatusCode {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new_2::<StatusCode>("StatusCode", 0)
+    }
+}
+
 /// Extension fields
 pub mod exts {
 
@@ -185,59 +423,156 @@ pub mod exts {
 
     pub const cache: ::protobuf::ext::ExtFieldOptional<::protobuf::descriptor::MethodOptions, ::protobuf::reflect::types::ProtobufTypeMessage<super::CacheOptions>> = ::protobuf::ext::ExtFieldOptional { field_number: 50034, phantom: ::std::marker::PhantomData };
 
+    pub const error: ::protobuf::ext::ExtFieldRepeated<::protobuf::descriptor::MethodOptions, ::protobuf::reflect::types::ProtobufTypeMessage<super::ErrorMapping>> = ::protobuf::ext::ExtFieldRepeated { field_number: 50038, phantom: ::std::marker::PhantomData };
+
+    pub const timeout_ms: ::protobuf::ext::ExtFieldOptional<::protobuf::descriptor::MethodOptions, ::protobuf::reflect::types::ProtobufTypeUint64> = ::protobuf::ext::ExtFieldOptional { field_number: 50039, phantom: ::std::marker::PhantomData };
+
+    pub const forward_metadata: ::protobuf::ext::ExtFieldRepeated<::protobuf::descriptor::MethodOptions, ::protobuf::reflect::types::ProtobufTypeString> = ::protobuf::ext::ExtFieldRepeated { field_number: 50040, phantom: ::std::marker::PhantomData };
+
+    pub const return_headers: ::protobuf::ext::ExtFieldRepeated<::protobuf::descriptor::MethodOptions, ::protobuf::reflect::types::ProtobufTypeString> = ::protobuf::ext::ExtFieldRepeated { field_number: 50041, phantom: ::std::marker::PhantomData };
+
     pub const name: ::protobuf::ext::ExtFieldOptional<::protobuf::descriptor::ServiceOptions, ::protobuf::reflect::types::ProtobufTypeString> = ::protobuf::ext::ExtFieldOptional { field_number: 50010, phantom: ::std::marker::PhantomData };
 
     pub const default_cache: ::protobuf::ext::ExtFieldOptional<::protobuf::descriptor::ServiceOptions, ::protobuf::reflect::types::ProtobufTypeMessage<super::CacheOptions>> = ::protobuf::ext::ExtFieldOptional { field_number: 50035, phantom: ::std::marker::PhantomData };
+
+    pub const default_forward_metadata: ::protobuf::ext::ExtFieldRepeated<::protobuf::descriptor::ServiceOptions, ::protobuf::reflect::types::ProtobufTypeString> = ::protobuf::ext::ExtFieldRepeated { field_number: 50042, phantom: ::std::marker::PhantomData };
+
+    pub const default_return_headers: ::protobuf::ext::ExtFieldRepeated<::protobuf::descriptor::ServiceOptions, ::protobuf::reflect::types::ProtobufTypeString> = ::protobuf::ext::ExtFieldRepeated { field_number: 50043, phantom: ::std::marker::PhantomData };
 }
 
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x0cpandit.proto\x12\x06pandit\x1a\x20google/protobuf/descriptor.proto\
     \"K\n\x0cCacheOptions\x12\x1a\n\x07disable\x18\xff\xd4\x03\x20\x01(\x08R\
     \x07disable\x12\x1f\n\ncache_time\x18\x80\xd5\x03\x20\x01(\x04R\tcacheTi\
-    me:D\n\rabsolute_path\x18\xe4\x86\x03\x20\x01(\t\x12\x1d.google.protobuf\
-    .FieldOptionsR\x0cabsolutePath:D\n\rrelative_path\x18\xe5\x86\x03\x20\
-    \x01(\t\x12\x1d.google.protobuf.FieldOptionsR\x0crelativePath:V\n\x0bfie\
-    ld_cache\x18\xf4\x86\x03\x20\x01(\x0b2\x14.pandit.CacheOptions\x12\x1d.g\
-    oogle.protobuf.FieldOptionsR\nfieldCache:1\n\x03key\x18\xf5\x86\x03\x20\
-    \x01(\x08\x12\x1d.google.protobuf.FieldOptionsR\x03key:5\n\x04path\x18\
-    \xee\x86\x03\x20\x01(\t\x12\x1f.google.protobuf.MessageOptionsR\x04path:\
-    L\n\x05cache\x18\xf2\x86\x03\x20\x01(\x0b2\x14.pandit.CacheOptions\x12\
-    \x1e.google.protobuf.MethodOptionsR\x05cache:5\n\x04name\x18\xda\x86\x03\
-    \x20\x01(\t\x12\x1f.google.protobuf.ServiceOptionsR\x04name:\\\n\rdefaul\
-    t_cache\x18\xf3\x86\x03\x20\x01(\x0b2\x14.pandit.CacheOptions\x12\x1f.go\
-    ogle.protobuf.ServiceOptionsR\x0cdefaultCacheJ\xaf\x05\n\x06\x12\x04\0\0\
-    \x18\x01\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\t\n\x02\x03\0\x12\x03\x01\0*\
-    \n\x08\n\x01\x02\x12\x03\x03\0\x0f\n\n\n\x02\x04\0\x12\x04\x05\0\x08\x01\
-    \n\n\n\x03\x04\0\x01\x12\x03\x05\x08\x14\n\x0b\n\x04\x04\0\x02\0\x12\x03\
-    \x06\x02\x17\n\x0c\n\x05\x04\0\x02\0\x05\x12\x03\x06\x02\x06\n\x0c\n\x05\
-    \x04\0\x02\0\x01\x12\x03\x06\x07\x0e\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\
-    \x06\x11\x16\n\x0b\n\x04\x04\0\x02\x01\x12\x03\x07\x02\x1c\n\x0c\n\x05\
-    \x04\0\x02\x01\x05\x12\x03\x07\x02\x08\n\x0c\n\x05\x04\0\x02\x01\x01\x12\
-    \x03\x07\t\x13\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\x07\x16\x1b\n\t\n\
-    \x01\x07\x12\x04\n\0\x0f\x01\n\t\n\x02\x07\0\x12\x03\x0b\x02\x1f\n\n\n\
-    \x03\x07\0\x02\x12\x03\n\x07#\n\n\n\x03\x07\0\x05\x12\x03\x0b\x02\x08\n\
-    \n\n\x03\x07\0\x01\x12\x03\x0b\t\x16\n\n\n\x03\x07\0\x03\x12\x03\x0b\x19\
-    \x1e\n\t\n\x02\x07\x01\x12\x03\x0c\x02\x1f\n\n\n\x03\x07\x01\x02\x12\x03\
-    \n\x07#\n\n\n\x03\x07\x01\x05\x12\x03\x0c\x02\x08\n\n\n\x03\x07\x01\x01\
-    \x12\x03\x0c\t\x16\n\n\n\x03\x07\x01\x03\x12\x03\x0c\x19\x1e\n\t\n\x02\
-    \x07\x02\x12\x03\r\x02#\n\n\n\x03\x07\x02\x02\x12\x03\n\x07#\n\n\n\x03\
-    \x07\x02\x06\x12\x03\r\x02\x0e\n\n\n\x03\x07\x02\x01\x12\x03\r\x0f\x1a\n\
-    \n\n\x03\x07\x02\x03\x12\x03\r\x1d\"\n\t\n\x02\x07\x03\x12\x03\x0e\x02\
-    \x13\n\n\n\x03\x07\x03\x02\x12\x03\n\x07#\n\n\n\x03\x07\x03\x05\x12\x03\
-    \x0e\x02\x06\n\n\n\x03\x07\x03\x01\x12\x03\x0e\x07\n\n\n\n\x03\x07\x03\
-    \x03\x12\x03\x0e\r\x12\n\x08\n\x01\x07\x12\x03\x11\0>\n\t\n\x02\x07\x04\
-    \x12\x03\x11(<\n\n\n\x03\x07\x04\x02\x12\x03\x11\x07%\n\n\n\x03\x07\x04\
-    \x05\x12\x03\x11(.\n\n\n\x03\x07\x04\x01\x12\x03\x11/3\n\n\n\x03\x07\x04\
-    \x03\x12\x03\x116;\n\x08\n\x01\x07\x12\x03\x13\0D\n\t\n\x02\x07\x05\x12\
-    \x03\x13'B\n\n\n\x03\x07\x05\x02\x12\x03\x13\x07$\n\n\n\x03\x07\x05\x06\
-    \x12\x03\x13'3\n\n\n\x03\x07\x05\x01\x12\x03\x1349\n\n\n\x03\x07\x05\x03\
-    \x12\x03\x13<A\n\t\n\x01\x07\x12\x04\x15\0\x18\x01\n\t\n\x02\x07\x06\x12\
-    \x03\x16\x02\x16\n\n\n\x03\x07\x06\x02\x12\x03\x15\x07%\n\n\n\x03\x07\
-    \x06\x05\x12\x03\x16\x02\x08\n\n\n\x03\x07\x06\x01\x12\x03\x16\t\r\n\n\n\
-    \x03\x07\x06\x03\x12\x03\x16\x10\x15\n\t\n\x02\x07\x07\x12\x03\x17\x02%\
-    \n\n\n\x03\x07\x07\x02\x12\x03\x15\x07%\n\n\n\x03\x07\x07\x06\x12\x03\
-    \x17\x02\x0e\n\n\n\x03\x07\x07\x01\x12\x03\x17\x0f\x1c\n\n\n\x03\x07\x07\
-    \x03\x12\x03\x17\x1f$b\x06proto3\
+    me\"a\n\x0cErrorMapping\x12#\n\x0cbackend_code\x18\x81\xd5\x03\x20\x01(\
+    \tR\x0bbackendCode\x12,\n\x06status\x18\x82\xd5\x03\x20\x01(\x0e2\x12.pa\
+    ndit.StatusCodeR\x06status*\xbd\x02\n\nStatusCode\x12\x06\n\x02OK\x10\0\
+    \x12\r\n\tCANCELLED\x10\x01\x12\x0b\n\x07UNKNOWN\x10\x02\x12\x14\n\x10IN\
+    VALID_ARGUMENT\x10\x03\x12\x15\n\x11DEADLINE_EXCEEDED\x10\x04\x12\r\n\tN\
+    OT_FOUND\x10\x05\x12\x12\n\x0eALREADY_EXISTS\x10\x06\x12\x15\n\x11PERMIS\
+    SION_DENIED\x10\x07\x12\x16\n\x12RESOURCE_EXHAUSTED\x10\x08\x12\x17\n\
+    \x13FAILED_PRECONDITION\x10\t\x12\x0b\n\x07ABORTED\x10\n\x12\x10\n\x0cOU\
+    T_OF_RANGE\x10\x0b\x12\x11\n\rUNIMPLEMENTED\x10\x0c\x12\x0c\n\x08INTERNA\
+    L\x10\r\x12\x0f\n\x0bUNAVAILABLE\x10\x0e\x12\r\n\tDATA_LOSS\x10\x0f\x12\
+    \x13\n\x0fUNAUTHENTICATED\x10\x10:D\n\rabsolute_path\x18\xe4\x86\x03\x20\
+    \x01(\t\x12\x1d.google.protobuf.FieldOptionsR\x0cabsolutePath:D\n\rrelat\
+    ive_path\x18\xe5\x86\x03\x20\x01(\t\x12\x1d.google.protobuf.FieldOptions\
+    R\x0crelativePath:V\n\x0bfield_cache\x18\xf4\x86\x03\x20\x01(\x0b2\x14.p\
+    andit.CacheOptions\x12\x1d.google.protobuf.FieldOptionsR\nfieldCache:1\n\
+    \x03key\x18\xf5\x86\x03\x20\x01(\x08\x12\x1d.google.protobuf.FieldOption\
+    sR\x03key:5\n\x04path\x18\xee\x86\x03\x20\x01(\t\x12\x1f.google.protobuf\
+    .MessageOptionsR\x04path:L\n\x05cache\x18\xf2\x86\x03\x20\x01(\x0b2\x14.\
+    pandit.CacheOptions\x12\x1e.google.protobuf.MethodOptionsR\x05cache:L\n\
+    \x05error\x18\xf6\x86\x03\x20\x03(\x0b2\x14.pandit.ErrorMapping\x12\x1e.\
+    google.protobuf.MethodOptionsR\x05error:?\n\ntimeout_ms\x18\xf7\x86\x03\
+    \x20\x01(\x04\x12\x1e.google.protobuf.MethodOptionsR\ttimeoutMs:K\n\x10f\
+    orward_metadata\x18\xf8\x86\x03\x20\x03(\t\x12\x1e.google.protobuf.Metho\
+    dOptionsR\x0fforwardMetadata:G\n\x0ereturn_headers\x18\xf9\x86\x03\x20\
+    \x03(\t\x12\x1e.google.protobuf.MethodOptionsR\rreturnHeaders:5\n\x04nam\
+    e\x18\xda\x86\x03\x20\x01(\t\x12\x1f.google.protobuf.ServiceOptionsR\x04\
+    name:\\\n\rdefault_cache\x18\xf3\x86\x03\x20\x01(\x0b2\x14.pandit.CacheO\
+    ptions\x12\x1f.google.protobuf.ServiceOptionsR\x0cdefaultCache:[\n\x18de\
+    fault_forward_metadata\x18\xfa\x86\x03\x20\x03(\t\x12\x1f.google.protobu\
+    f.ServiceOptionsR\x16defaultForwardMetadata:W\n\x16default_return_header\
+    s\x18\xfb\x86\x03\x20\x03(\t\x12\x1f.google.protobuf.ServiceOptionsR\x14\
+    defaultReturnHeadersJ\x89\x12\n\x06\x12\x04\0\0>\x01\n\x08\n\x01\x0c\x12\
+    \x03\0\0\x12\n\t\n\x02\x03\0\x12\x03\x01\0*\n\x08\n\x01\x02\x12\x03\x03\
+    \0\x0f\n\n\n\x02\x04\0\x12\x04\x05\0\x08\x01\n\n\n\x03\x04\0\x01\x12\x03\
+    \x05\x08\x14\n\x0b\n\x04\x04\0\x02\0\x12\x03\x06\x02\x17\n\x0c\n\x05\x04\
+    \0\x02\0\x05\x12\x03\x06\x02\x06\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\x06\
+    \x07\x0e\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\x06\x11\x16\n\x0b\n\x04\x04\
+    \0\x02\x01\x12\x03\x07\x02\x1c\n\x0c\n\x05\x04\0\x02\x01\x05\x12\x03\x07\
+    \x02\x08\n\x0c\n\x05\x04\0\x02\x01\x01\x12\x03\x07\t\x13\n\x0c\n\x05\x04\
+    \0\x02\x01\x03\x12\x03\x07\x16\x1b\n\n\n\x02\x05\0\x12\x04\n\0\x1c\x01\n\
+    \n\n\x03\x05\0\x01\x12\x03\n\x05\x0f\n\x0b\n\x04\x05\0\x02\0\x12\x03\x0b\
+    \x02\t\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03\x0b\x02\x04\n\x0c\n\x05\x05\0\
+    \x02\0\x02\x12\x03\x0b\x07\x08\n\x0b\n\x04\x05\0\x02\x01\x12\x03\x0c\x02\
+    \x10\n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03\x0c\x02\x0b\n\x0c\n\x05\x05\0\
+    \x02\x01\x02\x12\x03\x0c\x0e\x0f\n\x0b\n\x04\x05\0\x02\x02\x12\x03\r\x02\
+    \x0e\n\x0c\n\x05\x05\0\x02\x02\x01\x12\x03\r\x02\t\n\x0c\n\x05\x05\0\x02\
+    \x02\x02\x12\x03\r\x0c\r\n\x0b\n\x04\x05\0\x02\x03\x12\x03\x0e\x02\x17\n\
+    \x0c\n\x05\x05\0\x02\x03\x01\x12\x03\x0e\x02\x12\n\x0c\n\x05\x05\0\x02\
+    \x03\x02\x12\x03\x0e\x15\x16\n\x0b\n\x04\x05\0\x02\x04\x12\x03\x0f\x02\
+    \x18\n\x0c\n\x05\x05\0\x02\x04\x01\x12\x03\x0f\x02\x13\n\x0c\n\x05\x05\0\
+    \x02\x04\x02\x12\x03\x0f\x16\x17\n\x0b\n\x04\x05\0\x02\x05\x12\x03\x10\
+    \x02\x10\n\x0c\n\x05\x05\0\x02\x05\x01\x12\x03\x10\x02\x0b\n\x0c\n\x05\
+    \x05\0\x02\x05\x02\x12\x03\x10\x0e\x0f\n\x0b\n\x04\x05\0\x02\x06\x12\x03\
+    \x11\x02\x15\n\x0c\n\x05\x05\0\x02\x06\x01\x12\x03\x11\x02\x10\n\x0c\n\
+    \x05\x05\0\x02\x06\x02\x12\x03\x11\x13\x14\n\x0b\n\x04\x05\0\x02\x07\x12\
+    \x03\x12\x02\x18\n\x0c\n\x05\x05\0\x02\x07\x01\x12\x03\x12\x02\x13\n\x0c\
+    \n\x05\x05\0\x02\x07\x02\x12\x03\x12\x16\x17\n\x0b\n\x04\x05\0\x02\x08\
+    \x12\x03\x13\x02\x19\n\x0c\n\x05\x05\0\x02\x08\x01\x12\x03\x13\x02\x14\n\
+    \x0c\n\x05\x05\0\x02\x08\x02\x12\x03\x13\x17\x18\n\x0b\n\x04\x05\0\x02\t\
+    \x12\x03\x14\x02\x1a\n\x0c\n\x05\x05\0\x02\t\x01\x12\x03\x14\x02\x15\n\
+    \x0c\n\x05\x05\0\x02\t\x02\x12\x03\x14\x18\x19\n\x0b\n\x04\x05\0\x02\n\
+    \x12\x03\x15\x02\x0f\n\x0c\n\x05\x05\0\x02\n\x01\x12\x03\x15\x02\t\n\x0c\
+    \n\x05\x05\0\x02\n\x02\x12\x03\x15\x0c\x0e\n\x0b\n\x04\x05\0\x02\x0b\x12\
+    \x03\x16\x02\x14\n\x0c\n\x05\x05\0\x02\x0b\x01\x12\x03\x16\x02\x0e\n\x0c\
+    \n\x05\x05\0\x02\x0b\x02\x12\x03\x16\x11\x13\n\x0b\n\x04\x05\0\x02\x0c\
+    \x12\x03\x17\x02\x15\n\x0c\n\x05\x05\0\x02\x0c\x01\x12\x03\x17\x02\x0f\n\
+    \x0c\n\x05\x05\0\x02\x0c\x02\x12\x03\x17\x12\x14\n\x0b\n\x04\x05\0\x02\r\
+    \x12\x03\x18\x02\x10\n\x0c\n\x05\x05\0\x02\r\x01\x12\x03\x18\x02\n\n\x0c\
+    \n\x05\x05\0\x02\r\x02\x12\x03\x18\r\x0f\n\x0b\n\x04\x05\0\x02\x0e\x12\
+    \x03\x19\x02\x13\n\x0c\n\x05\x05\0\x02\x0e\x01\x12\x03\x19\x02\r\n\x0c\n\
+    \x05\x05\0\x02\x0e\x02\x12\x03\x19\x10\x12\n\x0b\n\x04\x05\0\x02\x0f\x12\
+    \x03\x1a\x02\x11\n\x0c\n\x05\x05\0\x02\x0f\x01\x12\x03\x1a\x02\x0b\n\x0c\
+    \n\x05\x05\0\x02\x0f\x02\x12\x03\x1a\x0e\x10\n\x0b\n\x04\x05\0\x02\x10\
+    \x12\x03\x1b\x02\x17\n\x0c\n\x05\x05\0\x02\x10\x01\x12\x03\x1b\x02\x11\n\
+    \x0c\n\x05\x05\0\x02\x10\x02\x12\x03\x1b\x14\x16\n\\\n\x02\x04\x01\x12\
+    \x04\x1f\0\"\x01\x1aP\x20Maps\x20a\x20backend\x20error\x20code\x20(HTTP\
+    \x20status\x20or\x20Postgres\x20SQLSTATE)\x20to\x20a\x20gRPC\x20status.\
+    \n\n\n\n\x03\x04\x01\x01\x12\x03\x1f\x08\x14\n\x0b\n\x04\x04\x01\x02\0\
+    \x12\x03\x20\x02\x1e\n\x0c\n\x05\x04\x01\x02\0\x05\x12\x03\x20\x02\x08\n\
+    \x0c\n\x05\x04\x01\x02\0\x01\x12\x03\x20\t\x15\n\x0c\n\x05\x04\x01\x02\0\
+    \x03\x12\x03\x20\x18\x1d\n\x0b\n\x04\x04\x01\x02\x01\x12\x03!\x02\x1c\n\
+    \x0c\n\x05\x04\x01\x02\x01\x06\x12\x03!\x02\x0c\n\x0c\n\x05\x04\x01\x02\
+    \x01\x01\x12\x03!\r\x13\n\x0c\n\x05\x04\x01\x02\x01\x03\x12\x03!\x16\x1b\
+    \n\t\n\x01\x07\x12\x04$\0)\x01\n\t\n\x02\x07\0\x12\x03%\x02\x1f\n\n\n\
+    \x03\x07\0\x02\x12\x03$\x07#\n\n\n\x03\x07\0\x05\x12\x03%\x02\x08\n\n\n\
+    \x03\x07\0\x01\x12\x03%\t\x16\n\n\n\x03\x07\0\x03\x12\x03%\x19\x1e\n\t\n\
+    \x02\x07\x01\x12\x03&\x02\x1f\n\n\n\x03\x07\x01\x02\x12\x03$\x07#\n\n\n\
+    \x03\x07\x01\x05\x12\x03&\x02\x08\n\n\n\x03\x07\x01\x01\x12\x03&\t\x16\n\
+    \n\n\x03\x07\x01\x03\x12\x03&\x19\x1e\n\t\n\x02\x07\x02\x12\x03'\x02#\n\
+    \n\n\x03\x07\x02\x02\x12\x03$\x07#\n\n\n\x03\x07\x02\x06\x12\x03'\x02\
+    \x0e\n\n\n\x03\x07\x02\x01\x12\x03'\x0f\x1a\n\n\n\x03\x07\x02\x03\x12\
+    \x03'\x1d\"\n\t\n\x02\x07\x03\x12\x03(\x02\x13\n\n\n\x03\x07\x03\x02\x12\
+    \x03$\x07#\n\n\n\x03\x07\x03\x05\x12\x03(\x02\x06\n\n\n\x03\x07\x03\x01\
+    \x12\x03(\x07\n\n\n\n\x03\x07\x03\x03\x12\x03(\r\x12\n\x08\n\x01\x07\x12\
+    \x03+\0>\n\t\n\x02\x07\x04\x12\x03+(<\n\n\n\x03\x07\x04\x02\x12\x03+\x07\
+    %\n\n\n\x03\x07\x04\x05\x12\x03+(.\n\n\n\x03\x07\x04\x01\x12\x03+/3\n\n\
+    \n\x03\x07\x04\x03\x12\x03+6;\n\t\n\x01\x07\x12\x04-\06\x01\n\t\n\x02\
+    \x07\x05\x12\x03.\x02\x1d\n\n\n\x03\x07\x05\x02\x12\x03-\x07$\n\n\n\x03\
+    \x07\x05\x06\x12\x03.\x02\x0e\n\n\n\x03\x07\x05\x01\x12\x03.\x0f\x14\n\n\
+    \n\x03\x07\x05\x03\x12\x03.\x17\x1c\n\t\n\x02\x07\x06\x12\x03/\x02&\n\n\
+    \n\x03\x07\x06\x02\x12\x03-\x07$\n\n\n\x03\x07\x06\x04\x12\x03/\x02\n\n\
+    \n\n\x03\x07\x06\x06\x12\x03/\x0b\x17\n\n\n\x03\x07\x06\x01\x12\x03/\x18\
+    \x1d\n\n\n\x03\x07\x06\x03\x12\x03/\x20%\nH\n\x02\x07\x07\x12\x031\x02\
+    \x1c\x1a=\x20Backend\x20timeout\x20used\x20when\x20the\x20client\x20send\
+    s\x20no\x20grpc-timeout.\n\n\n\n\x03\x07\x07\x02\x12\x03-\x07$\n\n\n\x03\
+    \x07\x07\x05\x12\x031\x02\x08\n\n\n\x03\x07\x07\x01\x12\x031\t\x13\n\n\n\
+    \x03\x07\x07\x03\x12\x031\x16\x1b\n_\n\x02\x07\x08\x12\x033\x02+\x1aT\
+    \x20Request\x20metadata\x20keys\x20passed\x20to\x20the\x20backend,\x20as\
+    \x20HTTP\x20headers\x20or\x20Postgres\x20settings.\n\n\n\n\x03\x07\x08\
+    \x02\x12\x03-\x07$\n\n\n\x03\x07\x08\x04\x12\x033\x02\n\n\n\n\x03\x07\
+    \x08\x05\x12\x033\x0b\x11\n\n\n\x03\x07\x08\x01\x12\x033\x12\"\n\n\n\x03\
+    \x07\x08\x03\x12\x033%*\nJ\n\x02\x07\t\x12\x035\x02)\x1a?\x20Backend\x20\
+    response\x20headers\x20sent\x20back\x20to\x20the\x20client\x20as\x20trai\
+    lers.\n\n\n\n\x03\x07\t\x02\x12\x03-\x07$\n\n\n\x03\x07\t\x04\x12\x035\
+    \x02\n\n\n\n\x03\x07\t\x05\x12\x035\x0b\x11\n\n\n\x03\x07\t\x01\x12\x035\
+    \x12\x20\n\n\n\x03\x07\t\x03\x12\x035#(\n\t\n\x01\x07\x12\x048\0>\x01\n\
+    \t\n\x02\x07\n\x12\x039\x02\x16\n\n\n\x03\x07\n\x02\x12\x038\x07%\n\n\n\
+    \x03\x07\n\x05\x12\x039\x02\x08\n\n\n\x03\x07\n\x01\x12\x039\t\r\n\n\n\
+    \x03\x07\n\x03\x12\x039\x10\x15\n\t\n\x02\x07\x0b\x12\x03:\x02%\n\n\n\
+    \x03\x07\x0b\x02\x12\x038\x07%\n\n\n\x03\x07\x0b\x06\x12\x03:\x02\x0e\n\
+    \n\n\x03\x07\x0b\x01\x12\x03:\x0f\x1c\n\n\n\x03\x07\x0b\x03\x12\x03:\x1f\
+    $\nE\n\x02\x07\x0c\x12\x03<\x023\x1a:\x20Apply\x20to\x20every\x20method,\
+    \x20alongside\x20the\x20method's\x20own\x20lists.\n\n\n\n\x03\x07\x0c\
+    \x02\x12\x038\x07%\n\n\n\x03\x07\x0c\x04\x12\x03<\x02\n\n\n\n\x03\x07\
+    \x0c\x05\x12\x03<\x0b\x11\n\n\n\x03\x07\x0c\x01\x12\x03<\x12*\n\n\n\x03\
+    \x07\x0c\x03\x12\x03<-2\n\t\n\x02\x07\r\x12\x03=\x021\n\n\n\x03\x07\r\
+    \x02\x12\x038\x07%\n\n\n\x03\x07\r\x04\x12\x03=\x02\n\n\n\n\x03\x07\r\
+    \x05\x12\x03=\x0b\x11\n\n\n\x03\x07\r\x01\x12\x03=\x12(\n\n\n\x03\x07\r\
+    \x03\x12\x03=+0b\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -256,7 +591,9 @@ pub fn file_descriptor() -> ::protobuf::reflect::FileDescriptor {
         deps.push(::protobuf::descriptor::file_descriptor());
         let mut messages = ::std::vec::Vec::new();
         messages.push(CacheOptions::generated_message_descriptor_data());
+        messages.push(ErrorMapping::generated_message_descriptor_data());
         let mut enums = ::std::vec::Vec::new();
+        enums.push(StatusCode::generated_enum_descriptor_data());
         ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
             file_descriptor_proto(),
             deps,
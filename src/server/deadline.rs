@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use http::HeaderMap;
+use tokio::time::Instant;
+
+use crate::services::{Deadline, ServiceError, ServiceResult};
+
+/// Largest value allowed by the 8 digit `grpc-timeout` format.
+const MAX_TIMEOUT_VALUE: u64 = 99_999_999;
+
+/// Reads the call deadline from the `grpc-timeout` request header.
+pub fn from_headers(headers: &HeaderMap) -> ServiceResult<Deadline> {
+    let value = match headers.get("grpc-timeout") {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| ServiceError::InvalidArgument("invalid grpc-timeout header".to_string()))?;
+    Ok(Some(Instant::now() + parse_timeout(value)?))
+}
+
+/// Parses a `grpc-timeout` value such as `100m` or `5S`.
+pub fn parse_timeout(value: &str) -> ServiceResult<Duration> {
+    let invalid = || ServiceError::InvalidArgument(format!("invalid grpc-timeout: {}", value));
+    if value.len() < 2 || value.len() > 9 {
+        return Err(invalid());
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let timeout = match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(timeout)
+}
+
+/// Encodes the time left until a deadline as a `grpc-timeout` value.
+pub fn encode_timeout(deadline: Instant) -> String {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    let millis = remaining.as_millis() as u64;
+    if millis <= MAX_TIMEOUT_VALUE {
+        return format!("{}m", millis);
+    }
+    let secs = remaining.as_secs();
+    if secs <= MAX_TIMEOUT_VALUE {
+        return format!("{}S", secs);
+    }
+    format!("{}H", (secs / 3600).min(MAX_TIMEOUT_VALUE))
+}
+
+mod tests {
+    #[test]
+    fn test_parse_timeout() {
+        use super::*;
+        assert_eq!(parse_timeout("100m").unwrap(), Duration::from_millis(100));
+        assert_eq!(parse_timeout("5S").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_timeout("2H").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_timeout("10u").unwrap(), Duration::from_micros(10));
+        assert!(parse_timeout("m").is_err());
+        assert!(parse_timeout("10x").is_err());
+        assert!(parse_timeout("123456789m").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encode_timeout() {
+        use super::*;
+        let timeout = encode_timeout(Instant::now() + Duration::from_secs(2));
+        let timeout = parse_timeout(&timeout).unwrap();
+        assert!(timeout <= Duration::from_secs(2));
+        assert!(timeout > Duration::from_secs(1));
+        assert_eq!(encode_timeout(Instant::now()), "0m");
+    }
+}
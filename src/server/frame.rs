@@ -0,0 +1,268 @@
+use std::convert::TryInto;
+use std::io::{Read, Write};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use flate2::{read, write, Compression};
+use http::HeaderMap;
+
+use crate::services::{ServiceError, ServiceResult};
+
+/// Length of the gRPC message prefix: compressed flag and big endian length.
+pub const HEADER_LEN: usize = 5;
+
+/// Default maximum message size, matching the gRPC default of 4MiB.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Encodings this node can decode, sent as `grpc-accept-encoding`.
+pub const ACCEPT_ENCODING: &str = "gzip,deflate";
+
+/// Message compression named by `grpc-encoding`, identity being `None`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> ServiceResult<Option<Self>> {
+        match name.trim() {
+            "" | "identity" => Ok(None),
+            "gzip" => Ok(Some(Encoding::Gzip)),
+            "deflate" => Ok(Some(Encoding::Deflate)),
+            name => Err(ServiceError::Unimplemented(format!(
+                "unsupported grpc-encoding: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Reads the encoding of incoming messages from `grpc-encoding`.
+    pub fn from_headers(headers: &HeaderMap) -> ServiceResult<Option<Self>> {
+        match headers.get("grpc-encoding") {
+            Some(name) => Self::from_name(name.to_str().unwrap_or_default()),
+            None => Ok(None),
+        }
+    }
+
+    /// Picks the first encoding listed in `grpc-accept-encoding` that this node supports.
+    pub fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let accept = headers.get("grpc-accept-encoding")?.to_str().ok()?;
+        accept
+            .split(',')
+            .find_map(|name| Self::from_name(name).ok().flatten())
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    fn compress(&self, data: &[u8]) -> ServiceResult<Vec<u8>> {
+        let out = match self {
+            Encoding::Gzip => {
+                let mut encoder = write::GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            // gRPC deflate is the zlib format.
+            Encoding::Deflate => {
+                let mut encoder = write::ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+        };
+        Ok(out)
+    }
+
+    fn decompress(&self, data: &[u8], max_message_size: usize) -> ServiceResult<Vec<u8>> {
+        let mut out = Vec::new();
+        // Read one byte past the limit to catch oversized messages.
+        let limit = max_message_size as u64 + 1;
+        match self {
+            Encoding::Gzip => read::GzDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut out)?,
+            Encoding::Deflate => read::ZlibDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut out)?,
+        };
+        if out.len() > max_message_size {
+            return Err(ServiceError::ResourceExhausted(format!(
+                "decompressed message exceeds maximum of {} bytes",
+                max_message_size
+            )));
+        }
+        Ok(out)
+    }
+}
+
+/// Incremental decoder for length-prefixed gRPC messages.
+///
+/// Data is buffered across HTTP/2 DATA frames until the declared length of a
+/// message arrives. Decoded messages keep their 5 byte prefix, and compressed
+/// messages are returned decompressed.
+pub struct FrameDecoder {
+    buf: BytesMut,
+    max_message_size: usize,
+    encoding: Option<Encoding>,
+}
+
+impl FrameDecoder {
+    pub fn new(max_message_size: usize) -> Self {
+        Self {
+            buf: BytesMut::new(),
+            max_message_size,
+            encoding: None,
+        }
+    }
+
+    /// Sets the `grpc-encoding` of the stream, used for compressed messages.
+    pub fn with_encoding(mut self, encoding: Option<Encoding>) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Pops the next complete message, if one has fully arrived.
+    pub fn next_message(&mut self) -> ServiceResult<Option<Bytes>> {
+        if self.buf.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let compressed = match self.buf[0] {
+            0 => false,
+            1 => true,
+            flag => {
+                return Err(ServiceError::Internal(format!(
+                    "invalid compressed flag: {}",
+                    flag
+                )))
+            }
+        };
+        let len = u32::from_be_bytes(self.buf[1..HEADER_LEN].try_into().unwrap()) as usize;
+        if len > self.max_message_size {
+            return Err(ServiceError::ResourceExhausted(format!(
+                "message of {} bytes exceeds maximum of {} bytes",
+                len, self.max_message_size
+            )));
+        }
+        if self.buf.len() < HEADER_LEN + len {
+            return Ok(None);
+        }
+        let message = self.buf.split_to(HEADER_LEN + len).freeze();
+        if !compressed {
+            return Ok(Some(message));
+        }
+        let encoding = self.encoding.ok_or(ServiceError::Internal(
+            "compressed message without grpc-encoding".to_string(),
+        ))?;
+        let message = encoding.decompress(&message[HEADER_LEN..], self.max_message_size)?;
+        Ok(Some(encode(&message[..])))
+    }
+
+    /// Checks no partial message is left once the stream has ended.
+    pub fn finish(&self) -> ServiceResult<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        Err(ServiceError::Internal(format!(
+            "stream ended with {} bytes of a truncated message",
+            self.buf.len()
+        )))
+    }
+}
+
+/// Prefixes an encoded message with an uncompressed gRPC header.
+pub fn encode(message: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(HEADER_LEN + message.len());
+    buf.put_u8(0);
+    buf.put_u32(message.len() as u32);
+    buf.extend_from_slice(message);
+    buf.freeze()
+}
+
+/// Compresses a framed message, setting its compressed flag.
+pub fn compress(message: Bytes, encoding: Option<Encoding>) -> ServiceResult<Bytes> {
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => return Ok(message),
+    };
+    let compressed = encoding.compress(&message[HEADER_LEN..])?;
+    let mut buf = BytesMut::with_capacity(HEADER_LEN + compressed.len());
+    buf.put_u8(1);
+    buf.put_u32(compressed.len() as u32);
+    buf.extend_from_slice(&compressed[..]);
+    Ok(buf.freeze())
+}
+
+mod tests {
+    #[test]
+    fn test_decode_across_frames() {
+        use super::*;
+        let mut decoder = FrameDecoder::new(DEFAULT_MAX_MESSAGE_SIZE);
+        decoder.push(&[0, 0, 0, 0, 3, 0x08]);
+        assert!(decoder.next_message().unwrap().is_none());
+        decoder.push(&[0x96, 0x01, 0, 0, 0, 0, 2, 0x08, 0x01]);
+        assert_eq!(
+            decoder.next_message().unwrap().unwrap(),
+            Bytes::from_static(&[0, 0, 0, 0, 3, 0x08, 0x96, 0x01])
+        );
+        assert_eq!(
+            decoder.next_message().unwrap().unwrap(),
+            Bytes::from_static(&[0, 0, 0, 0, 2, 0x08, 0x01])
+        );
+        assert!(decoder.next_message().unwrap().is_none());
+        assert!(decoder.finish().is_ok());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        use super::*;
+        use crate::services::base::StatusCode;
+        let mut decoder = FrameDecoder::new(2);
+        decoder.push(&[0, 0, 0, 0, 3]);
+        assert_eq!(
+            decoder.next_message().unwrap_err().status(),
+            StatusCode::RESOURCE_EXHAUSTED
+        );
+
+        let mut decoder = FrameDecoder::new(DEFAULT_MAX_MESSAGE_SIZE);
+        decoder.push(&[0, 0, 0, 0, 3, 0x08]);
+        assert!(decoder.next_message().unwrap().is_none());
+        assert_eq!(decoder.finish().unwrap_err().status(), StatusCode::INTERNAL);
+    }
+
+    #[test]
+    fn test_compressed_messages() {
+        use super::*;
+        let message = encode(&[0x08, 0x96, 0x01]);
+        for encoding in &[Encoding::Gzip, Encoding::Deflate] {
+            let compressed = compress(message.clone(), Some(*encoding)).unwrap();
+            assert_eq!(compressed[0], 1);
+
+            let mut decoder =
+                FrameDecoder::new(DEFAULT_MAX_MESSAGE_SIZE).with_encoding(Some(*encoding));
+            decoder.push(&compressed[..]);
+            assert_eq!(decoder.next_message().unwrap().unwrap(), message);
+
+            let mut decoder = FrameDecoder::new(2).with_encoding(Some(*encoding));
+            decoder.push(&compressed[..]);
+            assert!(decoder.next_message().is_err());
+        }
+
+        let mut decoder = FrameDecoder::new(DEFAULT_MAX_MESSAGE_SIZE);
+        decoder.push(&compress(message, Some(Encoding::Gzip)).unwrap()[..]);
+        assert_eq!(
+            decoder.next_message().unwrap_err().status(),
+            crate::services::base::StatusCode::INTERNAL
+        );
+        assert_eq!(
+            Encoding::from_name("snappy").unwrap_err().status(),
+            crate::services::base::StatusCode::UNIMPLEMENTED
+        );
+    }
+}
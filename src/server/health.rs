@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
+use protobuf::{CodedInputStream, CodedOutputStream};
+use tokio::sync::{watch, RwLock};
+use tokio::time::Instant;
+
+use super::frame;
+use crate::services::{with_deadline, MessageStream, ServiceError, ServiceResult, Services};
+
+pub const CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+
+pub const WATCH_PATH: &str = "/grpc.health.v1.Health/Watch";
+
+/// Default interval between backend probes while a client watches a service.
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `grpc.health.v1.HealthCheckResponse.ServingStatus`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServingStatus {
+    Unknown = 0,
+    Serving = 1,
+    NotServing = 2,
+    ServiceUnknown = 3,
+}
+
+/// Answers `Check` for the service named in the request.
+pub async fn check(services: &RwLock<Services>, data: &[Bytes]) -> ServiceResult<MessageStream> {
+    let service = parse_request(data)?;
+    let status = service_status(services, &service)
+        .await
+        .ok_or(ServiceError::NotFound(format!(
+            "unknown service: {}",
+            service
+        )))?;
+    Ok(stream::iter(vec![encode_response(status)]).boxed())
+}
+
+/// Answers `Watch`, sending the status now and again whenever a probe sees it change.
+///
+/// Once `shutdown` turns true the stream sends `NOT_SERVING` and ends, so watches don't hold
+/// up draining.
+pub fn watch(
+    services: std::sync::Arc<RwLock<Services>>,
+    data: &[Bytes],
+    interval: Duration,
+    shutdown: watch::Receiver<bool>,
+) -> ServiceResult<MessageStream> {
+    let service = parse_request(data)?;
+    let resp = stream::unfold(
+        Some((services, service, None, shutdown)),
+        move |state| async move {
+            let (services, service, last, mut shutdown) = state?;
+            let mut first = last.is_none();
+            loop {
+                let mut closed = false;
+                if !first {
+                    tokio::select! {
+                        _ = tokio::time::sleep(interval) => {}
+                        changed = shutdown.changed() => closed = changed.is_err(),
+                    }
+                }
+                first = false;
+                if closed || *shutdown.borrow() {
+                    return Some((encode_response(ServingStatus::NotServing), None));
+                }
+                let status = service_status(&services, &service)
+                    .await
+                    .unwrap_or(ServingStatus::ServiceUnknown);
+                if Some(status) != last {
+                    let resp = encode_response(status);
+                    return Some((resp, Some((services, service, Some(status), shutdown))));
+                }
+            }
+        },
+    );
+    Ok(resp.boxed())
+}
+
+/// Probes the backend of a service on this node, `None` if the service is not here.
+pub async fn service_status(services: &RwLock<Services>, name: &str) -> Option<ServingStatus> {
+    // An empty name asks after the server as a whole.
+    if name.is_empty() {
+        return Some(ServingStatus::Serving);
+    }
+    let name = name.rsplit('.').next().unwrap_or_default();
+    // Neither the services nor the writer stay locked while the backend is probed.
+    let probe = {
+        let services = services.read().await;
+        let service = services.get(name)?;
+        let writer = service.writer.lock().await;
+        writer.probe()
+    };
+    let deadline = Some(Instant::now() + PROBE_TIMEOUT);
+    match with_deadline(deadline, probe).await {
+        Ok(_) => Some(ServingStatus::Serving),
+        Err(err) => {
+            log::error!("health probe failed for {}: {}", name, err);
+            Some(ServingStatus::NotServing)
+        }
+    }
+}
+
+/// Reads the service name from a single `HealthCheckRequest`.
+fn parse_request(data: &[Bytes]) -> ServiceResult<String> {
+    let message = match data {
+        [message] => &message[frame::HEADER_LEN..],
+        _ => {
+            return Err(ServiceError::InvalidArgument(
+                "expected a single health check request".to_string(),
+            ))
+        }
+    };
+    let mut input = CodedInputStream::from_bytes(message);
+    let mut service = String::new();
+    let mut skipped = protobuf::UnknownFields::new();
+    while !input.eof()? {
+        match input.read_tag_unpack()? {
+            (1, protobuf::wire_format::WireTypeLengthDelimited) => service = input.read_string()?,
+            (field, wire_type) => {
+                protobuf::rt::read_unknown_or_skip_group(
+                    field,
+                    wire_type,
+                    &mut input,
+                    &mut skipped,
+                )?;
+            }
+        }
+    }
+    Ok(service)
+}
+
+/// Encodes a framed `HealthCheckResponse`.
+fn encode_response(status: ServingStatus) -> ServiceResult<Bytes> {
+    let mut buf = Vec::new();
+    {
+        let mut output = CodedOutputStream::vec(&mut buf);
+        if status != ServingStatus::Unknown {
+            output.write_enum(1, status as i32)?;
+        }
+        output.flush()?;
+    }
+    Ok(frame::encode(&buf[..]))
+}
+
+mod tests {
+    #[test]
+    fn test_parse_request() {
+        use super::*;
+        let buf = Bytes::from_static(&[
+            0, 0, 0, 0, 6, // gRPC header.
+            0x0a, 0x04, b'h', b't', b't', b'p', // service
+        ]);
+        assert_eq!(parse_request(&[buf]).unwrap(), "http");
+        assert!(parse_request(&[]).is_err());
+    }
+
+    #[test]
+    fn test_encode_response() {
+        use super::*;
+        assert_eq!(
+            encode_response(ServingStatus::NotServing).unwrap(),
+            Bytes::from_static(&[0, 0, 0, 0, 2, 0x08, 0x02])
+        );
+        assert_eq!(
+            encode_response(ServingStatus::Unknown).unwrap(),
+            Bytes::from_static(&[0, 0, 0, 0, 0])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_service_status() {
+        use super::*;
+        let services = RwLock::new(Services::default());
+        assert_eq!(
+            service_status(&services, "").await,
+            Some(ServingStatus::Serving)
+        );
+        assert_eq!(service_status(&services, "pkg.Missing").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_watch_ends_on_shutdown() {
+        use super::*;
+        let services = std::sync::Arc::new(RwLock::new(Services::default()));
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let request = Bytes::from_static(&[0, 0, 0, 0, 0]);
+        let interval = Duration::from_secs(3600);
+        let mut resp = watch(services, &[request], interval, shutdown_rx).unwrap();
+        assert_eq!(
+            resp.next().await.unwrap().unwrap(),
+            encode_response(ServingStatus::Serving).unwrap()
+        );
+        shutdown_tx.send(true).unwrap();
+        assert_eq!(
+            resp.next().await.unwrap().unwrap(),
+            encode_response(ServingStatus::NotServing).unwrap()
+        );
+        assert!(resp.next().await.is_none());
+    }
+}
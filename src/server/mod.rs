@@ -1,39 +1,98 @@
 #![feature(destructuring_assignment)]
+pub mod deadline;
+pub mod frame;
+pub mod health;
+pub mod reflection;
+pub mod tls;
+pub mod web;
+
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::sync::Arc;
+use std::time::Duration;
 
+use self::frame::{Encoding, FrameDecoder};
+use self::reflection::DescriptorPool;
+use self::tls::{ClusterConfig, TlsAcceptor, TlsConfig, HANDSHAKE_TIMEOUT};
 use crate::broker::{Broker, RemoteSender};
-use crate::services::{self, Sender, Service, ServiceError};
+use crate::services::{
+    self, with_deadline, Deadline, MessageStream, Metadata, Sender, ServiceError, ServiceResult,
+};
 use async_trait::async_trait;
 use bytes::Bytes;
-use dashmap::mapref::one::RefMut;
-use dashmap::DashMap;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use futures::Future;
 use h2::server::{self, SendResponse};
 use h2::RecvStream;
 use http::{HeaderMap, HeaderValue, Request};
-use protobuf::Message;
+use protobuf::{Message, ProtobufEnum};
 use std::collections::HashMap;
 use tokio;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::signal::ctrl_c;
-use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::sync::{watch, RwLock, Semaphore};
 use tonic;
 
+/// Default limit on client connections served at once.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+
+/// Default limit on streams open at once on a single connection.
+pub const DEFAULT_MAX_CONCURRENT_STREAMS: u32 = 100;
+
+/// Default time given to in-flight streams to finish on shutdown.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Header marking a request delegated by another pandit node, which is never delegated again.
+pub const DELEGATED_HEADER: &str = "pandit-delegated";
+
+/// Longest error message sent in `grpc-message`, as backend error bodies can be large.
+const MAX_STATUS_MESSAGE_LEN: usize = 1024;
+
 #[async_trait]
-pub trait Server {
-    async fn run(&self, addr: String) -> Result<(), Box<dyn std::error::Error>> {
+pub trait Server: Send + Sync + 'static {
+    /// Accepts connections until Ctrl-C, then drains the open ones.
+    async fn run(self: Arc<Self>, addr: String) -> Result<(), Box<dyn std::error::Error>> {
         log::info!("gRPC server starting on: {}...", addr);
         let listener = TcpListener::bind(addr.clone()).await?;
         log::info!("gRPC server listening on: {}", addr);
+        let max_connections = self.max_connections();
+        let connections = Arc::new(Semaphore::new(max_connections));
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let reload = self.tls().map(|tls| tokio::spawn(tls.watch()));
         loop {
-            tokio::select! {
-                _ = ctrl_c() => {
-                    return Ok(());
+            // Hold off accepting until a connection slot is free.
+            let permit = tokio::select! {
+                _ = ctrl_c() => break,
+                permit = connections.clone().acquire_owned() => permit?,
+            };
+            let (socket, saddr) = tokio::select! {
+                _ = ctrl_c() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        log::error!("error accepting connection: {}", e);
+                        continue;
+                    }
                 },
-               Ok((socket, saddr)) = listener.accept() => {
-                match self.serve(socket).await {
+            };
+            let server = self.clone();
+            let shutdown = shutdown_rx.clone();
+            let tls = self.tls();
+            tokio::spawn(async move {
+                let result = match tls {
+                    // Bound the handshake, as a silent client would otherwise hold its
+                    // connection slot indefinitely.
+                    Some(tls) => {
+                        match tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.accept(socket)).await {
+                            Ok(Ok((stream, node))) => server.serve(stream, node, shutdown).await,
+                            Ok(Err(err)) => Err(err.into()),
+                            Err(_) => Err("TLS handshake timed out".into()),
+                        }
+                    }
+                    None => server.serve(socket, false, shutdown).await,
+                };
+                match result {
                     Ok(_) => {}
                     Err(e) => {
                         log::error!(
@@ -43,82 +102,264 @@ pub trait Server {
                         );
                     }
                 }
-               }
-            }
+                drop(permit);
+            });
         }
+
+        if let Some(reload) = reload {
+            reload.abort();
+        }
+        log::info!("gRPC server draining connections...");
+        let _ = shutdown_tx.send(true);
+        // Every permit is back once all connections have closed.
+        let drained = tokio::time::timeout(
+            self.drain_timeout(),
+            connections.acquire_many(max_connections as u32),
+        );
+        if drained.await.is_err() {
+            log::error!("timed out draining connections");
+        }
+        Ok(())
     }
-    async fn serve(&self, socket: TcpStream) -> Result<(), Box<dyn Error + Send + Sync + '_>>;
+
+    /// Serves one connection, finishing open streams once `shutdown` turns true.
+    ///
+    /// `node` is set when the peer presented a pandit node certificate.
+    async fn serve<S>(
+        &self,
+        socket: S,
+        node: bool,
+        shutdown: watch::Receiver<bool>,
+    ) -> Result<(), Box<dyn Error + Send + Sync + '_>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    /// Terminates TLS on accepted connections when set, otherwise they are served in plaintext.
+    fn tls(&self) -> Option<Arc<TlsAcceptor>>;
+
+    fn max_connections(&self) -> usize;
+
+    fn drain_timeout(&self) -> Duration;
 }
 
 pub struct IntraServer {
     services: Arc<RwLock<services::Services>>,
     broker: Arc<Broker>,
+    max_message_size: usize,
+    health_interval: Duration,
+    max_connections: usize,
+    max_concurrent_streams: u32,
+    drain_timeout: Duration,
+    tls: Option<Arc<TlsAcceptor>>,
+    /// Whether delegated requests are only served to peers with a node certificate.
+    cluster_auth: bool,
 }
 
 #[async_trait]
 impl Server for IntraServer {
-    async fn serve(&self, socket: TcpStream) -> Result<(), Box<dyn Error + Send + Sync + '_>> {
-        let mut conn = server::handshake(socket).await?;
-        while let Some(result) = conn.accept().await {
-            let (req, mut send_resp) = result?;
+    async fn serve<S>(
+        &self,
+        socket: S,
+        node: bool,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> Result<(), Box<dyn Error + Send + Sync + '_>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut conn = server::Builder::new()
+            .max_concurrent_streams(self.max_concurrent_streams)
+            .handshake(socket)
+            .await?;
+        let mut draining = false;
+        loop {
+            let result = tokio::select! {
+                result = conn.accept() => result,
+                _ = shutdown.changed(), if !draining => {
+                    // Refuse new streams, accept keeps driving the open ones to completion.
+                    conn.graceful_shutdown();
+                    draining = true;
+                    continue;
+                }
+            };
+            let (mut req, mut send_resp) = match result {
+                Some(result) => result?,
+                None => break,
+            };
             let services = self.services.clone();
             let broker = self.broker.clone();
+            let max_message_size = self.max_message_size;
+            let health_interval = self.health_interval;
+            let trusted = node || !self.cluster_auth;
+            let stream_shutdown = shutdown.clone();
             tokio::spawn(async move {
-                let response = http::Response::new(());
-                let mut trailers = HeaderMap::new();
-                let services = services.read().await;
-                let resp_raw = IntraServer::handle_request(services, req, broker).await;
+                if req.uri().path() == reflection::PATH {
+                    let pool = IntraServer::descriptor_pool(&services, &broker).await;
+                    IntraServer::serve_reflection(pool, req, send_resp, max_message_size).await;
+                    return;
+                }
+                let encoding = Encoding::negotiate(req.headers());
+                let response = grpc_response(encoding);
+                let path = req.uri().path().to_string();
+                // A delegated request is never sent on to a third node.
+                let delegated = node || req.headers().contains_key(DELEGATED_HEADER);
+                let metadata = Metadata::new(req.headers().clone());
+                let resp_raw = match path.as_str() {
+                    health::CHECK_PATH => match read_body(&mut req, max_message_size).await {
+                        Ok(data) => health::check(&services, &data[..]).await,
+                        Err(err) => Err(err),
+                    },
+                    health::WATCH_PATH => match read_body(&mut req, max_message_size).await {
+                        Ok(data) => {
+                            health::watch(services, &data[..], health_interval, stream_shutdown)
+                        }
+                        Err(err) => Err(err),
+                    },
+                    _ if delegated && !trusted => Err(ServiceError::PermissionDenied(
+                        "delegated requests are only served to pandit nodes".to_string(),
+                    )),
+                    _ => match read_request(req, max_message_size) {
+                        Ok((deadline, data)) => {
+                            IntraServer::handle_request(
+                                &services, &path, deadline, data, &metadata, broker, delegated,
+                            )
+                            .await
+                        }
+                        Err(err) => Err(err),
+                    },
+                };
 
                 let mut send = send_resp.send_response(response, false).unwrap();
-                match resp_raw {
-                    Ok(payload) => {
-                        let mut payload = payload.to_vec();
-                        let payload_len: u32 = (payload.len() - 5).try_into().unwrap();
-
-                        // Write payload length to gRPC header.
-                        let grpc_header = payload_len.to_be_bytes();
-                        let mut grpc_header = grpc_header.iter();
-                        let header = payload[1..5].as_mut();
-                        for byte in header.iter_mut() {
-                            *byte = *grpc_header.next().unwrap();
+                let mut trailers = match resp_raw {
+                    Ok(mut messages) => {
+                        let mut trailers = status_trailers(None);
+                        // Frame each message as its own data frame.
+                        loop {
+                            // Stop waiting on the response once the client goes away.
+                            let reset = futures::future::poll_fn(|cx| send.poll_reset(cx));
+                            let message = tokio::select! {
+                                message = messages.next() => message,
+                                _ = reset => {
+                                    log::info!("client reset stream");
+                                    return;
+                                }
+                            };
+                            let message =
+                                message.map(|m| m.and_then(|m| frame::compress(m, encoding)));
+                            let message = match message {
+                                Some(Ok(message)) => message,
+                                None => break,
+                                Some(Err(err)) => {
+                                    log::error!("error occured streaming response: {}", err);
+                                    trailers = status_trailers(Some(&err));
+                                    break;
+                                }
+                            };
+                            if let Err(err) = send.send_data(message, false) {
+                                log::error!("error sending message to client: {}", err);
+                                return;
+                            }
                         }
-
-                        trailers.insert("grpc-status", HeaderValue::from(0i16));
-                        send.send_data(Bytes::copy_from_slice(&payload[..]), false)
-                            .unwrap();
+                        trailers
                     }
                     Err(err) => {
-                        log::error!("error occured handling request: {:?}", err);
-                        trailers.insert("grpc-status", HeaderValue::from(13i16));
-                        trailers.insert(
-                            "grpc-message",
-                            HeaderValue::try_from(format!(
-                                "an internal error occurred in pandit: {:?}",
-                                err
-                            ))
-                            .unwrap(),
-                        );
+                        log::error!("error occured handling request: {}", err);
+                        status_trailers(Some(&err))
                     }
-                }
+                };
+                // Backend headers picked for the client are known once the response is complete.
+                trailers.extend(metadata.response());
                 send.send_trailers(trailers).unwrap();
             });
         }
         Ok(())
     }
+
+    fn tls(&self) -> Option<Arc<TlsAcceptor>> {
+        self.tls.clone()
+    }
+
+    fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    fn drain_timeout(&self) -> Duration {
+        self.drain_timeout
+    }
 }
 
 impl IntraServer {
-    pub fn new(broker: Arc<Broker>) -> Self {
-        Self {
+    pub fn new(broker: Arc<Broker>, cfg: &config::Config) -> ServiceResult<Self> {
+        let max_message_size = cfg
+            .get_int("server.max_message_size")
+            .map(|size| size as usize)
+            .unwrap_or(frame::DEFAULT_MAX_MESSAGE_SIZE);
+        let health_interval = cfg
+            .get_int("server.health_interval_ms")
+            .map(|ms| Duration::from_millis(ms as u64))
+            .unwrap_or(health::DEFAULT_WATCH_INTERVAL);
+        let max_connections = cfg
+            .get_int("server.max_connections")
+            .map(|max| max as usize)
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS);
+        let max_concurrent_streams = cfg
+            .get_int("server.max_concurrent_streams")
+            .map(|max| max as u32)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_STREAMS);
+        let drain_timeout = cfg
+            .get_int("server.drain_timeout_ms")
+            .map(|ms| Duration::from_millis(ms as u64))
+            .unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+        let cluster = ClusterConfig::from_config(cfg)?;
+        let tls = match (TlsConfig::from_config(cfg, "server.tls")?, &cluster) {
+            (Some(mut config), Some(cluster)) => {
+                config.cluster_ca = Some(cluster.ca.clone());
+                Some(config)
+            }
+            (Some(config), None) => Some(config),
+            (None, Some(cluster)) => Some(cluster.server_config()),
+            (None, None) => None,
+        };
+        let tls = match tls {
+            Some(config) => Some(Arc::new(TlsAcceptor::new(config)?)),
+            None => None,
+        };
+        Ok(Self {
             services: Arc::new(RwLock::new(services::Services::default())),
             broker,
-        }
+            max_message_size,
+            health_interval,
+            max_connections,
+            max_concurrent_streams,
+            drain_timeout,
+            tls,
+            cluster_auth: cluster.is_some(),
+        })
     }
 
-    pub async fn add_servivce(&self, name: String, service: services::Service) {
-        log::info!("adding service to intra-server: {}", &name);
+    /// Publishes and adds services, unless one of them is already running on this node or,
+    /// when `unique`, on another node.
+    pub async fn add_services(
+        &self,
+        new_services: Vec<(String, services::Service)>,
+        unique: bool,
+    ) -> ServiceResult<()> {
         let services = self.services.write().await;
-        services.insert(name.clone(), service);
+        for (name, _) in new_services.iter() {
+            if services.contains_key(name)
+                || (unique && self.broker.is_served_elsewhere(name).await?)
+            {
+                return Err(ServiceError::AlreadyExists(format!(
+                    "service {} is already running",
+                    name
+                )));
+            }
+        }
+        for (name, service) in new_services {
+            log::info!("adding service to intra-server: {}", &name);
+            self.broker.publish_service(&name, &service)?;
+            services.insert(name, Arc::new(service));
+        }
+        Ok(())
     }
 
     pub async fn remove_service(&self, name: &String) {
@@ -132,24 +373,74 @@ impl IntraServer {
         services.contains_key(name)
     }
 
-    async fn handle_request(
-        services: RwLockReadGuard<'_, DashMap<String, Service>>,
+    /// Collects the descriptors of local services and of those published by other nodes.
+    ///
+    /// Other nodes are asked once the services are no longer locked.
+    async fn descriptor_pool(
+        services: &RwLock<services::Services>,
+        broker: &Broker,
+    ) -> DescriptorPool {
+        let mut pool = DescriptorPool::new();
+        for service in services.read().await.iter() {
+            pool.add_service(service.key(), &service.descriptors[..]);
+        }
+        match broker.service_descriptors().await {
+            Ok(remote) => {
+                for (name, files) in remote {
+                    pool.add_service(&name, &files[..]);
+                }
+            }
+            Err(err) => log::error!("unable to load descriptors from other nodes: {}", err),
+        }
+        pool
+    }
+
+    async fn serve_reflection(
+        pool: DescriptorPool,
         mut request: Request<RecvStream>,
-        broker: Arc<Broker>,
-    ) -> Result<Bytes, Box<dyn Error>> {
+        mut send_resp: SendResponse<Bytes>,
+        max_message_size: usize,
+    ) {
+        let encoding = Encoding::negotiate(request.headers());
+        let mut send = match send_resp.send_response(grpc_response(encoding), false) {
+            Ok(send) => send,
+            Err(err) => {
+                log::error!("error sending reflection response: {}", err);
+                return;
+            }
+        };
+        let request_encoding = Encoding::from_headers(request.headers());
         let body = request.body_mut();
-        let data: Vec<u8> = body
-            .data()
-            .await
-            .iter()
-            .map(|data| {
-                let data = data.as_ref().unwrap();
-                let _ = body.flow_control().release_capacity(data.len());
-                data.to_vec()
-            })
-            .flatten()
-            .collect();
-        let path = request.uri().to_string();
+        // Answer each request as it arrives, clients wait on a response before the next.
+        let result: ServiceResult<()> = async {
+            let mut decoder = FrameDecoder::new(max_message_size).with_encoding(request_encoding?);
+            while let Some(chunk) = body.data().await {
+                let chunk = chunk?;
+                let _ = body.flow_control().release_capacity(chunk.len());
+                decoder.push(&chunk[..]);
+                while let Some(message) = decoder.next_message()? {
+                    let resp = pool.respond(&message[frame::HEADER_LEN..])?;
+                    send.send_data(frame::compress(frame::encode(&resp[..]), encoding)?, false)?;
+                }
+            }
+            decoder.finish()
+        }
+        .await;
+        if let Err(err) = &result {
+            log::error!("error occured serving reflection: {}", err);
+        }
+        let _ = send.send_trailers(status_trailers(result.err().as_ref()));
+    }
+
+    async fn handle_request(
+        services: &RwLock<services::Services>,
+        path: &str,
+        deadline: Deadline,
+        data: MessageStream,
+        metadata: &Metadata,
+        broker: Arc<Broker>,
+        delegated: bool,
+    ) -> ServiceResult<MessageStream> {
         let mut path = path.rsplit("/");
         let method = path.next().unwrap();
         let service = {
@@ -160,7 +451,20 @@ impl IntraServer {
         let method_name = method.to_string();
         log::info!("request for {}_{}", &service_name, &method_name);
 
-        // subscribe for future cache.
+        // The service is taken out of the map, which is not held while the call runs.
+        let local = services
+            .read()
+            .await
+            .get(service)
+            .map(|service| service.value().clone());
+        if delegated && local.is_none() {
+            return Err(ServiceError::NotFound(format!(
+                "service {} is not on this node",
+                &service_name
+            )));
+        }
+
+        // subscribe for future cache, which also tells how a remote method streams.
         if !broker.is_subbed(&service_name, &method_name) {
             log::info!(
                 "subscribing to cache for {}_{}",
@@ -169,13 +473,23 @@ impl IntraServer {
             );
             broker.sub_service(&service_name, &method_name).await?;
         }
-        let mut _remote_sender: RemoteSender;
-        let mut _service: RefMut<String, Service>;
-        let service: &mut dyn Sender = match services.get_mut(service) {
+
+        // Bidirectional requests are passed on as they arrive, the rest are read whole
+        // before the call is made.
+        let bidi = match &local {
+            Some(s) => s.is_bidi(&method_name),
+            None => broker.is_bidi(&service_name, &method_name),
+        };
+        let (stream, data) = match bidi {
+            true => (Some(data), Vec::new()),
+            false => (None, with_deadline(deadline, data.try_collect()).await?),
+        };
+
+        let _remote_sender: RemoteSender;
+        let service: &dyn Sender = match &local {
             Some(s) => {
                 log::info!("found service {} on this node", &service_name);
-                _service = s;
-                _service.value_mut()
+                s.as_ref()
             }
             None => {
                 log::info!(
@@ -184,25 +498,161 @@ impl IntraServer {
                 );
                 // send to other node.
                 _remote_sender = broker.get_remote_sender(&service_name)?;
-                &mut _remote_sender
+                &_remote_sender
             }
         };
 
+        if let Some(data) = stream {
+            return service
+                .send_stream(&service_name, &method_name, data, deadline, metadata)
+                .await;
+        }
+
         // Probe cache for in date cached data.
-        if let Some(cached_data) = service
-            .probe_cache(&service_name, &method_name, &data[..])
-            .await?
-        {
-            log::info!("found cache hit for {}_{}", &service_name, &method_name);
-            return Ok(cached_data);
+        if data.len() == 1 {
+            if let Some(cached_data) = service
+                .probe_cache(&service_name, &method_name, &data[0][..])
+                .await?
+            {
+                log::info!("found cache hit for {}_{}", &service_name, &method_name);
+                return Ok(stream::iter(vec![Ok(cached_data)]).boxed());
+            }
+        }
+
+        service
+            .send(&service_name, &method_name, &data[..], deadline, metadata)
+            .await
+    }
+}
+
+/// Reads the deadline of a request, leaving its messages to be decoded as they arrive.
+fn read_request(
+    request: Request<RecvStream>,
+    max_message_size: usize,
+) -> ServiceResult<(Deadline, MessageStream)> {
+    let deadline = deadline::from_headers(request.headers())?;
+    let encoding = Encoding::from_headers(request.headers())?;
+    let decoder = FrameDecoder::new(max_message_size).with_encoding(encoding);
+    Ok((deadline, message_stream(request.into_body(), decoder)))
+}
+
+/// Decodes the messages of a request body, releasing flow control as data is read.
+fn message_stream(body: RecvStream, decoder: FrameDecoder) -> MessageStream {
+    stream::unfold(Some((body, decoder)), |state| async move {
+        let (mut body, mut decoder) = state?;
+        loop {
+            match decoder.next_message() {
+                Ok(Some(message)) => return Some((Ok(message), Some((body, decoder)))),
+                Ok(None) => {}
+                Err(err) => return Some((Err(err), None)),
+            }
+            match body.data().await {
+                Some(Ok(chunk)) => {
+                    let _ = body.flow_control().release_capacity(chunk.len());
+                    decoder.push(&chunk[..]);
+                }
+                Some(Err(err)) => return Some((Err(err.into()), None)),
+                None => break,
+            }
+        }
+        decoder.finish().err().map(|err| (Err(err), None))
+    })
+    .boxed()
+}
+
+/// Reads every message of a request body.
+async fn read_body(
+    request: &mut Request<RecvStream>,
+    max_message_size: usize,
+) -> ServiceResult<Vec<Bytes>> {
+    let encoding = Encoding::from_headers(request.headers())?;
+    let body = request.body_mut();
+    let mut decoder = FrameDecoder::new(max_message_size).with_encoding(encoding);
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        let _ = body.flow_control().release_capacity(chunk.len());
+        decoder.push(&chunk[..]);
+        while let Some(message) = decoder.next_message()? {
+            data.push(message);
+        }
+    }
+    decoder.finish()?;
+    Ok(data)
+}
+
+/// Builds the response headers, naming the encoding of response messages if any.
+fn grpc_response(encoding: Option<Encoding>) -> http::Response<()> {
+    let mut response = http::Response::new(());
+    let headers = response.headers_mut();
+    headers.insert(
+        http::header::CONTENT_TYPE,
+        HeaderValue::from_static("application/grpc"),
+    );
+    if let Some(encoding) = encoding {
+        headers.insert("grpc-encoding", HeaderValue::from_static(encoding.name()));
+    }
+    headers.insert(
+        "grpc-accept-encoding",
+        HeaderValue::from_static(frame::ACCEPT_ENCODING),
+    );
+    response
+}
+
+/// Builds the gRPC status trailers for a finished call.
+fn status_trailers(err: Option<&ServiceError>) -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    match err {
+        Some(err) => {
+            trailers.insert("grpc-status", HeaderValue::from(err.status().value()));
+            let message = HeaderValue::try_from(encode_status_message(&err.to_string()))
+                .unwrap_or(HeaderValue::from_static("an error occurred in pandit"));
+            trailers.insert("grpc-message", message);
         }
+        None => {
+            trailers.insert("grpc-status", HeaderValue::from(0i32));
+        }
+    }
+    trailers
+}
 
-        let resp_raw = service.send(&service_name, &method_name, &data[..]).await;
-        match resp_raw {
-            Ok(v) => Ok(v),
-            Err(err) => Err(ServiceError::new(
-                format!("an error occured sending the payload: {:?}", err).as_str(),
-            )),
+/// Truncates a status message and percent-encodes it as `grpc-message` requires.
+fn encode_status_message(message: &str) -> String {
+    let mut end = message.len().min(MAX_STATUS_MESSAGE_LEN);
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut out = String::with_capacity(end);
+    for byte in message[..end].bytes() {
+        if (b' '..=b'~').contains(&byte) && byte != b'%' {
+            out.push(char::from(byte));
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Decodes a percent-encoded `grpc-message`, leaving invalid escapes as they are.
+pub fn decode_status_message(message: &[u8]) -> String {
+    let mut out = Vec::with_capacity(message.len());
+    let mut i = 0;
+    while i < message.len() {
+        let escaped = message
+            .get(i + 1..i + 3)
+            .filter(|_| message[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(message[i]);
+                i += 1;
+            }
         }
     }
+    String::from_utf8_lossy(&out).to_string()
 }
@@ -0,0 +1,424 @@
+use std::collections::HashSet;
+
+use protobuf::descriptor::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto};
+use protobuf::wire_format::WireTypeLengthDelimited;
+use protobuf::{CodedInputStream, CodedOutputStream, Message, ProtobufEnum};
+
+use crate::services::{ServiceError, ServiceResult};
+
+/// Request path of the bidirectional `ServerReflectionInfo` method.
+pub const PATH: &str = "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo";
+
+pub const SERVICE_NAME: &str = "grpc.reflection.v1alpha.ServerReflection";
+
+enum Request {
+    FileByFilename(String),
+    FileContainingSymbol(String),
+    FileContainingExtension(String, i32),
+    AllExtensionNumbersOfType(String),
+    ListServices,
+}
+
+enum Response {
+    Files(Vec<Vec<u8>>),
+    ExtensionNumbers(String, Vec<i32>),
+    Services(Vec<String>),
+}
+
+/// File descriptors of every proxied service, local or on other nodes.
+#[derive(Default)]
+pub struct DescriptorPool {
+    files: Vec<FileDescriptorProto>,
+    services: Vec<String>,
+}
+
+impl DescriptorPool {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a service along with the files it was parsed from.
+    pub fn add_service(&mut self, name: &str, files: &[FileDescriptorProto]) {
+        for file in files {
+            if !self.files.iter().any(|f| f.get_name() == file.get_name()) {
+                self.files.push(file.clone());
+            }
+            for service in file.service.iter().filter(|s| s.get_name() == name) {
+                let service = qualify(file.get_package(), service.get_name());
+                if !self.services.contains(&service) {
+                    self.services.push(service);
+                }
+            }
+        }
+    }
+
+    /// Answers a single `ServerReflectionRequest`, encoded without its gRPC header.
+    pub fn respond(&self, buf: &[u8]) -> ServiceResult<bytes::Bytes> {
+        let (host, request) = parse_request(buf)?;
+        let response = match request {
+            Some(request) => self.answer(request),
+            None => Err(ServiceError::InvalidArgument(
+                "no message request in reflection request".to_string(),
+            )),
+        };
+        let out = encode(|output| {
+            output.write_string(1, &host)?;
+            output.write_bytes(2, buf)?;
+            match &response {
+                Ok(Response::Files(files)) => {
+                    let files = encode(|output| {
+                        for file in files {
+                            output.write_bytes(1, &file[..])?;
+                        }
+                        Ok(())
+                    })?;
+                    output.write_bytes(4, &files[..])?;
+                }
+                Ok(Response::ExtensionNumbers(base_type, numbers)) => {
+                    let numbers = encode(|output| {
+                        output.write_string(1, base_type)?;
+                        for number in numbers {
+                            output.write_int32(2, *number)?;
+                        }
+                        Ok(())
+                    })?;
+                    output.write_bytes(5, &numbers[..])?;
+                }
+                Ok(Response::Services(services)) => {
+                    let services = encode(|output| {
+                        for service in services {
+                            let service = encode(|output| Ok(output.write_string(1, service)?))?;
+                            output.write_bytes(1, &service[..])?;
+                        }
+                        Ok(())
+                    })?;
+                    output.write_bytes(6, &services[..])?;
+                }
+                Err(err) => {
+                    let error = encode(|output| {
+                        output.write_int32(1, err.status().value())?;
+                        Ok(output.write_string(2, &err.to_string())?)
+                    })?;
+                    output.write_bytes(7, &error[..])?;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(bytes::Bytes::from(out))
+    }
+
+    fn answer(&self, request: Request) -> ServiceResult<Response> {
+        match request {
+            Request::FileByFilename(name) => {
+                let file = self
+                    .find_file(&name)
+                    .ok_or(ServiceError::NotFound(format!("file not found: {}", name)))?;
+                Ok(Response::Files(self.with_dependencies(file)?))
+            }
+            Request::FileContainingSymbol(symbol) => {
+                let symbol = symbol.trim_start_matches('.');
+                let file = self
+                    .files
+                    .iter()
+                    .find(|file| file_symbols(file).contains(symbol))
+                    .ok_or(ServiceError::NotFound(format!(
+                        "symbol not found: {}",
+                        symbol
+                    )))?;
+                Ok(Response::Files(self.with_dependencies(file)?))
+            }
+            Request::FileContainingExtension(containing_type, number) => {
+                let file = self
+                    .files
+                    .iter()
+                    .find(|file| {
+                        file_extensions(file).iter().any(|ext| {
+                            extendee(ext) == containing_type && ext.get_number() == number
+                        })
+                    })
+                    .ok_or(ServiceError::NotFound(format!(
+                        "extension {} of {} not found",
+                        number, containing_type
+                    )))?;
+                Ok(Response::Files(self.with_dependencies(file)?))
+            }
+            Request::AllExtensionNumbersOfType(base_type) => {
+                let base_type = base_type.trim_start_matches('.').to_string();
+                if !self
+                    .files
+                    .iter()
+                    .any(|file| file_symbols(file).contains(&base_type))
+                {
+                    return Err(ServiceError::NotFound(format!(
+                        "type not found: {}",
+                        base_type
+                    )));
+                }
+                let numbers = self
+                    .files
+                    .iter()
+                    .flat_map(|file| file_extensions(file))
+                    .filter(|ext| extendee(ext) == base_type)
+                    .map(|ext| ext.get_number())
+                    .collect();
+                Ok(Response::ExtensionNumbers(base_type, numbers))
+            }
+            Request::ListServices => {
+                let mut services = self.services.clone();
+                services.push(SERVICE_NAME.to_string());
+                Ok(Response::Services(services))
+            }
+        }
+    }
+
+    fn find_file(&self, name: &str) -> Option<&FileDescriptorProto> {
+        let file = self.files.iter().find(|file| file.get_name() == name);
+        match file {
+            Some(file) => Some(file),
+            // Not part of the parsed files, as the parser knows it built in.
+            None if name == "google/protobuf/descriptor.proto" => {
+                Some(protobuf::descriptor::file_descriptor_proto())
+            }
+            None => None,
+        }
+    }
+
+    /// Serializes a file followed by everything it imports.
+    fn with_dependencies(&self, file: &FileDescriptorProto) -> ServiceResult<Vec<Vec<u8>>> {
+        let mut seen = HashSet::new();
+        let mut out = Vec::new();
+        let mut pending = vec![file];
+        while let Some(file) = pending.pop() {
+            if !seen.insert(file.get_name().to_string()) {
+                continue;
+            }
+            out.push(file.write_to_bytes()?);
+            for dependency in file.dependency.iter() {
+                match self.find_file(dependency) {
+                    Some(dependency) => pending.push(dependency),
+                    None => log::error!("no descriptor for dependency: {}", dependency),
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn parse_request(buf: &[u8]) -> ServiceResult<(String, Option<Request>)> {
+    let mut input = CodedInputStream::from_bytes(buf);
+    let mut host = String::new();
+    let mut request = None;
+    let mut skipped = protobuf::UnknownFields::new();
+    while !input.eof()? {
+        let (number, wire_type) = input.read_tag_unpack()?;
+        if (1..=7).contains(&number) && wire_type != WireTypeLengthDelimited {
+            return Err(ServiceError::InvalidArgument(format!(
+                "unexpected wire type for reflection request field {}",
+                number
+            )));
+        }
+        match number {
+            1 => host = input.read_string()?,
+            3 => request = Some(Request::FileByFilename(input.read_string()?)),
+            4 => request = Some(Request::FileContainingSymbol(input.read_string()?)),
+            5 => {
+                let (containing_type, number) = parse_extension_request(&input.read_bytes()?)?;
+                request = Some(Request::FileContainingExtension(containing_type, number));
+            }
+            6 => request = Some(Request::AllExtensionNumbersOfType(input.read_string()?)),
+            7 => {
+                input.read_string()?;
+                request = Some(Request::ListServices);
+            }
+            _ => {
+                protobuf::rt::read_unknown_or_skip_group(
+                    number,
+                    wire_type,
+                    &mut input,
+                    &mut skipped,
+                )?;
+            }
+        }
+    }
+    Ok((host, request))
+}
+
+fn parse_extension_request(buf: &[u8]) -> ServiceResult<(String, i32)> {
+    let mut input = CodedInputStream::from_bytes(buf);
+    let mut containing_type = String::new();
+    let mut number = 0;
+    let mut skipped = protobuf::UnknownFields::new();
+    while !input.eof()? {
+        match input.read_tag_unpack()? {
+            (1, WireTypeLengthDelimited) => containing_type = input.read_string()?,
+            (2, protobuf::wire_format::WireTypeVarint) => number = input.read_int32()?,
+            (field, wire_type) => {
+                protobuf::rt::read_unknown_or_skip_group(
+                    field,
+                    wire_type,
+                    &mut input,
+                    &mut skipped,
+                )?;
+            }
+        }
+    }
+    Ok((containing_type, number))
+}
+
+fn encode<F>(write: F) -> ServiceResult<Vec<u8>>
+where
+    F: FnOnce(&mut CodedOutputStream) -> ServiceResult<()>,
+{
+    let mut buf = Vec::new();
+    {
+        let mut output = CodedOutputStream::vec(&mut buf);
+        write(&mut output)?;
+        output.flush()?;
+    }
+    Ok(buf)
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+fn extendee(ext: &FieldDescriptorProto) -> &str {
+    ext.get_extendee().trim_start_matches('.')
+}
+
+/// Fully-qualified names of every symbol defined in a file.
+fn file_symbols(file: &FileDescriptorProto) -> HashSet<String> {
+    fn message_symbols(name: String, message: &DescriptorProto, symbols: &mut HashSet<String>) {
+        for nested in message.nested_type.iter() {
+            message_symbols(qualify(&name, nested.get_name()), nested, symbols);
+        }
+        for enum_type in message.enum_type.iter() {
+            symbols.insert(qualify(&name, enum_type.get_name()));
+        }
+        for field in message.field.iter().chain(message.extension.iter()) {
+            symbols.insert(qualify(&name, field.get_name()));
+        }
+        symbols.insert(name);
+    }
+
+    let package = file.get_package();
+    let mut symbols = HashSet::new();
+    for message in file.message_type.iter() {
+        message_symbols(qualify(package, message.get_name()), message, &mut symbols);
+    }
+    for enum_type in file.enum_type.iter() {
+        symbols.insert(qualify(package, enum_type.get_name()));
+    }
+    for ext in file.extension.iter() {
+        symbols.insert(qualify(package, ext.get_name()));
+    }
+    for service in file.service.iter() {
+        let name = qualify(package, service.get_name());
+        for method in service.method.iter() {
+            symbols.insert(qualify(&name, method.get_name()));
+        }
+        symbols.insert(name);
+    }
+    symbols
+}
+
+/// Every extension declared in a file, including ones nested in messages.
+fn file_extensions(file: &FileDescriptorProto) -> Vec<&FieldDescriptorProto> {
+    fn message_extensions<'a>(
+        message: &'a DescriptorProto,
+        out: &mut Vec<&'a FieldDescriptorProto>,
+    ) {
+        out.extend(message.extension.iter());
+        for nested in message.nested_type.iter() {
+            message_extensions(nested, out);
+        }
+    }
+
+    let mut out: Vec<&FieldDescriptorProto> = file.extension.iter().collect();
+    for message in file.message_type.iter() {
+        message_extensions(message, &mut out);
+    }
+    out
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn example_pool() -> super::DescriptorPool {
+        use std::path::PathBuf;
+        let parsed = protobuf_parse::pure::parse_and_typecheck(
+            &[PathBuf::from("./src/proto")],
+            &[PathBuf::from("./src/proto/examples/example1.proto")],
+        )
+        .unwrap();
+        let mut pool = super::DescriptorPool::new();
+        pool.add_service("ExampleService", &parsed.file_descriptors[..]);
+        pool
+    }
+
+    #[allow(dead_code)]
+    fn request(field: u32, value: &str) -> Vec<u8> {
+        super::encode(|output| Ok(output.write_string(field, value)?)).unwrap()
+    }
+
+    #[test]
+    fn test_list_services() {
+        use super::*;
+        let pool = example_pool();
+        let resp = pool.respond(&request(7, "")[..]).unwrap();
+        let mut input = CodedInputStream::from_bytes(&resp[..]);
+        let mut services = Vec::new();
+        while !input.eof().unwrap() {
+            let (number, _) = input.read_tag_unpack().unwrap();
+            let buf = input.read_bytes().unwrap();
+            if number != 6 {
+                continue;
+            }
+            let mut list = CodedInputStream::from_bytes(&buf[..]);
+            while !list.eof().unwrap() {
+                list.read_tag_unpack().unwrap();
+                let service = list.read_bytes().unwrap();
+                let mut service = CodedInputStream::from_bytes(&service[..]);
+                service.read_tag_unpack().unwrap();
+                services.push(service.read_string().unwrap());
+            }
+        }
+        assert_eq!(
+            services,
+            vec![
+                "http_demo.ExampleService".to_string(),
+                SERVICE_NAME.to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_containing_symbol() {
+        use super::*;
+        let pool = example_pool();
+        for symbol in &[
+            "http_demo.ExampleService",
+            "http_demo.ExampleService.GetExample",
+            ".http_demo.ExampleRequest",
+        ] {
+            match pool.answer(Request::FileContainingSymbol(symbol.to_string())) {
+                Ok(Response::Files(files)) => {
+                    let file = FileDescriptorProto::parse_from_bytes(&files[0][..]).unwrap();
+                    assert!(file.get_name().ends_with("example1.proto"));
+                    // Imports are sent along with the file.
+                    assert!(files.len() > 1);
+                }
+                _ => panic!("no file found for {}", symbol),
+            }
+        }
+        match pool.answer(Request::FileContainingSymbol(
+            "http_demo.Missing".to_string(),
+        )) {
+            Err(err) => assert_eq!(err.status(), crate::services::base::StatusCode::NOT_FOUND),
+            _ => panic!("found file for missing symbol"),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use grpcio::{
+    CertificateRequestType, ChannelCredentials, ChannelCredentialsBuilder, ServerCredentials,
+    ServerCredentialsBuilder,
+};
+use openssl::ssl::{
+    self, AlpnError, Ssl, SslAcceptor, SslConnector, SslFiletype, SslMethod, SslRef, SslVerifyMode,
+};
+use openssl::stack::Stack;
+use openssl::x509::store::{X509Store, X509StoreBuilder};
+use openssl::x509::{X509StoreContext, X509};
+use tokio::net::TcpStream;
+use tokio_openssl::SslStream;
+
+use crate::services::{ServiceError, ServiceResult};
+
+/// Default interval between checks of the certificate files for changes.
+pub const DEFAULT_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Time a client has to complete the TLS handshake before its connection is dropped.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ALPN protocol list offering HTTP/2 only.
+pub(crate) const ALPN_H2: &[u8] = b"\x02h2";
+
+/// PEM files used to terminate TLS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// CA client certificates must be issued by, turning on mutual TLS.
+    pub client_ca: Option<PathBuf>,
+    /// CA of pandit node certificates, whose holders may send delegated requests.
+    pub cluster_ca: Option<PathBuf>,
+    pub reload_interval: Duration,
+}
+
+impl TlsConfig {
+    /// Reads `{prefix}.cert`, `{prefix}.key` and `{prefix}.client_ca`, `None` when no
+    /// certificate is configured.
+    pub fn from_config(cfg: &config::Config, prefix: &str) -> ServiceResult<Option<Self>> {
+        let cert = match cfg.get_str(&format!("{}.cert", prefix)) {
+            Ok(cert) => PathBuf::from(cert),
+            Err(_) => return Ok(None),
+        };
+        let key = cfg.get_str(&format!("{}.key", prefix)).map_err(|_| {
+            ServiceError::InvalidArgument(format!(
+                "{}.key is required with {}.cert",
+                prefix, prefix
+            ))
+        })?;
+        let client_ca = cfg
+            .get_str(&format!("{}.client_ca", prefix))
+            .ok()
+            .map(PathBuf::from);
+        let reload_interval = cfg
+            .get_int(&format!("{}.reload_interval_ms", prefix))
+            .map(|ms| Duration::from_millis(ms as u64))
+            .unwrap_or(DEFAULT_RELOAD_INTERVAL);
+        Ok(Some(Self {
+            cert,
+            key: PathBuf::from(key),
+            client_ca,
+            cluster_ca: None,
+            reload_interval,
+        }))
+    }
+
+    /// Credentials serving the admin API with the same certificate, requiring a client
+    /// certificate issued by `client_ca` when one is set.
+    pub fn admin_credentials(&self) -> ServiceResult<ServerCredentials> {
+        let mut builder = ServerCredentialsBuilder::new()
+            .add_cert(std::fs::read(&self.cert)?, std::fs::read(&self.key)?);
+        if let Some(client_ca) = &self.client_ca {
+            builder = builder.root_cert(
+                std::fs::read(client_ca)?,
+                CertificateRequestType::RequestAndRequireClientCertificateAndVerify,
+            );
+        }
+        Ok(builder.build())
+    }
+
+    /// Credentials for the daemon's own admin client, which presents the server certificate.
+    ///
+    /// `ca` issued the server certificate; when unset the certificate must be self-signed.
+    pub fn admin_client_credentials(
+        &self,
+        ca: Option<&PathBuf>,
+    ) -> ServiceResult<ChannelCredentials> {
+        let root = std::fs::read(ca.unwrap_or(&self.cert))?;
+        Ok(ChannelCredentialsBuilder::new()
+            .root_cert(root)
+            .cert(std::fs::read(&self.cert)?, std::fs::read(&self.key)?)
+            .build())
+    }
+
+    /// Modification times of the configured files, used to spot rotated certificates.
+    fn modified(&self) -> ServiceResult<Vec<SystemTime>> {
+        let mut paths = vec![&self.cert, &self.key];
+        paths.extend(self.client_ca.iter());
+        paths.extend(self.cluster_ca.iter());
+        let mut modified = Vec::with_capacity(paths.len());
+        for path in paths {
+            modified.push(std::fs::metadata(path)?.modified()?);
+        }
+        Ok(modified)
+    }
+
+    fn build(&self) -> ServiceResult<Acceptor> {
+        let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+        builder.set_private_key_file(&self.key, SslFiletype::PEM)?;
+        builder.set_certificate_chain_file(&self.cert)?;
+        builder.check_private_key()?;
+        builder.set_alpn_select_callback(|_, client| {
+            ssl::select_next_proto(ALPN_H2, client).ok_or(AlpnError::NOACK)
+        });
+        for ca in self.client_ca.iter().chain(self.cluster_ca.iter()) {
+            builder.set_ca_file(ca)?;
+            for cert in X509::stack_from_pem(&std::fs::read(ca)?)? {
+                builder.add_client_ca(&cert)?;
+            }
+        }
+        if self.client_ca.is_some() {
+            builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+        } else if self.cluster_ca.is_some() {
+            // Only nodes are asked for a certificate, clients may connect without one.
+            builder.set_verify(SslVerifyMode::PEER);
+        }
+        let nodes = match &self.cluster_ca {
+            Some(cluster_ca) => Some(Arc::new(load_store(cluster_ca)?)),
+            None => None,
+        };
+        Ok(Acceptor {
+            acceptor: Arc::new(builder.build()),
+            nodes,
+        })
+    }
+}
+
+/// Certificates pandit nodes present to each other, all issued by the cluster CA.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterConfig {
+    pub ca: PathBuf,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl ClusterConfig {
+    /// Reads `cluster.tls.ca`, `cluster.tls.cert` and `cluster.tls.key`, `None` when no CA
+    /// is configured.
+    pub fn from_config(cfg: &config::Config) -> ServiceResult<Option<Self>> {
+        let ca = match cfg.get_str("cluster.tls.ca") {
+            Ok(ca) => PathBuf::from(ca),
+            Err(_) => return Ok(None),
+        };
+        let path = |name: &str| {
+            cfg.get_str(&format!("cluster.tls.{}", name))
+                .map(PathBuf::from)
+                .map_err(|_| {
+                    ServiceError::InvalidArgument(format!(
+                        "cluster.tls.{} is required with cluster.tls.ca",
+                        name
+                    ))
+                })
+        };
+        Ok(Some(Self {
+            cert: path("cert")?,
+            key: path("key")?,
+            ca,
+        }))
+    }
+
+    /// Serves clients with the node certificate when the server has none of its own.
+    pub fn server_config(&self) -> TlsConfig {
+        TlsConfig {
+            cert: self.cert.clone(),
+            key: self.key.clone(),
+            client_ca: None,
+            cluster_ca: Some(self.ca.clone()),
+            reload_interval: DEFAULT_RELOAD_INTERVAL,
+        }
+    }
+
+    /// Opens a mutual TLS connection to another node.
+    ///
+    /// The connector is built per connection so rotated node certificates are picked up.
+    pub async fn connect(&self, socket: TcpStream) -> ServiceResult<SslStream<TcpStream>> {
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+        // Replace the system CAs the builder loads, so only node certificates are trusted.
+        builder.set_cert_store(load_store(&self.ca)?);
+        builder.set_certificate_chain_file(&self.cert)?;
+        builder.set_private_key_file(&self.key, SslFiletype::PEM)?;
+        builder.set_alpn_protos(ALPN_H2)?;
+        let mut config = builder.build().configure()?;
+        // Nodes are addressed by the IP published in Redis, the cluster CA is what vouches
+        // for them.
+        config.set_verify_hostname(false);
+        let ssl = config.into_ssl("pandit")?;
+        let mut stream = SslStream::new(ssl, socket)?;
+        Pin::new(&mut stream).connect().await.map_err(|err| {
+            ServiceError::Unavailable(format!("TLS handshake with node failed: {}", err))
+        })?;
+        Ok(stream)
+    }
+}
+
+#[derive(Clone)]
+struct Acceptor {
+    acceptor: Arc<SslAcceptor>,
+    /// Trust store holding only the cluster CA, used to recognise other nodes.
+    nodes: Option<Arc<X509Store>>,
+}
+
+/// Terminates TLS on accepted connections, picking up new certificates as files change.
+pub struct TlsAcceptor {
+    config: TlsConfig,
+    current: RwLock<(Vec<SystemTime>, Acceptor)>,
+}
+
+impl TlsAcceptor {
+    pub fn new(config: TlsConfig) -> ServiceResult<Self> {
+        let modified = config.modified()?;
+        let acceptor = config.build()?;
+        Ok(Self {
+            config,
+            current: RwLock::new((modified, acceptor)),
+        })
+    }
+
+    /// Runs the TLS handshake on a client connection, also returning whether the peer
+    /// presented a pandit node certificate.
+    pub async fn accept(&self, socket: TcpStream) -> ServiceResult<(SslStream<TcpStream>, bool)> {
+        let acceptor = self.current.read().unwrap().1.clone();
+        let ssl = Ssl::new(acceptor.acceptor.context())?;
+        let mut stream = SslStream::new(ssl, socket)?;
+        Pin::new(&mut stream).accept().await.map_err(|err| {
+            ServiceError::Unauthenticated(format!("TLS handshake failed: {}", err))
+        })?;
+        let node = match &acceptor.nodes {
+            Some(nodes) => is_node(nodes, stream.ssl())?,
+            None => false,
+        };
+        Ok((stream, node))
+    }
+
+    /// Rebuilds the acceptor if any of the files changed, returning whether it did.
+    ///
+    /// Connections already open keep the certificates they were accepted with.
+    pub fn reload(&self) -> ServiceResult<bool> {
+        let modified = self.config.modified()?;
+        if modified == self.current.read().unwrap().0 {
+            return Ok(false);
+        }
+        let acceptor = self.config.build()?;
+        *self.current.write().unwrap() = (modified, acceptor);
+        Ok(true)
+    }
+
+    /// Checks the files for changes until the task is dropped.
+    pub async fn watch(self: Arc<Self>) {
+        loop {
+            tokio::time::sleep(self.config.reload_interval).await;
+            match self.reload() {
+                Ok(true) => log::info!("reloaded TLS certificate {:?}", self.config.cert),
+                Ok(false) => {}
+                // Keep serving with the previous certificates until the files are fixed.
+                Err(err) => log::error!("unable to reload TLS certificates: {}", err),
+            }
+        }
+    }
+}
+
+fn load_store(ca: &PathBuf) -> ServiceResult<X509Store> {
+    let mut builder = X509StoreBuilder::new()?;
+    for cert in X509::stack_from_pem(&std::fs::read(ca)?)? {
+        builder.add_cert(cert)?;
+    }
+    Ok(builder.build())
+}
+
+/// Checks the peer certificate chains up to the cluster CA.
+fn is_node(nodes: &X509Store, ssl: &SslRef) -> ServiceResult<bool> {
+    let cert = match ssl.peer_certificate() {
+        Some(cert) => cert,
+        None => return Ok(false),
+    };
+    let mut chain = Stack::new()?;
+    for cert in ssl.peer_cert_chain().into_iter().flatten() {
+        chain.push(cert.to_owned())?;
+    }
+    let mut context = X509StoreContext::new()?;
+    Ok(context.init(nodes, &cert, &chain, |context| context.verify_cert())?)
+}
+
+mod tests {
+    #[test]
+    fn test_tls_config() {
+        use super::*;
+        let mut cfg = config::Config::default();
+        assert_eq!(TlsConfig::from_config(&cfg, "server.tls").unwrap(), None);
+
+        cfg.set("server.tls.cert", "cert.pem").unwrap();
+        assert!(TlsConfig::from_config(&cfg, "server.tls").is_err());
+
+        cfg.set("server.tls.key", "key.pem").unwrap();
+        cfg.set("server.tls.client_ca", "ca.pem").unwrap();
+        let tls = TlsConfig::from_config(&cfg, "server.tls").unwrap().unwrap();
+        assert_eq!(tls.key, PathBuf::from("key.pem"));
+        assert_eq!(tls.client_ca, Some(PathBuf::from("ca.pem")));
+        assert_eq!(tls.reload_interval, DEFAULT_RELOAD_INTERVAL);
+
+        assert_eq!(tls.cluster_ca, None);
+        assert!(tls.admin_credentials().is_err());
+        assert!(TlsAcceptor::new(tls).is_err());
+    }
+
+    #[test]
+    fn test_cluster_config() {
+        use super::*;
+        let mut cfg = config::Config::default();
+        assert_eq!(ClusterConfig::from_config(&cfg).unwrap(), None);
+
+        cfg.set("cluster.tls.ca", "ca.pem").unwrap();
+        cfg.set("cluster.tls.cert", "node.pem").unwrap();
+        assert!(ClusterConfig::from_config(&cfg).is_err());
+
+        cfg.set("cluster.tls.key", "node.key").unwrap();
+        let cluster = ClusterConfig::from_config(&cfg).unwrap().unwrap();
+        let tls = cluster.server_config();
+        assert_eq!(tls.cert, PathBuf::from("node.pem"));
+        assert_eq!(tls.cluster_ca, Some(PathBuf::from("ca.pem")));
+        assert_eq!(tls.client_ca, None);
+    }
+}
@@ -0,0 +1,444 @@
+use std::convert::{Infallible, TryFrom};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::stream::{self, StreamExt};
+use http::{header, HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
+use hyper::body::HttpBody;
+use hyper::server::accept::{self, Accept};
+use hyper::server::conn::AddrIncoming;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::Body;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::signal::ctrl_c;
+use tokio_openssl::SslStream;
+
+use super::frame::{self, Encoding, FrameDecoder};
+use super::tls::{TlsAcceptor, HANDSHAKE_TIMEOUT};
+use super::{deadline, status_trailers, IntraServer, Server};
+use crate::services::{with_deadline, MessageStream, Metadata, ServiceError, ServiceResult};
+
+/// Default address of the gRPC-Web listener.
+pub const DEFAULT_ADDRESS: &str = "0.0.0.0:50123";
+
+/// Flag marking the trailers frame at the end of a gRPC-Web response.
+const TRAILERS_FLAG: u8 = 0x80;
+
+const ALLOWED_HEADERS: &str =
+    "content-type,x-grpc-web,x-user-agent,grpc-timeout,grpc-encoding,grpc-accept-encoding";
+
+const EXPOSED_HEADERS: &str = "grpc-status,grpc-message,grpc-encoding";
+
+/// Whether to start the gRPC-Web listener.
+///
+/// It shares the gRPC port's handler, so without `server.tls` it only runs in plaintext when
+/// `server.web.enabled` is set.
+pub fn enabled(cfg: &config::Config, server: &IntraServer) -> bool {
+    cfg.get_bool("server.web.enabled")
+        .unwrap_or(server.tls().is_some())
+}
+
+/// Origins browsers may call the listener from, set by `server.web.allowed_origins`.
+///
+/// A `*` entry allows any origin. No cross-origin calls are allowed by default.
+#[derive(Debug, Clone, Default)]
+pub struct AllowedOrigins {
+    origins: Vec<String>,
+}
+
+impl AllowedOrigins {
+    pub fn new(origins: Vec<String>) -> Self {
+        Self { origins }
+    }
+
+    pub fn from_config(cfg: &config::Config) -> Self {
+        match cfg.get::<Vec<String>>("server.web.allowed_origins") {
+            Ok(origins) => Self::new(origins),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn allows(&self, origin: &HeaderValue) -> bool {
+        self.origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.as_bytes() == origin.as_bytes())
+    }
+}
+
+/// Serves gRPC-Web over HTTP/1.1, passing calls to the same handler as the HTTP/2 port.
+///
+/// Connections are served over TLS, with the same certificates, when the gRPC port has TLS.
+pub async fn run(
+    server: Arc<IntraServer>,
+    addr: String,
+    origins: AllowedOrigins,
+) -> ServiceResult<()> {
+    log::info!("gRPC-Web server starting on: {}...", addr);
+    let addr: SocketAddr = addr.parse()?;
+    let listener = TcpListener::bind(addr).await?;
+    let origins = Arc::new(origins);
+    match server.tls() {
+        Some(tls) => {
+            let (tx, rx) = mpsc::unbounded();
+            let handshakes = tokio::spawn(accept_tls(listener, tls, tx));
+            let result = serve(server, origins, accept::from_stream(rx)).await;
+            handshakes.abort();
+            result
+        }
+        None => serve(server, origins, AddrIncoming::from_listener(listener)?).await,
+    }
+}
+
+/// Runs the TLS handshake on each accepted socket, passing on the connections that
+/// complete it.
+async fn accept_tls(
+    listener: TcpListener,
+    tls: Arc<TlsAcceptor>,
+    incoming: mpsc::UnboundedSender<std::io::Result<SslStream<TcpStream>>>,
+) {
+    while !incoming.is_closed() {
+        let socket = match listener.accept().await {
+            Ok((socket, _)) => socket,
+            Err(err) => {
+                log::error!("error accepting gRPC-Web connection: {}", err);
+                continue;
+            }
+        };
+        let tls = tls.clone();
+        let incoming = incoming.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.accept(socket)).await {
+                Ok(Ok((stream, _))) => {
+                    let _ = incoming.unbounded_send(Ok(stream));
+                }
+                Ok(Err(err)) => log::error!("gRPC-Web TLS handshake failed: {}", err),
+                Err(_) => log::error!("gRPC-Web TLS handshake timed out"),
+            }
+        });
+    }
+}
+
+async fn serve<I, S>(
+    server: Arc<IntraServer>,
+    origins: Arc<AllowedOrigins>,
+    incoming: I,
+) -> ServiceResult<()>
+where
+    I: Accept<Conn = S>,
+    I::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let make_service = make_service_fn(move |_: &S| {
+        let server = server.clone();
+        let origins = origins.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let server = server.clone();
+                let origins = origins.clone();
+                async move { Ok::<_, Infallible>(handle(server, &origins, req).await) }
+            }))
+        }
+    });
+    hyper::Server::builder(incoming)
+        .http1_only(true)
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            let _ = ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+async fn handle(
+    server: Arc<IntraServer>,
+    origins: &AllowedOrigins,
+    mut req: Request<Body>,
+) -> Response<Body> {
+    if req.method() == Method::OPTIONS {
+        return preflight(&req, origins);
+    }
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    if req.method() != Method::POST || !content_type.starts_with("application/grpc-web") {
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        return resp;
+    }
+    let text = content_type.starts_with("application/grpc-web-text");
+    let encoding = Encoding::negotiate(req.headers());
+
+    let path = req.uri().path().to_string();
+    let metadata = Metadata::new(req.headers().clone());
+    let messages = match read_request(&server, &mut req, text).await {
+        Ok((deadline, data)) => {
+            let services = server.services.read().await;
+            let broker = server.broker.clone();
+            let data = stream::iter(data.into_iter().map(Ok)).boxed();
+            IntraServer::handle_request(services, &path, deadline, data, &metadata, broker, false)
+                .await
+        }
+        Err(err) => Err(err),
+    };
+    let messages = match messages {
+        Ok(messages) => messages,
+        Err(err) => {
+            log::error!("error occured handling gRPC-Web request: {}", err);
+            stream::iter(vec![Err(err)]).boxed()
+        }
+    };
+
+    let mut resp = Response::new(Body::wrap_stream(response_body(
+        messages, text, encoding, metadata,
+    )));
+    let headers = resp.headers_mut();
+    if let Ok(content_type) = HeaderValue::try_from(content_type) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    if let Some(encoding) = encoding {
+        headers.insert("grpc-encoding", HeaderValue::from_static(encoding.name()));
+    }
+    headers.insert(
+        "grpc-accept-encoding",
+        HeaderValue::from_static(frame::ACCEPT_ENCODING),
+    );
+    cors_headers(req.headers(), headers, origins);
+    headers.insert(
+        header::ACCESS_CONTROL_EXPOSE_HEADERS,
+        HeaderValue::from_static(EXPOSED_HEADERS),
+    );
+    resp
+}
+
+/// Answers a CORS preflight request.
+fn preflight(req: &Request<Body>, origins: &AllowedOrigins) -> Response<Body> {
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = StatusCode::NO_CONTENT;
+    let headers = resp.headers_mut();
+    cors_headers(req.headers(), headers, origins);
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static("POST, OPTIONS"),
+    );
+    let allowed = req
+        .headers()
+        .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+        .cloned()
+        .unwrap_or(HeaderValue::from_static(ALLOWED_HEADERS));
+    headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allowed);
+    headers.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from(86400));
+    resp
+}
+
+/// Allows the request's origin when it is listed, so browsers on other origins can't read
+/// the response.
+fn cors_headers(req: &HeaderMap, resp: &mut HeaderMap, origins: &AllowedOrigins) {
+    if let Some(origin) = req.get(header::ORIGIN) {
+        if origins.allows(origin) {
+            resp.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+        }
+    }
+    resp.insert(header::VARY, HeaderValue::from_static("origin"));
+}
+
+async fn read_request(
+    server: &IntraServer,
+    req: &mut Request<Body>,
+    text: bool,
+) -> ServiceResult<(crate::services::Deadline, Vec<Bytes>)> {
+    let deadline = deadline::from_headers(req.headers())?;
+    let encoding = Encoding::from_headers(req.headers())?;
+    // gRPC-Web has no client streaming, so the body holds a single message.
+    let mut limit = frame::HEADER_LEN + server.max_message_size;
+    if text {
+        limit = (limit + 2) / 3 * 4;
+    }
+    let body = req.body_mut();
+    let body = with_deadline(deadline, async {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = body.data().await {
+            buf.extend_from_slice(&chunk?[..]);
+            if buf.len() > limit {
+                return Err(ServiceError::ResourceExhausted(format!(
+                    "request body exceeds {} bytes",
+                    limit
+                )));
+            }
+        }
+        Ok(buf.freeze())
+    })
+    .await?;
+    let body = if text { decode_text(&body[..])? } else { body };
+
+    let mut decoder = FrameDecoder::new(server.max_message_size).with_encoding(encoding);
+    decoder.push(&body[..]);
+    let mut data = Vec::new();
+    while let Some(message) = decoder.next_message()? {
+        data.push(message);
+    }
+    decoder.finish()?;
+    Ok((deadline, data))
+}
+
+/// Frames each message, then the status as a trailers frame, base64 encoding for text clients.
+///
+/// Only messages are compressed, the trailers frame is always sent as is.
+fn response_body(
+    messages: MessageStream,
+    text: bool,
+    encoding: Option<Encoding>,
+    metadata: Metadata,
+) -> impl futures::Stream<Item = Result<Bytes, Infallible>> {
+    stream::unfold(Some((messages, metadata)), move |state| async move {
+        let (mut messages, metadata) = state?;
+        let message = messages.next().await;
+        let message = message.map(|m| m.and_then(|m| frame::compress(m, encoding)));
+        let (chunk, state) = match message {
+            Some(Ok(message)) => (message, Some((messages, metadata))),
+            Some(Err(err)) => (trailers_frame(Some(&err), &metadata.response()), None),
+            None => (trailers_frame(None, &metadata.response()), None),
+        };
+        let chunk = if text {
+            Bytes::from(base64::encode(&chunk[..]))
+        } else {
+            chunk
+        };
+        Some((Ok(chunk), state))
+    })
+}
+
+/// Encodes the status and any backend headers returned to the client.
+fn trailers_frame(err: Option<&ServiceError>, metadata: &HeaderMap) -> Bytes {
+    let mut headers = status_trailers(err);
+    headers.extend(metadata.clone());
+    let mut trailers = Vec::new();
+    for (name, value) in headers.iter() {
+        trailers.extend_from_slice(name.as_str().as_bytes());
+        trailers.extend_from_slice(b": ");
+        trailers.extend_from_slice(value.as_bytes());
+        trailers.extend_from_slice(b"\r\n");
+    }
+    let mut buf = BytesMut::with_capacity(frame::HEADER_LEN + trailers.len());
+    buf.put_u8(TRAILERS_FLAG);
+    buf.put_u32(trailers.len() as u32);
+    buf.extend_from_slice(&trailers[..]);
+    buf.freeze()
+}
+
+/// Decodes a grpc-web-text body, which may be several padded base64 chunks back to back.
+fn decode_text(body: &[u8]) -> ServiceResult<Bytes> {
+    let body: Vec<u8> = body
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    if body.len() % 4 != 0 {
+        return Err(ServiceError::InvalidArgument(
+            "invalid base64 in grpc-web-text body".to_string(),
+        ));
+    }
+    let mut out = Vec::with_capacity(body.len() / 4 * 3);
+    for group in body.chunks(4) {
+        let group = base64::decode(group)
+            .map_err(|err| ServiceError::InvalidArgument(format!("invalid base64: {}", err)))?;
+        out.extend_from_slice(&group[..]);
+    }
+    Ok(Bytes::from(out))
+}
+
+mod tests {
+    #[test]
+    fn test_decode_text() {
+        use super::*;
+        let body = format!(
+            "{}{}",
+            base64::encode(&[0, 0, 0, 0]),
+            base64::encode(&[0, 3])
+        );
+        assert_eq!(
+            decode_text(body.as_bytes()).unwrap(),
+            Bytes::from_static(&[0, 0, 0, 0, 0, 3])
+        );
+        assert!(decode_text(b"AAA").is_err());
+    }
+
+    #[test]
+    fn test_cors_headers() {
+        use super::*;
+        let origins = AllowedOrigins::new(vec!["https://app.example.com".to_string()]);
+        let mut req = HeaderMap::new();
+        req.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://app.example.com"),
+        );
+        let mut resp = HeaderMap::new();
+        cors_headers(&req, &mut resp, &origins);
+        assert_eq!(
+            resp.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://app.example.com"
+        );
+
+        req.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://evil.test"),
+        );
+        let mut resp = HeaderMap::new();
+        cors_headers(&req, &mut resp, &origins);
+        assert!(resp.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        let mut resp = HeaderMap::new();
+        cors_headers(&req, &mut resp, &AllowedOrigins::default());
+        assert!(resp.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        let origins = AllowedOrigins::new(vec!["*".to_string()]);
+        let mut resp = HeaderMap::new();
+        cors_headers(&req, &mut resp, &origins);
+        assert_eq!(
+            resp.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://evil.test"
+        );
+    }
+
+    #[test]
+    fn test_trailers_frame() {
+        use super::*;
+        use crate::server::decode_status_message;
+        let frame = trailers_frame(None, &HeaderMap::new());
+        assert_eq!(
+            frame,
+            Bytes::from_static(b"\x80\x00\x00\x00\x10grpc-status: 0\r\n")
+        );
+        let err = ServiceError::NotFound("missing".to_string());
+        let frame = trailers_frame(Some(&err), &HeaderMap::new());
+        assert_eq!(frame[0], TRAILERS_FLAG);
+        let trailers = std::str::from_utf8(&frame[frame::HEADER_LEN..]).unwrap();
+        assert_eq!(trailers, "grpc-status: 5\r\ngrpc-message: missing\r\n");
+
+        // Messages are percent-encoded and truncated, so a backend body can't break the frame.
+        let err = ServiceError::NotFound("50% gone\r\nété".to_string());
+        let frame = trailers_frame(Some(&err), &HeaderMap::new());
+        let trailers = std::str::from_utf8(&frame[frame::HEADER_LEN..]).unwrap();
+        let message = "50%25 gone%0D%0A%C3%A9t%C3%A9";
+        assert_eq!(
+            trailers,
+            format!("grpc-status: 5\r\ngrpc-message: {}\r\n", message)
+        );
+        assert_eq!(decode_status_message(message.as_bytes()), "50% gone\r\nété");
+        let err = ServiceError::NotFound("é".repeat(1000));
+        let headers = status_trailers(Some(&err));
+        let message = headers.get("grpc-message").unwrap().as_bytes();
+        assert_eq!(message.len(), 512 * 6);
+        assert_eq!(decode_status_message(message), "é".repeat(512));
+
+        let mut metadata = HeaderMap::new();
+        metadata.insert("x-trace-id", HeaderValue::from_static("abc"));
+        let frame = trailers_frame(None, &metadata);
+        let trailers = std::str::from_utf8(&frame[frame::HEADER_LEN..]).unwrap();
+        assert_eq!(trailers, "grpc-status: 0\r\nx-trace-id: abc\r\n");
+    }
+}
@@ -1,4 +1,4 @@
-use std::{io::Write, sync::Arc};
+use std::{collections::HashMap, io::Write, sync::Arc};
 
 use dashmap::{mapref::one::Ref, DashMap};
 use protobuf::{descriptor::FieldDescriptorProto, CodedInputStream};
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::services::{
     value::{ProtoEnum, Value},
+    well_known::WellKnown,
     ServiceError,
 };
 
@@ -16,6 +17,8 @@ use std::convert::TryFrom;
 pub struct Field {
     pub descriptor: FieldDescriptorProto,
     pub cache: Option<super::base::CacheOptions>,
+    /// Name of the oneof the field is a member of, from its `oneof_index`.
+    pub oneof: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -29,6 +32,10 @@ pub struct Message {
     fields: DashMap<u32, Field>,
     pub fields_by_name: DashMap<String, Field>,
     pub message: protobuf::descriptor::DescriptorProto,
+    /// Whether the message is from a proto3 file, which packs repeated scalars by default and
+    /// leaves plain scalar fields without presence.
+    #[serde(default)]
+    pub proto3: bool,
 }
 
 macro_rules! as_variant {
@@ -42,6 +49,11 @@ macro_rules! as_variant {
     };
 }
 
+/// Fails the encoding of a value that does not fit its field.
+fn in_range<T>(value: ServiceResult<T>) -> protobuf::ProtobufResult<T> {
+    value.map_err(|err| protobuf::ProtobufError::MessageNotInitialized(err.to_string()))
+}
+
 impl Message {
     pub(crate) fn new(
         message: protobuf::descriptor::DescriptorProto,
@@ -60,6 +72,7 @@ impl Message {
                     Field {
                         descriptor: field.clone(),
                         cache,
+                        oneof: Self::oneof_name(&message, field),
                     },
                 )
             })
@@ -76,6 +89,7 @@ impl Message {
                     Field {
                         descriptor: field.clone(),
                         cache,
+                        oneof: Self::oneof_name(&message, field),
                     },
                 )
             })
@@ -87,11 +101,204 @@ impl Message {
             fields_by_name,
             message,
             parent,
+            proto3: false,
+        }
+    }
+
+    /// Sets the `syntax` of the file the message is declared in, proto2 when empty.
+    pub(crate) fn with_syntax(mut self, syntax: &str) -> Self {
+        self.proto3 = syntax == "proto3";
+        self
+    }
+
+    /// Whether a field tells a default value apart from being unset.
+    ///
+    /// Singular fields have presence unless they are plain proto3 scalars, whose defaults are
+    /// never written and so read as unset. Repeated fields are unset when empty.
+    pub fn has_presence(&self, field: &FieldDescriptorProto) -> bool {
+        use protobuf::descriptor::field_descriptor_proto::Label::LABEL_REPEATED;
+        use protobuf::descriptor::field_descriptor_proto::Type::TYPE_MESSAGE;
+        if field.get_label() == LABEL_REPEATED {
+            return false;
+        }
+        !self.proto3
+            || field.get_proto3_optional()
+            || field.has_oneof_index()
+            || field.get_field_type() == TYPE_MESSAGE
+    }
+
+    /// Whether a repeated field is written packed: scalars in proto3 unless `packed = false`,
+    /// and only those asking for it in proto2.
+    fn is_packed(&self, field: &FieldDescriptorProto) -> bool {
+        use protobuf::descriptor::field_descriptor_proto::Label::LABEL_REPEATED;
+        use protobuf::descriptor::field_descriptor_proto::Type::*;
+        if field.get_label() != LABEL_REPEATED {
+            return false;
+        }
+        match field.get_field_type() {
+            TYPE_STRING | TYPE_BYTES | TYPE_MESSAGE | TYPE_GROUP => false,
+            _ => match field.options.as_ref() {
+                Some(opts) if opts.has_packed() => opts.get_packed(),
+                _ => self.proto3,
+            },
+        }
+    }
+
+    /// Names the first proto2 `required` field without a value.
+    fn missing_required(&self, fields: &FieldsMap) -> Option<String> {
+        use protobuf::descriptor::field_descriptor_proto::Label::LABEL_REQUIRED;
+        self.message
+            .field
+            .iter()
+            .filter(|field| field.get_label() == LABEL_REQUIRED)
+            .find(|field| {
+                fields.get(field.get_name()).map_or(true, |value| {
+                    matches!(value.value(), None | Some(Value::None))
+                })
+            })
+            .map(|field| field.get_name().to_string())
+    }
+
+    fn oneof_name(
+        message: &protobuf::descriptor::DescriptorProto,
+        field: &FieldDescriptorProto,
+    ) -> Option<String> {
+        // Synthetic oneofs only track presence of proto3 `optional` fields.
+        if !field.has_oneof_index() || field.get_proto3_optional() {
+            return None;
+        }
+        let index = usize::try_from(field.get_oneof_index()).ok()?;
+        let oneof = message.oneof_decl.get(index)?;
+        Some(oneof.get_name().to_string())
+    }
+
+    /// Picks the member to encode for each oneof, by oneof name.
+    ///
+    /// This is the member recorded when the fields were decoded, otherwise the lowest
+    /// numbered member holding a value, so at most one member of a oneof is ever written.
+    pub fn oneof_cases(&self, fields: &Fields) -> HashMap<String, String> {
+        let mut members: Vec<(i32, String, String)> = self
+            .fields_by_name
+            .iter()
+            .filter_map(|field| {
+                let oneof = field.oneof.clone()?;
+                Some((field.descriptor.get_number(), oneof, field.key().clone()))
+            })
+            .collect();
+        members.sort();
+        let is_set = |name: &String| {
+            fields.map.get(name).map_or(false, |value| {
+                !matches!(value.value(), None | Some(Value::None))
+            })
+        };
+        let mut cases = HashMap::new();
+        for (_, oneof, name) in members {
+            if cases.contains_key(&oneof) || !is_set(&name) {
+                continue;
+            }
+            let recorded = fields.oneofs.get(&oneof).map(|case| case.value().clone());
+            match recorded {
+                Some(case) if is_set(&case) => cases.insert(oneof, case),
+                _ => cases.insert(oneof, name),
+            };
+        }
+        cases
+    }
+
+    /// Flattens oneofs written as tagged objects, `{"result": {"ok": ...}}`, into the member
+    /// they hold.
+    fn untag_oneofs(&self, fields: &Fields) -> Fields {
+        let out = Fields::new(FieldsMap::new());
+        for kv in fields.map.iter() {
+            let is_oneof = !self.fields_by_name.contains_key(kv.key())
+                && self
+                    .message
+                    .oneof_decl
+                    .iter()
+                    .any(|oneof| oneof.get_name() == kv.key());
+            match kv.value() {
+                Some(Value::Message(tagged)) if is_oneof => {
+                    for member in tagged.map.iter() {
+                        out.map.insert(member.key().clone(), member.value().clone());
+                        out.oneofs.insert(kv.key().clone(), member.key().clone());
+                    }
+                }
+                value => {
+                    out.map.insert(kv.key().clone(), value.clone());
+                }
+            }
+        }
+        for kv in fields.oneofs.iter() {
+            out.oneofs
+                .entry(kv.key().clone())
+                .or_insert(kv.value().clone());
+        }
+        out.unknown = fields.unknown.clone();
+        out
+    }
+
+    /// Whether this is the entry message protoc generates for a `map<K, V>` field.
+    pub fn is_map_entry(&self) -> bool {
+        self.message
+            .options
+            .as_ref()
+            .map_or(false, |opts| opts.get_map_entry())
+    }
+
+    /// Looks up the message a field refers to, see [`find_message`].
+    pub fn message_type(&self, type_name: &str) -> Option<Ref<String, Message>> {
+        find_message(&self.parent, type_name)
+    }
+
+    /// Parses a map key from its JSON string form into the type of the entry's key field.
+    fn map_key(&self, key: &str) -> ServiceResult<Value> {
+        use protobuf::descriptor::field_descriptor_proto::Type::*;
+        let field = self
+            .fields_by_name
+            .get("key")
+            .ok_or(ServiceError::new("map entry without key field"))?;
+        let invalid = |_| ServiceError::new(format!("invalid map key: {}", key).as_str());
+        Ok(match field.descriptor.get_field_type() {
+            TYPE_STRING => Value::String(key.to_string()),
+            TYPE_BOOL => Value::Bool(key.parse().map_err(invalid)?),
+            TYPE_UINT64 | TYPE_FIXED64 => Value::from_int(key.parse::<u64>().map_err(invalid)?),
+            _ => Value::from_int(key.parse::<i64>().map_err(invalid)?),
+        })
+    }
+
+    fn default_map_key(&self) -> String {
+        use protobuf::descriptor::field_descriptor_proto::Type::*;
+        let key_type = self
+            .fields_by_name
+            .get("key")
+            .map(|field| field.descriptor.get_field_type());
+        match key_type {
+            Some(TYPE_STRING) => "".to_string(),
+            Some(TYPE_BOOL) => "false".to_string(),
+            _ => "0".to_string(),
         }
     }
 
     pub fn fields_from_bytes(&self, buf: &[u8]) -> ServiceResult<Fields> {
         use std::convert::TryInto;
+        if buf.len() < 5 {
+            return Err(ServiceError::new("message shorter than gRPC header"));
+        }
+        if buf[0] != 0 {
+            return Err(ServiceError::new("unable to parse compressed message"));
+        }
+        let len = u32::from_be_bytes(buf[1..5].try_into()?) as usize;
+        if len != buf.len() - 5 {
+            return Err(ServiceError::new(
+                format!(
+                    "gRPC header length {} does not match message length {}",
+                    len,
+                    buf.len() - 5
+                )
+                .as_str(),
+            ));
+        }
+
         let mut input = CodedInputStream::from_bytes(buf);
         input.read_raw_bytes(5)?; // Pop gRPC header.
         self.fields_from_bytes_delimited(&mut input, buf.len().try_into()?)
@@ -103,18 +310,64 @@ impl Message {
         len: u64,
     ) -> ServiceResult<Fields> {
         let fields = FieldsMap::new();
+        let oneofs = DashMap::new();
+        let mut unknown = Vec::new();
         while input.pos() < len && !input.eof()? {
-            let (name, value) = match self.from_field_descriptor_proto(input) {
+            let (number, wire_type) = input.read_tag()?.unpack();
+            if !self.fields.contains_key(&number) {
+                // Fields from a newer version of the message are passed on as they arrived.
+                read_unknown(input, number, wire_type, &mut unknown)?;
+                continue;
+            }
+            let (name, value) = match self.from_field_descriptor_proto(input, number, wire_type) {
                 Ok(val) => val,
                 Err(e) => {
                     log::error!("soft proto parsing error: {:?}", e);
                     continue;
                 }
             };
+            let field = match self.fields_by_name.get(&name) {
+                Some(field) => field,
+                None => continue,
+            };
+            // A plain proto3 scalar sent with its default is the same as one left unset.
+            let is_default = value.as_ref().map_or(false, Value::is_default);
+            if is_default && !self.has_presence(&field.descriptor) {
+                continue;
+            }
+            // Map entries and unpacked repeated values arrive one per tag, and packed runs may
+            // be split, so merge them into the values read so far.
+            let value = match (value, fields.remove(&name)) {
+                (Some(Value::Map(entries)), Some((_, Some(Value::Map(mut merged))))) => {
+                    merged.extend(entries);
+                    Some(Value::Map(merged))
+                }
+                (Some(Value::Array(items)), Some((_, Some(Value::Array(mut merged))))) => {
+                    merged.extend(items);
+                    Some(Value::Array(merged))
+                }
+                (value, _) => value,
+            };
+            // The last member of a oneof on the wire wins.
+            if let Some(oneof) = field.oneof.clone() {
+                if let Some((_, previous)) = oneofs.remove(&oneof) {
+                    fields.remove(&previous);
+                }
+                oneofs.insert(oneof, name.clone());
+            }
             fields.insert(name, value);
         }
+        if let Some(name) = self.missing_required(&fields) {
+            return Err(ServiceError::InvalidArgument(format!(
+                "missing required field: {}",
+                name
+            )));
+        }
 
-        Ok(Fields::new(fields))
+        let mut fields = Fields::new(fields);
+        fields.oneofs = oneofs;
+        fields.unknown = unknown;
+        Ok(fields)
     }
 
     pub fn write_bytes_from_fields(
@@ -122,8 +375,50 @@ impl Message {
         output: &mut protobuf::CodedOutputStream,
         fields: &Fields,
     ) -> protobuf::ProtobufResult<()> {
-        output.write_raw_bytes(&[0, 0, 0, 0, 0])?; // Prepend gRPC header.
-        self._write_bytes_from_fields(output, fields)
+        let buf: Vec<u8> = Vec::with_capacity(1000);
+        use bytes::BufMut;
+        let mut buf = buf.writer();
+        {
+            let mut sub_output = protobuf::CodedOutputStream::new(&mut buf);
+            self._write_bytes_from_fields(&mut sub_output, fields)?;
+        }
+        let buf = buf.into_inner();
+
+        // Prepend gRPC header.
+        output.write_raw_bytes(&[0])?;
+        output.write_raw_bytes(&u32::try_from(buf.len()).unwrap().to_be_bytes())?;
+        output.write_raw_bytes(&buf[..])
+    }
+
+    /// Decodes a message without a gRPC header, such as one embedded in an `Any`.
+    pub fn fields_from_raw(&self, buf: &[u8]) -> ServiceResult<Fields> {
+        let mut input = CodedInputStream::from_bytes(buf);
+        self.fields_from_bytes_delimited(&mut input, u64::try_from(buf.len())?)
+    }
+
+    /// Encodes the fields without a gRPC header.
+    pub fn raw_bytes_from_fields(&self, fields: &Fields) -> ServiceResult<Vec<u8>> {
+        let buf: Vec<u8> = Vec::with_capacity(1000);
+        use bytes::BufMut;
+        let mut buf = buf.writer();
+        {
+            let mut output = protobuf::CodedOutputStream::new(&mut buf);
+            self._write_bytes_from_fields(&mut output, fields)?;
+        }
+        Ok(buf.into_inner())
+    }
+
+    /// Encodes the fields as a single gRPC framed message.
+    pub fn bytes_from_fields(&self, fields: &Fields) -> ServiceResult<bytes::Bytes> {
+        let buf: Vec<u8> = Vec::with_capacity(1000);
+        use bytes::BufMut;
+        let mut buf = buf.writer();
+        {
+            let mut output = protobuf::CodedOutputStream::new(&mut buf);
+            self.write_bytes_from_fields(&mut output, fields)?;
+        }
+        let buf = buf.into_inner();
+        Ok(bytes::Bytes::from(buf))
     }
 
     fn _write_bytes_from_fields(
@@ -131,6 +426,20 @@ impl Message {
         output: &mut protobuf::CodedOutputStream,
         fields: &Fields,
     ) -> protobuf::ProtobufResult<()> {
+        let untagged;
+        let fields = if self.message.oneof_decl.is_empty() {
+            fields
+        } else {
+            untagged = self.untag_oneofs(fields);
+            &untagged
+        };
+        if let Some(name) = self.missing_required(&fields.map) {
+            return Err(protobuf::ProtobufError::MessageNotInitialized(format!(
+                "missing required field: {}",
+                name
+            )));
+        }
+        let cases = self.oneof_cases(fields);
         for kv in fields.map.iter() {
             let key = kv.key().clone();
             let value = match kv.value().clone() {
@@ -143,6 +452,16 @@ impl Message {
             let field = self.fields_by_name.get(&key).ok_or(
                 protobuf::ProtobufError::MessageNotInitialized(format!("no field: {}", key)),
             )?;
+            if let Some(oneof) = &field.oneof {
+                if cases.get(oneof) != Some(&key) {
+                    log::warn!(
+                        "skipping {:?}, another member of oneof {} is set",
+                        key,
+                        oneof
+                    );
+                    continue;
+                }
+            }
 
             use protobuf::descriptor::field_descriptor_proto::Label;
             use protobuf::descriptor::field_descriptor_proto::Type::*;
@@ -158,23 +477,50 @@ impl Message {
                 write(output, value)
             }
 
-            fn write_value_repeated<'b, T: Clone>(
+            /// Writes a tag per item, or a single length-delimited run of items when packed.
+            fn write_value_repeated<T: Clone>(
                 field: &FieldDescriptorProto,
-                output: &mut protobuf::CodedOutputStream<'b>,
-                write: fn(&mut protobuf::CodedOutputStream<'b>, T) -> protobuf::ProtobufResult<()>,
+                output: &mut protobuf::CodedOutputStream,
+                write: for<'c> fn(
+                    &mut protobuf::CodedOutputStream<'c>,
+                    T,
+                ) -> protobuf::ProtobufResult<()>,
                 value: Vec<Value>,
                 wire_type: protobuf::wire_format::WireType,
                 extract_value: fn(Value) -> protobuf::ProtobufResult<T>,
+                packed: bool,
             ) -> protobuf::ProtobufResult<()> {
                 let num = u32::try_from(field.get_number()).unwrap();
-                output.write_tag(num, protobuf::wire_format::WireTypeLengthDelimited)?;
-                output.write_raw_varint64(u64::try_from(value.len()).unwrap())?;
-                for item in value {
-                    write(output, extract_value(item)?)?;
+                if !packed {
+                    for item in value {
+                        output.write_tag(num, wire_type)?;
+                        write(output, extract_value(item)?)?;
+                    }
+                    return Ok(());
                 }
-                Ok(())
+                if value.is_empty() {
+                    return Ok(());
+                }
+                let buf: Vec<u8> = Vec::with_capacity(100);
+                use bytes::BufMut;
+                let mut buf = buf.writer();
+                {
+                    let mut sub_output = protobuf::CodedOutputStream::new(&mut buf);
+                    for item in value {
+                        write(&mut sub_output, extract_value(item)?)?;
+                    }
+                }
+                let buf = buf.into_inner();
+                output.write_tag(num, protobuf::wire_format::WireTypeLengthDelimited)?;
+                output.write_raw_varint64(u64::try_from(buf.len()).unwrap())?;
+                output.write_all(&buf[..])
             }
 
+            // Fields without presence are unset when they hold their default.
+            if !self.has_presence(&field.descriptor) && value.is_default() {
+                continue;
+            }
+            let packed = self.is_packed(&field.descriptor);
             let field = &field.descriptor;
             match field.get_field_type() {
                 TYPE_DOUBLE => match value {
@@ -188,10 +534,11 @@ impl Message {
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_double_no_tag,
+                        |output, v: f64| output.write_double_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeFixed64,
                         |val| Ok(as_variant!(val, Value::Float)?.to_f64()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -200,16 +547,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_float_no_tag,
-                        v.to_f32(),
-                        protobuf::wire_format::WireTypeFixed64,
+                        in_range(v.to_f32())?,
+                        protobuf::wire_format::WireTypeFixed32,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_float_no_tag,
+                        |output, v: f32| output.write_float_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeFixed32,
-                        |val| Ok(as_variant!(val, Value::Float)?.to_f32()),
+                        |val| in_range(as_variant!(val, Value::Float)?.to_f32()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -218,16 +566,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_int64_no_tag,
-                        v.to_i64(),
+                        in_range(v.to_i64())?,
                         protobuf::wire_format::WireTypeVarint,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_int64_no_tag,
+                        |output, v: i64| output.write_int64_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_i64()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_i64()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -236,16 +585,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_uint64_no_tag,
-                        v.to_u64(),
+                        in_range(v.to_u64())?,
                         protobuf::wire_format::WireTypeVarint,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_uint64_no_tag,
+                        |output, v: u64| output.write_uint64_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_u64()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_u64()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -254,16 +604,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_int32_no_tag,
-                        v.to_i32(),
+                        in_range(v.to_i32())?,
                         protobuf::wire_format::WireTypeVarint,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_int32_no_tag,
+                        |output, v: i32| output.write_int32_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_i32()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_i32()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -272,16 +623,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_fixed64_no_tag,
-                        v.to_u64(),
+                        in_range(v.to_u64())?,
                         protobuf::wire_format::WireTypeFixed64,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_fixed64_no_tag,
+                        |output, v: u64| output.write_fixed64_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeFixed64,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_u64()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_u64()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -290,16 +642,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_fixed32_no_tag,
-                        v.to_u32(),
+                        in_range(v.to_u32())?,
                         protobuf::wire_format::WireTypeFixed32,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_fixed32_no_tag,
+                        |output, v: u32| output.write_fixed32_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeFixed32,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_u32()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_u32()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -314,10 +667,11 @@ impl Message {
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_bool_no_tag,
+                        |output, v: bool| output.write_bool_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeVarint,
                         |val| as_variant!(val, Value::Bool),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -340,6 +694,7 @@ impl Message {
                         arr,
                         protobuf::wire_format::WireTypeLengthDelimited,
                         |val| as_variant!(val, Value::String),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -362,6 +717,7 @@ impl Message {
                         arr,
                         protobuf::wire_format::WireTypeLengthDelimited,
                         |val| as_variant!(val, Value::Bytes),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -370,16 +726,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_uint32_no_tag,
-                        v.to_u32(),
+                        in_range(v.to_u32())?,
                         protobuf::wire_format::WireTypeVarint,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_uint32_no_tag,
+                        |output, v: u32| output.write_uint32_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_u32()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_u32()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -388,16 +745,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_sfixed32_no_tag,
-                        v.to_i32(),
-                        protobuf::wire_format::WireTypeVarint,
+                        in_range(v.to_i32())?,
+                        protobuf::wire_format::WireTypeFixed32,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_sfixed32_no_tag,
+                        |output, v: i32| output.write_sfixed32_no_tag(v),
                         arr,
-                        protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_i32()),
+                        protobuf::wire_format::WireTypeFixed32,
+                        |val| in_range(as_variant!(val, Value::Int)?.to_i32()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -406,16 +764,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_sfixed64_no_tag,
-                        v.to_i64(),
-                        protobuf::wire_format::WireTypeVarint,
+                        in_range(v.to_i64())?,
+                        protobuf::wire_format::WireTypeFixed64,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_sfixed64_no_tag,
+                        |output, v: i64| output.write_sfixed64_no_tag(v),
                         arr,
-                        protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_i64()),
+                        protobuf::wire_format::WireTypeFixed64,
+                        |val| in_range(as_variant!(val, Value::Int)?.to_i64()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -424,16 +783,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_sint32_no_tag,
-                        v.to_i32(),
+                        in_range(v.to_i32())?,
                         protobuf::wire_format::WireTypeVarint,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_sint32_no_tag,
+                        |output, v: i32| output.write_sint32_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_i32()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_i32()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -442,16 +802,17 @@ impl Message {
                         field,
                         output,
                         protobuf::CodedOutputStream::write_sint64_no_tag,
-                        v.to_i64(),
+                        in_range(v.to_i64())?,
                         protobuf::wire_format::WireTypeVarint,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
                         output,
-                        protobuf::CodedOutputStream::write_sint64_no_tag,
+                        |output, v: i64| output.write_sint64_no_tag(v),
                         arr,
                         protobuf::wire_format::WireTypeVarint,
-                        |val| Ok(as_variant!(val, Value::Int)?.to_i64()),
+                        |val| in_range(as_variant!(val, Value::Int)?.to_i64()),
+                        packed,
                     )?,
                     _ => continue,
                 },
@@ -464,7 +825,7 @@ impl Message {
                             protobuf::CodedOutputStream::write_enum_no_tag(output, val.value())
                         },
                         v,
-                        protobuf::wire_format::WireTypeLengthDelimited,
+                        protobuf::wire_format::WireTypeVarint,
                     )?,
                     Value::Array(arr) => write_value_repeated(
                         field,
@@ -474,16 +835,84 @@ impl Message {
                             protobuf::CodedOutputStream::write_enum_no_tag(output, val.value())
                         },
                         arr,
-                        protobuf::wire_format::WireTypeLengthDelimited,
+                        protobuf::wire_format::WireTypeVarint,
                         |val| as_variant!(val, Value::Enum),
+                        packed,
                     )?,
                     _ => continue,
                 },
                 TYPE_MESSAGE => {
                     let message_name = field.get_type_name().to_string();
-                    let parent = self.parent.clone();
-                    let other_message = parent.get(&message_name).unwrap();
+                    let other_message = self.message_type(&message_name).ok_or(
+                        protobuf::ProtobufError::MessageNotInitialized(format!(
+                            "no message called: {}",
+                            message_name
+                        )),
+                    )?;
                     let num = u32::try_from(field.get_number()).unwrap();
+                    if other_message.is_map_entry() {
+                        // JSON objects decode as messages, so accept either for a map.
+                        let entries: Vec<(String, Value)> = match value {
+                            Value::Map(entries) => entries.into_iter().collect(),
+                            Value::Message(item) => item
+                                .map
+                                .iter()
+                                .filter_map(|kv| Some((kv.key().clone(), kv.value().clone()?)))
+                                .collect(),
+                            _ => continue,
+                        };
+                        for (key, value) in entries {
+                            let entry = FieldsMap::new();
+                            let key = other_message.map_key(&key).map_err(|e| {
+                                protobuf::ProtobufError::MessageNotInitialized(e.to_string())
+                            })?;
+                            entry.insert("key".to_string(), Some(key));
+                            entry.insert("value".to_string(), Some(value));
+
+                            let buf: Vec<u8> = Vec::with_capacity(100);
+                            use bytes::BufMut;
+                            let mut buf = buf.writer();
+                            {
+                                let mut sub_output = protobuf::CodedOutputStream::new(&mut buf);
+                                other_message._write_bytes_from_fields(
+                                    &mut sub_output,
+                                    &Fields::new(entry),
+                                )?;
+                            }
+                            let buf = buf.into_inner();
+                            output
+                                .write_tag(num, protobuf::wire_format::WireTypeLengthDelimited)?;
+                            output.write_raw_varint64(u64::try_from(buf.len()).unwrap())?;
+                            output.write_all(&buf[..])?;
+                        }
+                        continue;
+                    }
+                    // Well-known types arrive in their JSON mapping, such as a string for a
+                    // Timestamp.
+                    let value = match WellKnown::from_type_name(&message_name) {
+                        Some(kind) => {
+                            let to_message = |value| {
+                                kind.from_json(&other_message, value)
+                                    .map(Value::Message)
+                                    .map_err(|e| {
+                                        protobuf::ProtobufError::MessageNotInitialized(
+                                            e.to_string(),
+                                        )
+                                    })
+                            };
+                            match (value, field.get_label()) {
+                                (Value::Array(items), Label::LABEL_REPEATED) => Value::Array(
+                                    items
+                                        .into_iter()
+                                        .map(to_message)
+                                        .collect::<protobuf::ProtobufResult<_>>()?,
+                                ),
+                                (Value::None, _) if kind != WellKnown::Value => continue,
+                                (value, _) => to_message(value)?,
+                            }
+                        }
+                        None => value,
+                    };
                     output.write_tag(num, protobuf::wire_format::WireTypeLengthDelimited)?;
                     match value {
                         Value::Message(item) => {
@@ -534,16 +963,16 @@ impl Message {
                 _ => continue,
             }
         }
-        Ok(())
+        output.write_raw_bytes(&fields.unknown[..])
     }
 
     fn from_field_descriptor_proto(
         &self,
         input: &mut CodedInputStream,
+        number: u32,
+        wire_type: protobuf::wire_format::WireType,
     ) -> ServiceResult<(String, Option<Value>)> {
         use protobuf::descriptor::field_descriptor_proto::Type::*;
-        let tag = input.read_tag()?;
-        let (number, wire_type) = tag.unpack();
         let field = self.fields.get(&number).ok_or(ServiceError::new(
             format!("field unknown: number({})", number).as_str(),
         ))?;
@@ -649,8 +1078,26 @@ impl Message {
                 TYPE_MESSAGE => {
                     let message_name = field.get_type_name().to_string();
                     let label = field.get_label();
+                    if let Some(entry) = self.message_type(&message_name) {
+                        if entry.is_map_entry() {
+                            return Ok((field.get_name().to_string(), entry.map_entry(input)?));
+                        }
+                    }
                     match self.parse_another_message(input, &message_name, field) {
-                        Ok(v) => parse_vec!(v, label, Value::Message),
+                        Ok(v) => match WellKnown::from_type_name(&message_name) {
+                            Some(kind) => {
+                                let message =
+                                    self.message_type(&message_name).ok_or(ServiceError::new(
+                                        format!("no message called: {}", message_name).as_str(),
+                                    ))?;
+                                let v = v
+                                    .iter()
+                                    .map(|fields| kind.to_json(&message, fields))
+                                    .collect::<ServiceResult<Vec<Value>>>()?;
+                                parse_vec!(v, label, std::convert::identity)
+                            }
+                            None => parse_vec!(v, label, Value::Message),
+                        },
                         _ => None,
                     }
                 }
@@ -659,6 +1106,27 @@ impl Message {
         ))
     }
 
+    /// Reads a single map entry as a map holding just that entry.
+    fn map_entry(&self, input: &mut CodedInputStream) -> ServiceResult<Option<Value>> {
+        let len = input.pos() + input.read_raw_varint64()?;
+        let entry = self.fields_from_bytes_delimited(input, len)?;
+        let key = match entry.map.get("key").and_then(|key| key.value().clone()) {
+            Some(key) => key
+                .to_map_key()
+                .ok_or(ServiceError::new("unsupported map key type"))?,
+            // An absent key is the default of its type.
+            None => self.default_map_key(),
+        };
+        let value = entry
+            .map
+            .get("value")
+            .and_then(|value| value.value().clone())
+            .unwrap_or(Value::None);
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(key, value);
+        Ok(Some(Value::Map(map)))
+    }
+
     fn parse_another_message(
         &self,
         input: &mut CodedInputStream,
@@ -666,8 +1134,7 @@ impl Message {
         field: &FieldDescriptorProto,
     ) -> ServiceResult<Vec<Fields>> {
         use protobuf::descriptor::field_descriptor_proto::Label::*;
-        let parent = self.parent.clone();
-        let message = parent.get(message_name).ok_or(ServiceError::new(
+        let message = self.message_type(message_name).ok_or(ServiceError::new(
             format!("no message called: {}", message_name).as_str(),
         ))?;
         let repeated_len = if field.get_label() == LABEL_REPEATED {
@@ -684,6 +1151,57 @@ impl Message {
     }
 }
 
+/// Copies a field missing from the descriptor, tag and value, onto `out`.
+fn read_unknown(
+    input: &mut CodedInputStream,
+    number: u32,
+    wire_type: protobuf::wire_format::WireType,
+    out: &mut Vec<u8>,
+) -> ServiceResult<()> {
+    use bytes::BufMut;
+    use protobuf::wire_format::*;
+    let mut buf = Vec::with_capacity(16).writer();
+    {
+        let mut output = protobuf::CodedOutputStream::new(&mut buf);
+        output.write_tag(number, wire_type)?;
+        match wire_type {
+            WireTypeVarint => output.write_raw_varint64(input.read_raw_varint64()?)?,
+            WireTypeFixed64 => {
+                output.write_raw_little_endian64(input.read_raw_little_endian64()?)?
+            }
+            WireTypeFixed32 => {
+                output.write_raw_little_endian32(input.read_raw_little_endian32()?)?
+            }
+            WireTypeLengthDelimited => output.write_bytes_no_tag(&input.read_bytes()?[..])?,
+            _ => {
+                return Err(ServiceError::new(
+                    format!("unsupported wire type of field {}", number).as_str(),
+                ))
+            }
+        }
+    }
+    out.extend(buf.into_inner());
+    Ok(())
+}
+
+/// Finds a message by a field's type name.
+///
+/// Type names are fully qualified (`.pkg.Outer.Inner`) and messages are registered by full
+/// name without the leading dot. Leading scopes are dropped until a registered name matches,
+/// for registries keyed by names relative to their package.
+pub fn find_message<'a>(
+    messages: &'a DashMap<String, Message>,
+    type_name: &str,
+) -> Option<Ref<'a, String, Message>> {
+    let mut name = type_name.trim_start_matches('.');
+    loop {
+        if let Some(message) = messages.get(name) {
+            return Some(message);
+        }
+        name = name.splitn(2, '.').nth(1)?;
+    }
+}
+
 mod message_tests {
     use std::hash::Hash;
 
@@ -853,7 +1371,7 @@ mod message_tests {
         ];
 
         let buf: &[u8] = &[
-            0, 0, 0, 0, 0, // gRPC header.
+            0, 0, 0, 0, 27, // gRPC header.
             0x08, 0x96, 0x01, // Field varint
             0x12, 0x07, 0x74, 0x65, 0x73, 0x74, 0x69, 0x6e, 0x67, // Field string
             0x1a, 0x03, 0x08, 0x96, 0x01, // Embedded message
@@ -895,6 +1413,227 @@ mod message_tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_oneof_fields() -> ServiceResult<()> {
+        use super::*;
+        use protobuf::descriptor::field_descriptor_proto::Type::*;
+
+        let mut desc = protobuf::descriptor::DescriptorProto::new();
+        let mut oneof = protobuf::descriptor::OneofDescriptorProto::new();
+        oneof.set_name("result".to_string());
+        desc.oneof_decl.push(oneof);
+        for (name, number) in &[("ok", 1), ("error", 2)] {
+            let mut field = FieldDescriptorProto::new();
+            field.set_name(name.to_string());
+            field.set_number(*number);
+            field.set_field_type(TYPE_STRING);
+            field.set_oneof_index(0);
+            desc.field.push(field);
+        }
+        let m = Message::new(desc, "".to_string(), Arc::new(DashMap::new()));
+
+        // A JSON backend setting both members only gets the first one encoded.
+        let fields = FieldsMap::new();
+        fields.insert(
+            "ok".to_string(),
+            Some(Value::from_string("yes".to_string())),
+        );
+        fields.insert(
+            "error".to_string(),
+            Some(Value::from_string("no".to_string())),
+        );
+        let got = m.fields_from_bytes(&m.bytes_from_fields(&Fields::new(fields))?[..])?;
+        assert_eq!(got.map.len(), 1);
+        assert_eq!(got.oneofs.get("result").unwrap().value(), "ok");
+
+        // Tagged objects are read back as their member.
+        let tagged = FieldsMap::new();
+        tagged.insert(
+            "error".to_string(),
+            Some(Value::from_string("no".to_string())),
+        );
+        let fields = FieldsMap::new();
+        fields.insert(
+            "result".to_string(),
+            Some(Value::Message(Fields::new(tagged))),
+        );
+        let got = m.fields_from_bytes(&m.bytes_from_fields(&Fields::new(fields))?[..])?;
+        assert_eq!(got.oneofs.get("result").unwrap().value(), "error");
+        assert!(got.map.contains_key("error"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_fields() -> ServiceResult<()> {
+        use super::*;
+        use protobuf::descriptor::field_descriptor_proto::Label::*;
+        use protobuf::descriptor::field_descriptor_proto::Type::{self, *};
+
+        fn field(name: &str, number: i32, field_type: Type) -> FieldDescriptorProto {
+            let mut field = FieldDescriptorProto::new();
+            field.set_name(name.to_string());
+            field.set_number(number);
+            field.set_field_type(field_type);
+            field
+        }
+
+        let mut entry = protobuf::descriptor::DescriptorProto::new();
+        entry.set_name("LabelsEntry".to_string());
+        entry.field.push(field("key", 1, TYPE_STRING));
+        entry.field.push(field("value", 2, TYPE_INT32));
+        let mut opts = protobuf::descriptor::MessageOptions::new();
+        opts.set_map_entry(true);
+        entry.options = Some(opts).into();
+
+        let mut desc = protobuf::descriptor::DescriptorProto::new();
+        let mut labels = field("labels", 1, TYPE_MESSAGE);
+        labels.set_label(LABEL_REPEATED);
+        labels.set_type_name(".pkg.Outer.LabelsEntry".to_string());
+        desc.field.push(labels);
+
+        let parent: Arc<DashMap<String, Message>> = Arc::new(DashMap::new());
+        parent.insert(
+            "Outer.LabelsEntry".to_string(),
+            Message::new(entry, "".to_string(), parent.clone()),
+        );
+        let m = Message::new(desc, "".to_string(), parent);
+
+        // A JSON object, as decoded by the JSON handler.
+        let object = FieldsMap::new();
+        object.insert("a".to_string(), Some(Value::from_int(1)));
+        object.insert("b".to_string(), Some(Value::from_int(2)));
+        let fields = FieldsMap::new();
+        fields.insert(
+            "labels".to_string(),
+            Some(Value::Message(Fields::new(object))),
+        );
+        let buf = m.bytes_from_fields(&Fields::new(fields))?;
+
+        let got = m.fields_from_bytes(&buf[..])?;
+        let got = got.map.get("labels").unwrap().clone().unwrap();
+        let mut want = std::collections::BTreeMap::new();
+        want.insert("a".to_string(), Value::from_int(1));
+        want.insert("b".to_string(), Value::from_int(2));
+        assert_eq!(got, Value::Map(want));
+        assert_eq!(serde_json::to_string(&got).unwrap(), "{\"a\":1,\"b\":2}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_and_presence() -> ServiceResult<()> {
+        use super::*;
+        use protobuf::descriptor::field_descriptor_proto::Label::*;
+        use protobuf::descriptor::field_descriptor_proto::Type::*;
+
+        let mut desc = protobuf::descriptor::DescriptorProto::new();
+        let mut oneof = protobuf::descriptor::OneofDescriptorProto::new();
+        oneof.set_name("_limit".to_string());
+        desc.oneof_decl.push(oneof);
+        for (name, number) in &[("ids", 1), ("count", 2), ("limit", 3)] {
+            let mut field = FieldDescriptorProto::new();
+            field.set_name(name.to_string());
+            field.set_number(*number);
+            field.set_field_type(TYPE_INT32);
+            field.set_label(LABEL_OPTIONAL);
+            desc.field.push(field);
+        }
+        desc.field[0].set_label(LABEL_REPEATED);
+        desc.field[2].set_proto3_optional(true);
+        desc.field[2].set_oneof_index(0);
+        let parent = Arc::new(DashMap::new());
+        let m = Message::new(desc.clone(), "".to_string(), parent.clone()).with_syntax("proto3");
+
+        // Unpacked and packed runs of the same field are both read.
+        let buf: &[u8] = &[
+            0, 0, 0, 0, 11, // gRPC header.
+            0x08, 0x01, 0x08, 0x02, // Unpacked ids
+            0x0a, 0x01, 0x03, // Packed ids
+            0x10, 0x00, // count, no presence
+            0x18, 0x00, // limit, proto3 optional
+        ];
+        let got = m.fields_from_bytes(buf)?;
+        let ids = vec![Value::from_int(1), Value::from_int(2), Value::from_int(3)];
+        assert_eq!(
+            got.map.get("ids").unwrap().clone(),
+            Some(Value::Array(ids.clone()))
+        );
+        assert!(!got.map.contains_key("count"));
+        assert_eq!(
+            got.map.get("limit").unwrap().clone(),
+            Some(Value::from_int(0))
+        );
+
+        let fields = FieldsMap::new();
+        fields.insert("ids".to_string(), Some(Value::Array(ids.clone())));
+        fields.insert("count".to_string(), Some(Value::from_int(0)));
+        let fields = Fields::new(fields);
+        assert_eq!(
+            &m.bytes_from_fields(&fields)?[..],
+            &[0, 0, 0, 0, 5, 0x0a, 0x03, 0x01, 0x02, 0x03]
+        );
+
+        // proto2 writes repeated scalars unpacked and keeps explicit defaults.
+        let proto2 = Message::new(desc.clone(), "".to_string(), parent.clone());
+        let buf = proto2.bytes_from_fields(&fields)?;
+        let got = proto2.fields_from_bytes(&buf[..])?;
+        assert_eq!(buf.len(), 5 + 6 + 2);
+        assert_eq!(got.map.get("ids").unwrap().clone(), Some(Value::Array(ids)));
+        assert_eq!(
+            got.map.get("count").unwrap().clone(),
+            Some(Value::from_int(0))
+        );
+
+        // Values that do not fit the field fail instead of being written as zero.
+        for value in vec![
+            Value::from_int(i64::from(i32::MAX) + 1),
+            Value::Array(vec![Value::from_int(1), Value::from_int(u64::MAX)]),
+        ] {
+            let fields = FieldsMap::new();
+            fields.insert("ids".to_string(), Some(value));
+            assert!(m.bytes_from_fields(&Fields::new(fields)).is_err());
+        }
+
+        desc.field[1].set_label(LABEL_REQUIRED);
+        let required = Message::new(desc, "".to_string(), parent);
+        assert!(required.fields_from_bytes(&[0, 0, 0, 0, 0]).is_err());
+        assert!(required
+            .bytes_from_fields(&Fields::new(FieldsMap::new()))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_fields_and_wide_values() -> ServiceResult<()> {
+        use super::*;
+        use protobuf::descriptor::field_descriptor_proto::Type::*;
+
+        let mut desc = protobuf::descriptor::DescriptorProto::new();
+        let mut field = FieldDescriptorProto::new();
+        field.set_name("id".to_string());
+        field.set_number(1);
+        field.set_field_type(TYPE_UINT64);
+        desc.field.push(field);
+        let m = Message::new(desc, "".to_string(), Arc::new(DashMap::new()));
+
+        let buf: &[u8] = &[
+            0, 0, 0, 0, 20, // gRPC header.
+            0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // u64::MAX id
+            0x12, 0x02, 0x68, 0x69, // Unknown string
+            0x1d, 0x01, 0x02, 0x03, 0x04, // Unknown fixed32
+        ];
+        let got = m.fields_from_bytes(buf)?;
+        let id = got.map.get("id").unwrap().clone().unwrap();
+        assert_eq!(id, Value::from_int(u64::MAX));
+        assert_ne!(id, Value::from_int(-1i64));
+        assert_eq!(serde_json::to_string(&id).unwrap(), "18446744073709551615");
+        assert_eq!(got.map.len(), 1);
+        assert_eq!(&m.bytes_from_fields(&got)?[..], buf);
+
+        let float = Value::from_float(0.1f32);
+        assert_eq!(serde_json::to_string(&float).unwrap(), "0.1");
+        Ok(())
+    }
 }
 
 impl Clone for Message {
@@ -906,6 +1645,7 @@ impl Clone for Message {
             parent: self.parent.clone(),
             fields_by_name: self.fields_by_name.clone(),
             name: self.name.clone(),
+            proto3: self.proto3,
         }
     }
 }
@@ -1,5 +1,7 @@
 pub mod message;
+pub mod template;
 pub mod value;
+pub mod well_known;
 
 use crate::broker::Broker;
 use crate::handlers::json::JsonHandler;
@@ -7,10 +9,13 @@ use crate::handlers::sql::SQLHandler;
 use crate::proto;
 use crate::proto::gen::format::postgres::exts::postgres;
 use crate::services::message::Message;
+use crate::services::template::{EnvAllowList, HeaderTemplate, PathTemplate, SuccessStatus};
 use access_json::JSONQuery;
 use async_trait::async_trait;
 use config;
 use dashmap::DashMap;
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, StreamExt};
 use protobuf::descriptor::{MethodDescriptorProto, MethodOptions};
 use protobuf::{self};
 use protobuf_parse;
@@ -18,7 +23,7 @@ use redis::ToRedisArgs;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 use std::mem::ManuallyDrop;
@@ -46,11 +51,133 @@ pub mod format {
 pub mod base {
     pub use crate::proto::gen::pandit::exts::cache as method_cache;
     pub use crate::proto::gen::pandit::exts::default_cache;
+    pub use crate::proto::gen::pandit::exts::default_forward_metadata;
+    pub use crate::proto::gen::pandit::exts::default_return_headers;
+    pub use crate::proto::gen::pandit::exts::error as method_error;
     pub use crate::proto::gen::pandit::exts::field_cache;
+    pub use crate::proto::gen::pandit::exts::forward_metadata as method_forward_metadata;
+    pub use crate::proto::gen::pandit::exts::return_headers as method_return_headers;
+    pub use crate::proto::gen::pandit::exts::timeout_ms as method_timeout;
     pub use crate::proto::gen::pandit::CacheOptions;
+    pub use crate::proto::gen::pandit::ErrorMapping;
+    pub use crate::proto::gen::pandit::StatusCode;
 }
 
-pub type ServiceResult<T> = Result<T, Box<dyn std::error::Error>>;
+pub type ServiceResult<T> = Result<T, ServiceError>;
+
+/// Stream of gRPC framed messages sent in response to a single call.
+pub type MessageStream = BoxStream<'static, ServiceResult<bytes::Bytes>>;
+
+/// Stream of decoded responses from a writer for server-streaming methods.
+pub type FieldsStream = BoxStream<'static, ServiceResult<Fields>>;
+
+/// Point in time by which a call must complete, if the call has one.
+pub type Deadline = Option<tokio::time::Instant>;
+
+/// Fails with `DeadlineExceeded` if the future has not resolved by the deadline.
+pub async fn with_deadline<T, F>(deadline: Deadline, fut: F) -> ServiceResult<T>
+where
+    F: std::future::Future<Output = ServiceResult<T>>,
+{
+    match deadline {
+        Some(deadline) => match tokio::time::timeout_at(deadline, fut).await {
+            Ok(resp) => resp,
+            Err(_) => Err(ServiceError::DeadlineExceeded(
+                "deadline exceeded".to_string(),
+            )),
+        },
+        None => fut.await,
+    }
+}
+
+/// Ends a response stream with `DeadlineExceeded` once the deadline passes.
+pub fn stream_with_deadline(resp: MessageStream, deadline: Deadline) -> MessageStream {
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return resp,
+    };
+    stream::unfold((resp, false), move |(mut resp, done)| async move {
+        if done {
+            return None;
+        }
+        match tokio::time::timeout_at(deadline, resp.next()).await {
+            Ok(Some(item)) => Some((item, (resp, false))),
+            Ok(None) => None,
+            Err(_) => {
+                let err = ServiceError::DeadlineExceeded("deadline exceeded".to_string());
+                Some((Err(err), (resp, true)))
+            }
+        }
+    })
+    .boxed()
+}
+
+/// gRPC metadata of a call: what the client sent, and the backend response headers
+/// that go back to it in the trailers.
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    pub request: http::HeaderMap,
+    response: Arc<std::sync::Mutex<http::HeaderMap>>,
+}
+
+impl Metadata {
+    pub fn new(request: http::HeaderMap) -> Self {
+        Self {
+            request,
+            response: Default::default(),
+        }
+    }
+
+    /// Request metadata a client may set, leaving out gRPC and transport headers.
+    pub fn forwardable(&self) -> impl Iterator<Item = (&http::HeaderName, &http::HeaderValue)> {
+        self.request.iter().filter(|(name, _)| !is_reserved(name.as_str()))
+    }
+
+    /// Values of the listed request keys, for passing on to a backend.
+    pub fn select(&self, keys: &[String]) -> Vec<(String, String)> {
+        keys.iter()
+            .filter(|key| !is_reserved(key))
+            .filter_map(|key| {
+                let value = self.request.get(key.as_str())?.to_str().ok()?;
+                Some((key.clone(), value.to_string()))
+            })
+            .collect()
+    }
+
+    /// Keeps the listed backend headers to send back to the client.
+    pub fn add_response(&self, headers: &http::HeaderMap, names: &[String]) {
+        let mut response = self.response.lock().unwrap();
+        for name in names.iter().filter(|name| !is_reserved(name)) {
+            for value in headers.get_all(name.as_str()) {
+                if let Ok(name) = http::HeaderName::from_str(name) {
+                    response.append(name, value.clone());
+                }
+            }
+        }
+    }
+
+    /// Keeps every non-reserved header, for metadata already selected by another node.
+    pub fn extend_response(&self, headers: &http::HeaderMap) {
+        let mut response = self.response.lock().unwrap();
+        for (name, value) in headers.iter().filter(|(name, _)| !is_reserved(name.as_str())) {
+            response.append(name.clone(), value.clone());
+        }
+    }
+
+    pub fn response(&self) -> http::HeaderMap {
+        self.response.lock().unwrap().clone()
+    }
+}
+
+/// Headers that belong to the gRPC protocol, HTTP/2 or pandit itself.
+fn is_reserved(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("grpc-")
+        || name.starts_with("pandit-")
+        || name.starts_with(':')
+        || ["content-type", "content-length", "te", "host", "user-agent", "method", "uri"]
+            .contains(&name.as_str())
+}
 
 impl FromStr for Protocol {
     type Err = ();
@@ -63,30 +190,179 @@ impl FromStr for Protocol {
 }
 
 #[derive(Debug, Clone)]
-pub struct ServiceError {
-    err: String,
+pub enum ServiceError {
+    InvalidArgument(String),
+    DeadlineExceeded(String),
+    NotFound(String),
+    AlreadyExists(String),
+    PermissionDenied(String),
+    ResourceExhausted(String),
+    Unimplemented(String),
+    Internal(String),
+    Unavailable(String),
+    Unauthenticated(String),
+    /// Failure reported by a backend, keyed by its HTTP status or SQLSTATE.
+    Backend { code: String, message: String },
+    /// Status already resolved, either by a method override or by another node.
+    Status {
+        code: base::StatusCode,
+        message: String,
+    },
 }
 
 impl ServiceError {
-    pub fn new(err: &str) -> Box<Self> {
-        Box::new(ServiceError {
-            err: String::from_str(err).unwrap(),
-        })
+    pub fn new(err: &str) -> Self {
+        ServiceError::Internal(err.to_string())
+    }
+
+    /// Resolves a backend failure using the method's error overrides.
+    pub fn with_mappings(self, mappings: &[base::ErrorMapping]) -> Self {
+        match self {
+            ServiceError::Backend { code, message } => {
+                match mappings.iter().find(|mapping| mapping.backend_code == code) {
+                    Some(mapping) => ServiceError::Status {
+                        code: mapping.status.enum_value().unwrap_or_default(),
+                        message,
+                    },
+                    None => ServiceError::Backend { code, message },
+                }
+            }
+            err => err,
+        }
+    }
+
+    /// gRPC status reported to the client.
+    pub fn status(&self) -> base::StatusCode {
+        use base::StatusCode::*;
+        match self {
+            ServiceError::InvalidArgument(_) => INVALID_ARGUMENT,
+            ServiceError::DeadlineExceeded(_) => DEADLINE_EXCEEDED,
+            ServiceError::NotFound(_) => NOT_FOUND,
+            ServiceError::AlreadyExists(_) => ALREADY_EXISTS,
+            ServiceError::PermissionDenied(_) => PERMISSION_DENIED,
+            ServiceError::ResourceExhausted(_) => RESOURCE_EXHAUSTED,
+            ServiceError::Unimplemented(_) => UNIMPLEMENTED,
+            ServiceError::Internal(_) => INTERNAL,
+            ServiceError::Unavailable(_) => UNAVAILABLE,
+            ServiceError::Unauthenticated(_) => UNAUTHENTICATED,
+            ServiceError::Status { code, .. } => *code,
+            ServiceError::Backend { code, .. } => match code.as_str() {
+                "400" => INVALID_ARGUMENT,
+                "401" => UNAUTHENTICATED,
+                "403" => PERMISSION_DENIED,
+                "404" => NOT_FOUND,
+                "409" => ALREADY_EXISTS,
+                "429" => RESOURCE_EXHAUSTED,
+                "501" => UNIMPLEMENTED,
+                "502" | "503" => UNAVAILABLE,
+                "504" => DEADLINE_EXCEEDED,
+                "23505" => ALREADY_EXISTS, // Postgres unique_violation.
+                _ => UNKNOWN,
+            },
+        }
     }
 }
 
 impl Display for ServiceError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "unable to create service object: {}", self.err)
+        match self {
+            ServiceError::InvalidArgument(err)
+            | ServiceError::DeadlineExceeded(err)
+            | ServiceError::NotFound(err)
+            | ServiceError::AlreadyExists(err)
+            | ServiceError::PermissionDenied(err)
+            | ServiceError::ResourceExhausted(err)
+            | ServiceError::Unimplemented(err)
+            | ServiceError::Internal(err)
+            | ServiceError::Unavailable(err)
+            | ServiceError::Unauthenticated(err) => write!(f, "{}", err),
+            ServiceError::Backend { code, message } => {
+                write!(f, "backend returned {}: {}", code, message)
+            }
+            ServiceError::Status { message, .. } => write!(f, "{}", message),
+        }
     }
 }
 
 impl std::error::Error for ServiceError {}
 
+/// Converts errors of the libraries pandit calls into internal errors.
+macro_rules! internal_error_from {
+    ($($err:ty),* $(,)?) => {
+        $(
+            impl From<$err> for ServiceError {
+                fn from(err: $err) -> Self {
+                    ServiceError::Internal(err.to_string())
+                }
+            }
+        )*
+    };
+}
+
+internal_error_from!(
+    std::io::Error,
+    std::env::VarError,
+    std::net::AddrParseError,
+    std::time::SystemTimeError,
+    std::array::TryFromSliceError,
+    std::num::TryFromIntError,
+    h2::Error,
+    http::Error,
+    http::header::InvalidHeaderName,
+    http::header::InvalidHeaderValue,
+    http::method::InvalidMethod,
+    hyper::Error,
+    openssl::error::ErrorStack,
+    serde_json::Error,
+    protobuf::ProtobufError,
+    redis::RedisError,
+    config::ConfigError,
+    tokio_postgres::Error,
+    kube::Error,
+    bollard::errors::Error,
+    grpcio::Error,
+    crossbeam_channel::RecvError,
+);
+
+impl<T> From<crossbeam_channel::SendError<T>> for ServiceError {
+    fn from(err: crossbeam_channel::SendError<T>) -> Self {
+        ServiceError::Internal(err.to_string())
+    }
+}
+
+impl<T> From<tokio::sync::mpsc::error::SendError<T>> for ServiceError {
+    fn from(err: tokio::sync::mpsc::error::SendError<T>) -> Self {
+        ServiceError::Internal(err.to_string())
+    }
+}
+
 #[async_trait]
 pub trait Handler {
     fn from_payload(&self, buf: bytes::Bytes) -> ServiceResult<Fields>;
+
+    /// Parses a backend response, given its status and headers when the writer has them.
+    fn from_response(
+        &self,
+        _status: Option<http::StatusCode>,
+        _headers: Option<&http::HeaderMap>,
+        buf: bytes::Bytes,
+    ) -> ServiceResult<Fields> {
+        self.from_payload(buf)
+    }
+
+    /// Splits a payload into one set of fields per streamed message.
+    fn from_payload_stream(&self, buf: bytes::Bytes) -> ServiceResult<Vec<Fields>> {
+        Ok(vec![self.from_payload(buf)?])
+    }
+
     async fn to_payload(&self, fields: &Fields) -> ServiceResult<bytes::Bytes>;
+
+    /// Builds a single payload for every message of a client-streaming call.
+    async fn to_payload_batch(&self, _fields: &[Fields]) -> ServiceResult<bytes::Bytes> {
+        Err(ServiceError::new(
+            "batched payloads are not supported by this handler",
+        ))
+    }
 }
 
 pub struct MessageField {
@@ -110,6 +386,10 @@ pub type FieldsMap = DashMap<String, Option<Value>>;
 #[derive(Debug, Clone)]
 pub struct Fields {
     pub map: FieldsMap,
+    /// Member set in each oneof, by oneof name, recorded when decoding protobuf.
+    pub oneofs: DashMap<String, String>,
+    /// Encoded fields the message descriptor does not know, written back out as they are.
+    pub unknown: Vec<u8>,
 }
 
 impl std::hash::Hash for Fields {
@@ -170,7 +450,11 @@ impl<'de> Deserialize<'de> for Fields {
 
 impl Fields {
     pub fn new(map: FieldsMap) -> Self {
-        Self { map }
+        Self {
+            map,
+            oneofs: Default::default(),
+            unknown: Vec::new(),
+        }
     }
 }
 
@@ -197,12 +481,57 @@ impl Serialize for Fields {
 }
 
 pub struct Method {
-    pub api: Option<format::HTTP>,
+    pub api: Option<HttpApi>,
     pub handler: Option<Arc<dyn Handler + Sync + Send + 'static>>,
     pub input_message: String,
     pub output_message: String,
     pub cache: Option<base::CacheOptions>,
     pub primary_key: Option<String>,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+    pub errors: Vec<base::ErrorMapping>,
+    pub timeout_ms: Option<u64>,
+    /// Request metadata keys passed to the backend.
+    pub forward_metadata: Vec<String>,
+    /// Backend response headers returned to the client.
+    pub return_headers: Vec<String>,
+}
+
+/// HTTP mapping of a method, with its templates parsed once the service is loaded.
+#[derive(Clone)]
+pub struct HttpApi {
+    pub http: format::HTTP,
+    /// HTTP method of the pattern, such as `GET`.
+    pub method: &'static str,
+    pub path: PathTemplate,
+    /// Header templates by lowercase name, the method's replacing the service's.
+    pub headers: Vec<(String, HeaderTemplate)>,
+    pub success_status: SuccessStatus,
+}
+
+impl HttpApi {
+    /// Parses the templates of a method's HTTP mapping, failing on invalid ones.
+    fn new(http: format::HTTP, headers: Vec<(String, HeaderTemplate)>) -> ServiceResult<Self> {
+        use crate::proto::gen::format::http::http::Pattern;
+        let (method, path) = match http.pattern.as_ref().ok_or(ServiceError::InvalidArgument(
+            "no pattern in api".to_string(),
+        ))? {
+            Pattern::get(s) => ("GET", s),
+            Pattern::put(s) => ("PUT", s),
+            Pattern::post(s) => ("POST", s),
+            Pattern::delete(s) => ("DELETE", s),
+            Pattern::patch(s) => ("PATCH", s),
+        };
+        let path = PathTemplate::parse(path)?;
+        let success_status = SuccessStatus::parse(&http.success_status)?;
+        Ok(Self {
+            http,
+            method,
+            path,
+            headers,
+            success_status,
+        })
+    }
 }
 
 impl Serialize for base::CacheOptions {
@@ -267,11 +596,13 @@ impl Serialize for Method {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut strc = sr.serialize_struct("Method", 4)?;
+        let mut strc = sr.serialize_struct("Method", 6)?;
         strc.serialize_field("input_message", &self.input_message)?;
         strc.serialize_field("output_message", &self.output_message)?;
         strc.serialize_field("cache", &self.cache)?;
         strc.serialize_field("primary_key", &self.primary_key)?;
+        strc.serialize_field("client_streaming", &self.client_streaming)?;
+        strc.serialize_field("server_streaming", &self.server_streaming)?;
         strc.end()
     }
 }
@@ -297,6 +628,8 @@ impl<'de> Deserialize<'de> for Method {
                 let mut output_message = None;
                 let mut cache = None;
                 let mut primary_key = None;
+                let mut client_streaming = None;
+                let mut server_streaming = None;
                 while let Some(key) = map.next_key()? {
                     match key {
                         "input_message" => {
@@ -311,6 +644,12 @@ impl<'de> Deserialize<'de> for Method {
                         "primary_key" => {
                             primary_key = Some(map.next_value()?);
                         }
+                        "client_streaming" => {
+                            client_streaming = Some(map.next_value()?);
+                        }
+                        "server_streaming" => {
+                            server_streaming = Some(map.next_value()?);
+                        }
                         _ => continue,
                     }
                 }
@@ -322,6 +661,12 @@ impl<'de> Deserialize<'de> for Method {
                     cache: cache.ok_or_else(|| de::Error::missing_field("cache"))?,
                     primary_key: primary_key
                         .ok_or_else(|| de::Error::missing_field("primary_key"))?,
+                    client_streaming: client_streaming.unwrap_or_default(),
+                    server_streaming: server_streaming.unwrap_or_default(),
+                    errors: Default::default(),
+                    timeout_ms: None,
+                    forward_metadata: Default::default(),
+                    return_headers: Default::default(),
                     api: None,
                     handler: None,
                 };
@@ -334,12 +679,15 @@ impl<'de> Deserialize<'de> for Method {
     }
 }
 
-pub type Services = DashMap<String, Service>;
+/// Services of this node, shared so calls do not hold the map while they run.
+pub type Services = DashMap<String, Arc<Service>>;
 
-pub type WriterRef = Box<Mutex<dyn Writer>>;
+pub type WriterRef = Arc<Mutex<dyn Writer>>;
 
 pub struct Service {
     pub name: String,
+    /// Name of the service in its proto file, which calls are routed by.
+    pub proto_name: String,
     pub protocol: Protocol,
     pub methods: DashMap<String, Method>,
     pub messages: Arc<DashMap<String, Message>>,
@@ -347,38 +695,78 @@ pub struct Service {
     pub default_handler: Option<Arc<dyn Handler + Sync + Send + 'static>>,
     pub default_cache: base::CacheOptions,
     pub broker: Arc<Broker>,
+    /// Every file the service was parsed from, imports included.
+    pub descriptors: Vec<protobuf::descriptor::FileDescriptorProto>,
 }
 
 impl Service {
-    pub fn from_file(
+    /// Loads every service defined in a proto file, building a writer for each one.
+    ///
+    /// Header templates may only read the environment variables `env` allows.
+    pub fn from_file<F>(
         path: &str,
         include: &[&str],
-        writer: WriterRef,
+        mut writer: F,
         broker: Arc<Broker>,
-    ) -> Result<Self, ServiceError> {
+        env: &EnvAllowList,
+    ) -> Result<Vec<Self>, ServiceError>
+    where
+        F: FnMut(&protobuf::descriptor::ServiceDescriptorProto) -> ServiceResult<WriterRef>,
+    {
         let path_buf = &PathBuf::from(path);
         let include: Vec<PathBuf> = include.iter().map(|v| PathBuf::from(v)).collect();
-        let parsed =
-            protobuf_parse::pure::parse_and_typecheck(&include[..], &[path_buf.clone()]).unwrap();
-        let filename = path_buf.file_name().unwrap().to_str().unwrap();
+        let parsed = protobuf_parse::pure::parse_and_typecheck(&include[..], &[path_buf.clone()])
+            .map_err(|err| ServiceError::InvalidArgument(err.to_string()))?;
+        // Descriptors are named by their import path, relative to an include directory.
         let file = parsed
             .file_descriptors
             .iter()
-            .find(|&x| {
-                let name = x.get_name().to_string();
-                name.ends_with(filename)
-            })
-            .unwrap();
-        let service = file.service.first().unwrap();
+            .find(|&x| path_buf.ends_with(x.get_name()))
+            .ok_or(ServiceError::NotFound(format!(
+                "no descriptor for {}",
+                path
+            )))?;
+        if file.service.is_empty() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "no service in {}",
+                path
+            )));
+        }
 
-        let mut output = Self::get_service_attrs_base(file, writer, broker, &service)?;
-        match Self::get_service_type(&service) {
-            Protocol::HTTP => output.get_service_attrs_http(&service)?,
-            Protocol::Postgres => output.get_service_attrs_postgres(&service)?,
-            _ => panic!("unknown protocol"),
-        };
+        // Services of a file share its messages.
+        let messages = Self::messages_from_files(&parsed.file_descriptors);
+        let mut services = Vec::with_capacity(file.service.len());
+        for service in file.service.iter() {
+            let protocol = Self::get_service_type(service);
+            if let Protocol::None = protocol {
+                log::info!(
+                    "skipping service {} without an http or postgres format",
+                    service.get_name()
+                );
+                continue;
+            }
+            let mut output = Self::get_service_attrs_base(
+                messages.clone(),
+                writer(service)?,
+                broker.clone(),
+                service,
+            )?;
+            match protocol {
+                Protocol::HTTP => output.get_service_attrs_http(service, env)?,
+                Protocol::Postgres => output.get_service_attrs_postgres(service)?,
+                Protocol::None => {}
+            };
+            output.descriptors = parsed.file_descriptors.clone();
+            services.push(output);
+        }
+        if services.is_empty() {
+            return Err(ServiceError::InvalidArgument(format!(
+                "no service with an http or postgres format in {}",
+                path
+            )));
+        }
 
-        Ok(output)
+        Ok(services)
     }
 
     fn get_service_type(service: &protobuf::descriptor::ServiceDescriptorProto) -> Protocol {
@@ -397,23 +785,52 @@ impl Service {
         }
     }
 
-    fn get_service_attrs_base(
-        file: &protobuf::descriptor::FileDescriptorProto,
-        writer: WriterRef,
-        broker: Arc<Broker>,
-        service: &protobuf::descriptor::ServiceDescriptorProto,
-    ) -> Result<Self, ServiceError> {
+    /// Flattens messages and the types nested in them, such as map entries, naming nested
+    /// types `Outer.Inner`.
+    fn nested_messages(
+        scope: &str,
+        messages: &[protobuf::descriptor::DescriptorProto],
+    ) -> Vec<(String, protobuf::descriptor::DescriptorProto)> {
+        let mut out = Vec::with_capacity(messages.len());
+        for message in messages {
+            let name = format!("{}{}", scope, message.get_name());
+            let scope = format!("{}.", name);
+            out.extend(Self::nested_messages(&scope, &message.nested_type));
+            out.push((name, message.clone()));
+        }
+        out
+    }
+
+    /// Full name of a message type, as used to key `messages`: the type name without its
+    /// leading dot.
+    fn type_name(name: &str) -> String {
+        name.trim_start_matches('.').to_string()
+    }
+
+    fn messages_from_files(
+        files: &[protobuf::descriptor::FileDescriptorProto],
+    ) -> Arc<DashMap<String, Message>> {
         use proto::gen::pandit::exts;
         let mut messages: Arc<DashMap<String, Message>> = Arc::new(DashMap::new());
+        // Every file is loaded so imported types resolve, each message keyed by full name.
         messages = Arc::new(
-            file.message_type
+            files
                 .iter()
-                .map(|message| {
-                    let name = message.get_name().to_string();
+                .flat_map(|file| {
+                    let scope = match file.get_package() {
+                        "" => "".to_string(),
+                        package => format!("{}.", package),
+                    };
+                    let syntax = file.get_syntax();
+                    Self::nested_messages(&scope, &file.message_type)
+                        .into_iter()
+                        .map(move |(name, message)| (name, message, syntax))
+                })
+                .map(|(name, message, syntax)| {
                     log::info!("{}", name);
                     let opts = message.options.get_ref();
                     let path = exts::path.get(opts).unwrap_or("".to_string());
-                    let config = Message::new(message.clone(), path, messages.clone());
+                    let config = Message::new(message, path, messages.clone()).with_syntax(syntax);
                     (name, config)
                 })
                 .collect(),
@@ -421,7 +838,16 @@ impl Service {
         messages
             .iter_mut()
             .for_each(|mut m| m.parent = messages.clone());
+        well_known::register(&messages);
+        messages
+    }
 
+    fn get_service_attrs_base(
+        messages: Arc<DashMap<String, Message>>,
+        writer: WriterRef,
+        broker: Arc<Broker>,
+        service: &protobuf::descriptor::ServiceDescriptorProto,
+    ) -> Result<Self, ServiceError> {
         let default_handler = {
             let handler = format::handlers::default_handler.get(service.options.get_ref());
             match handler {
@@ -439,6 +865,7 @@ impl Service {
 
         Ok(Self {
             name: Default::default(),
+            proto_name: service.get_name().to_string(),
             methods: Default::default(),
             protocol: Protocol::None,
             broker,
@@ -446,41 +873,71 @@ impl Service {
             writer,
             default_handler,
             default_cache,
+            descriptors: Default::default(),
         })
     }
 
     fn get_service_attrs_http(
         &mut self,
         service: &protobuf::descriptor::ServiceDescriptorProto,
+        env: &EnvAllowList,
     ) -> Result<(), ServiceError> {
         use proto::gen::pandit::exts;
 
         let opts = service.options.get_ref();
-        self.name = exts::name.get(opts).unwrap();
+        self.name = exts::name
+            .get(opts)
+            .ok_or(ServiceError::InvalidArgument(format!(
+                "service {} has no (pandit.name) option",
+                service.get_name()
+            )))?;
         self.protocol = Protocol::HTTP;
+        let service_headers = proto::gen::format::http::exts::http_service
+            .get(opts)
+            .map(|http| http.headers)
+            .unwrap_or_default();
 
         self.methods = service
             .method
             .iter()
             .map(|method| {
-                let api = format::http_api.get(method.options.get_ref()).unwrap();
-                let input_message = method.get_input_type().to_string();
-                let input_message = input_message.split('.').last().unwrap().to_string();
-                let output_message = method.get_output_type().to_string();
-                let output_message = output_message.split('.').last().unwrap().to_string();
-                (
+                let http = format::http_api.get(method.options.get_ref()).ok_or(
+                    ServiceError::InvalidArgument(format!(
+                        "method {} has no http option",
+                        method.get_name()
+                    )),
+                )?;
+                let headers = Self::headers(&service_headers, &http.headers)
+                    .into_iter()
+                    .map(|(name, value)| Ok((name, HeaderTemplate::parse(&value, env)?)))
+                    .collect::<ServiceResult<_>>()?;
+                let input_message = Self::type_name(method.get_input_type());
+                let output_message = Self::type_name(method.get_output_type());
+                Ok((
                     method.get_name().to_string(),
                     Method {
                         input_message: input_message.clone(),
                         output_message: output_message.clone(),
                         handler: self.handler_for_method(&method),
-                        api: Some(api),
+                        api: Some(HttpApi::new(http, headers)?),
                         cache: base::method_cache.get(method.options.get_ref()),
                         primary_key: self.primary_key_for_method(&input_message),
+                        client_streaming: method.get_client_streaming(),
+                        server_streaming: method.get_server_streaming(),
+                        errors: base::method_error.get(method.options.get_ref()),
+                        timeout_ms: base::method_timeout.get(method.options.get_ref()),
+                        forward_metadata: Self::metadata_keys(
+                            base::default_forward_metadata.get(opts),
+                            base::method_forward_metadata.get(method.options.get_ref()),
+                        ),
+                        return_headers: Self::metadata_keys(
+                            base::default_return_headers.get(opts),
+                            base::method_return_headers.get(method.options.get_ref()),
+                        ),
                     },
-                )
+                ))
             })
-            .collect();
+            .collect::<ServiceResult<_>>()?;
 
         Ok(())
     }
@@ -492,17 +949,20 @@ impl Service {
         use proto::gen::pandit::exts;
 
         let opts = service.options.get_ref();
-        self.name = exts::name.get(opts).unwrap();
+        self.name = exts::name
+            .get(opts)
+            .ok_or(ServiceError::InvalidArgument(format!(
+                "service {} has no (pandit.name) option",
+                service.get_name()
+            )))?;
         self.protocol = Protocol::Postgres;
 
         self.methods = service
             .method
             .iter()
             .map(|method| {
-                let input_message = method.get_input_type().to_string();
-                let input_message = input_message.split('.').last().unwrap().to_string();
-                let output_message = method.get_output_type().to_string();
-                let output_message = output_message.split('.').last().unwrap().to_string();
+                let input_message = Self::type_name(method.get_input_type());
+                let output_message = Self::type_name(method.get_output_type());
                 (
                     method.get_name().to_string(),
                     Method {
@@ -512,6 +972,18 @@ impl Service {
                         api: None,
                         cache: base::method_cache.get(method.options.get_ref()),
                         primary_key: self.primary_key_for_method(&input_message),
+                        client_streaming: method.get_client_streaming(),
+                        server_streaming: method.get_server_streaming(),
+                        errors: base::method_error.get(method.options.get_ref()),
+                        timeout_ms: base::method_timeout.get(method.options.get_ref()),
+                        forward_metadata: Self::metadata_keys(
+                            base::default_forward_metadata.get(opts),
+                            base::method_forward_metadata.get(method.options.get_ref()),
+                        ),
+                        return_headers: Self::metadata_keys(
+                            base::default_return_headers.get(opts),
+                            base::method_return_headers.get(method.options.get_ref()),
+                        ),
                     },
                 )
             })
@@ -520,6 +992,31 @@ impl Service {
         Ok(())
     }
 
+    /// Joins the service and method lists, lowercased as gRPC metadata keys are.
+    fn metadata_keys(service: Vec<String>, method: Vec<String>) -> Vec<String> {
+        let mut keys: Vec<String> = service
+            .into_iter()
+            .chain(method.into_iter())
+            .map(|key| key.to_lowercase())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Joins the service and method headers, lowercasing the names so a method header
+    /// replaces the service header it shares a name with.
+    fn headers(
+        service: &HashMap<String, String>,
+        method: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        service
+            .iter()
+            .chain(method.iter())
+            .map(|(name, value)| (name.to_lowercase(), value.clone()))
+            .collect()
+    }
+
     fn primary_key_for_method(&self, message_name: &String) -> Option<String> {
         let message = self.messages.get(message_name).unwrap();
         let message = message.value();
@@ -537,11 +1034,10 @@ impl Service {
         &self,
         method: &MethodDescriptorProto,
     ) -> Option<Arc<dyn Handler + Sync + Send + 'static>> {
-        let message = {
-            let name = method.get_output_type().to_string();
-            let name = name.split('.').last().unwrap().to_string();
-            self.messages.get(&name).unwrap()
-        };
+        let message = self
+            .messages
+            .get(&Self::type_name(method.get_output_type()))
+            .unwrap();
         let options = method.options.get_ref();
         {
             use format::handlers;
@@ -556,20 +1052,12 @@ impl Service {
                 None => {
                     // Quick solution to default postgres service to SQL handler.
                     match postgres.get(options) {
-                        Some(opts) => {
-                            let input_message = method.get_input_type().to_string();
-                            let input_message =
-                                input_message.split('.').last().unwrap().to_string();
-                            let output_message = method.get_output_type().to_string();
-                            let output_message =
-                                output_message.split('.').last().unwrap().to_string();
-                            Some(Arc::new(SQLHandler::new(
-                                self.messages.clone(),
-                                input_message,
-                                output_message,
-                                opts,
-                            )))
-                        }
+                        Some(opts) => Some(Arc::new(SQLHandler::new(
+                            self.messages.clone(),
+                            Self::type_name(method.get_input_type()),
+                            Self::type_name(method.get_output_type()),
+                            opts,
+                        ))),
                         None => None,
                     }
                 }
@@ -588,51 +1076,61 @@ impl Service {
         }
     }
 
-    fn context_from_api(api: &Option<format::HTTP>) -> ServiceResult<WriterContext> {
+    /// Sets the HTTP method, URI and headers of a call, filling their templates from the
+    /// request.
+    ///
+    /// Returns the fields left for the request body, without those the path consumed.
+    fn context_from_api(
+        api: &Option<HttpApi>,
+        fields: &Fields,
+    ) -> ServiceResult<(WriterContext, Fields)> {
         let context = WriterContext::new();
-        use crate::proto::gen::format::http::http::Pattern;
-        match api {
-            Some(http) => match http
-                .pattern
-                .as_ref()
-                .ok_or(ServiceError::new("no pattern in api"))?
-            {
-                Pattern::get(s) => {
-                    context.insert("method".to_string(), "GET".to_string());
-                    context.insert("uri".to_string(), s.clone());
-                }
-                Pattern::put(s) => {
-                    context.insert("method".to_string(), "PUT".to_string());
-                    context.insert("uri".to_string(), s.clone());
-                }
-                Pattern::post(s) => {
-                    context.insert("method".to_string(), "POST".to_string());
-                    context.insert("uri".to_string(), s.clone());
-                }
-                Pattern::delete(s) => {
-                    context.insert("method".to_string(), "DELETE".to_string());
-                    context.insert("uri".to_string(), s.clone());
-                }
-                Pattern::patch(s) => {
-                    context.insert("method".to_string(), "PATCH".to_string());
-                    context.insert("uri".to_string(), s.clone());
-                }
-            },
-            None => {}
+        use crate::proto::gen::format::http::HTTPFieldEncoding;
+        let api = match api {
+            Some(api) => api,
+            None => return Ok((context, fields.clone())),
+        };
+        let http = &api.http;
+        let method = api.method;
+        context.insert("method".to_string(), method.to_string());
+        context.insert("uri".to_string(), api.path.expand(fields)?);
+        for (name, template) in api.headers.iter() {
+            if let Some(value) = template.expand(fields)? {
+                context.insert(name.clone(), value);
+            }
         }
-
-        Ok(context)
+        let query = match http.fields.enum_value() {
+            Ok(HTTPFieldEncoding::FIELDS_QUERY) => true,
+            Ok(HTTPFieldEncoding::FIELDS_BODY) => false,
+            _ => method == "GET" || method == "DELETE",
+        };
+        if query {
+            context.insert("fields".to_string(), "query".to_string());
+        }
+        Ok((context, api.path.body(fields)))
     }
 }
 
 #[async_trait]
 pub trait Sender: Send + Sync {
     async fn send(
-        &mut self,
+        &self,
         service_name: &String,
         method: &String,
-        data: &[u8],
-    ) -> ServiceResult<bytes::Bytes>;
+        data: &[bytes::Bytes],
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream>;
+
+    /// Sends a bidirectional call, yielding responses while requests are still arriving.
+    async fn send_stream(
+        &self,
+        service_name: &String,
+        method: &String,
+        data: MessageStream,
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream>;
 
     async fn probe_cache(
         &self,
@@ -645,24 +1143,186 @@ pub trait Sender: Send + Sync {
 #[async_trait]
 impl Sender for Service {
     async fn send(
-        &mut self,
+        &self,
+        service_name: &String,
+        method: &String,
+        data: &[bytes::Bytes],
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream> {
+        let (errors, deadline) = self.method_deadline(service_name, method, deadline)?;
+        let resp = self.send_method(service_name, method, data, deadline, metadata);
+        let resp = with_deadline(deadline, resp).await;
+        Self::map_response(resp, errors, deadline)
+    }
+
+    async fn send_stream(
+        &self,
+        service_name: &String,
+        method: &String,
+        data: MessageStream,
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream> {
+        let (errors, deadline) = self.method_deadline(service_name, method, deadline)?;
+        let resp = {
+            let method_name = method;
+            let method = self.methods.get(method_name).unwrap();
+            let call = self.bidi_call(service_name, method_name, &method, metadata)?;
+            call.send(data, deadline)
+        };
+        Self::map_response(Ok(resp), errors, deadline)
+    }
+
+    async fn probe_cache(
+        &self,
         service_name: &String,
         method: &String,
         data: &[u8],
-    ) -> ServiceResult<bytes::Bytes> {
-        let method = self.methods.get_mut(method).unwrap();
+    ) -> ServiceResult<Option<bytes::Bytes>> {
+        let method = self.methods.get_mut(method).ok_or(ServiceError::Unimplemented(
+            format!("unknown method {}.{}", service_name, method),
+        ))?;
+        if method.client_streaming || method.server_streaming {
+            return Ok(None);
+        }
         let messages = self.messages.clone();
         let message = messages.get(&method.input_message).unwrap();
+
         let fields = message.fields_from_bytes(data)?;
 
+        let primary_key = {
+            let key = method
+                .primary_key
+                .as_ref()
+                .ok_or(ServiceError::new("no primary key for method"))?;
+            let val = fields.map.get(key);
+            let val = val.ok_or(ServiceError::new(
+                format!("error finding entry for primary key: {}", key).as_str(),
+            ))?;
+            let val = val.value().to_owned();
+            val.ok_or(ServiceError::new(
+                format!("no entry for primary key: {}", key).as_str(),
+            ))?
+        };
+        self.broker
+            .probe_cache(&service_name, method.key(), &primary_key)
+    }
+}
+
+impl Service {
+    /// Whether the method streams in both directions, in which case calls go through
+    /// `send_stream` rather than waiting for the whole request.
+    pub fn is_bidi(&self, method: &String) -> bool {
+        self.methods
+            .get(method)
+            .map(|method| method.client_streaming && method.server_streaming)
+            .unwrap_or_default()
+    }
+
+    /// Error mappings of a method, and the deadline of a call to it, which falls back to the
+    /// method's timeout when the client sent none.
+    fn method_deadline(
+        &self,
+        service_name: &String,
+        method: &String,
+        deadline: Deadline,
+    ) -> ServiceResult<(Vec<base::ErrorMapping>, Deadline)> {
+        let method = self
+            .methods
+            .get(method)
+            .ok_or(ServiceError::Unimplemented(format!(
+                "unknown method {}.{}",
+                service_name, method
+            )))?;
+        let timeout_ms = method.timeout_ms;
+        let deadline = deadline.or_else(|| {
+            let timeout = std::time::Duration::from_millis(timeout_ms?);
+            Some(tokio::time::Instant::now() + timeout)
+        });
+        Ok((method.errors.clone(), deadline))
+    }
+
+    /// Ends a response at the deadline, mapping backend errors to the method's statuses.
+    fn map_response(
+        resp: ServiceResult<MessageStream>,
+        errors: Vec<base::ErrorMapping>,
+        deadline: Deadline,
+    ) -> ServiceResult<MessageStream> {
+        let resp = resp.map_err(|err| err.with_mappings(&errors[..]))?;
+        let resp = stream_with_deadline(resp, deadline);
+        Ok(resp
+            .map(move |resp| resp.map_err(|err| err.with_mappings(&errors[..])))
+            .boxed())
+    }
+
+    /// Takes what a bidirectional call needs from the method, so that it can outlive the
+    /// borrow of the service.
+    fn bidi_call(
+        &self,
+        service_name: &String,
+        method_name: &String,
+        method: &Method,
+        metadata: &Metadata,
+    ) -> ServiceResult<BidiCall> {
+        let handler = method
+            .handler
+            .as_ref()
+            .or(self.default_handler.as_ref())
+            .ok_or(ServiceError::new(
+                format!(
+                    "unable to find handler or default handler for {}.{}",
+                    service_name, method_name
+                )
+                .as_str(),
+            ))?;
+        Ok(BidiCall {
+            writer: self.writer.clone(),
+            handler: handler.clone(),
+            messages: self.messages.clone(),
+            api: method.api.clone(),
+            input_message: method.input_message.clone(),
+            output_message: method.output_message.clone(),
+            forwarded: metadata.select(&method.forward_metadata[..]),
+            return_headers: method.return_headers.clone(),
+            metadata: metadata.clone(),
+        })
+    }
+
+    async fn send_method(
+        &self,
+        service_name: &String,
+        method: &String,
+        data: &[bytes::Bytes],
+        deadline: Deadline,
+        metadata: &Metadata,
+    ) -> ServiceResult<MessageStream> {
+        let method = self.methods.get_mut(method).unwrap();
+        if method.client_streaming && method.server_streaming {
+            // A bidirectional call sent whole still goes one backend call per message.
+            let call = self.bidi_call(service_name, method.key(), &method, metadata)?;
+            let data = stream::iter(data.to_vec().into_iter().map(Ok)).boxed();
+            return Ok(call.send(data, deadline));
+        }
+        let messages = self.messages.clone();
+        let message = messages.get(&method.input_message).unwrap();
+        let mut batch = Vec::with_capacity(data.len());
+        for buf in data {
+            batch.push(message.fields_from_bytes(&buf[..])?);
+        }
+
         log::info!(
             "sending local data request for {}_{}",
             service_name,
             method.key()
         );
 
-        let writer = self.writer.get_mut();
-        let context = Self::context_from_api(&method.api)?;
+        let mut writer = self.writer.lock().await;
+        let writer = &mut *writer;
+        let success = Self::success_status(&method.api);
+        let forwarded = metadata.select(&method.forward_metadata[..]);
+        let api = &method.api;
+        let context_for = |fields: &Fields| Self::context_for(api, fields, &forwarded[..]);
 
         let handler = method
             .handler
@@ -677,68 +1337,222 @@ impl Sender for Service {
                 .as_str(),
             ))?;
 
-        let resp = writer.write_request(context, &fields, handler).await?;
-        let resp_fields = handler.from_payload(resp)?;
-
-        let buf: Vec<u8> = Vec::with_capacity(1000);
-        use bytes::BufMut;
-        let mut buf = buf.writer();
-        {
+        if method.client_streaming && !method.server_streaming {
+            // A batch is sent in one call, so its path is filled from the first message.
+            let first = batch
+                .first()
+                .cloned()
+                .unwrap_or(Fields::new(FieldsMap::new()));
+            let (context, _) = context_for(&first)?;
+            // Each message leaves out the fields the path consumes, as a single request does.
+            let batch = match api {
+                Some(api) => batch.iter().map(|fields| api.path.body(fields)).collect(),
+                None => batch,
+            };
+            let resp = writer
+                .write_batch(context, deadline, &success, &batch[..], handler)
+                .await?;
+            Self::return_headers(&resp, &method.return_headers[..], metadata);
+            let resp_fields = Self::fields_from_response(handler, resp)?;
             let message = messages.get(&method.output_message).unwrap();
-            let mut output = protobuf::CodedOutputStream::new(&mut buf);
-            message.write_bytes_from_fields(&mut output, &resp_fields)?;
+            return Ok(stream::iter(vec![message.bytes_from_fields(&resp_fields)]).boxed());
         }
+
+        let fields = batch
+            .into_iter()
+            .next()
+            .ok_or(ServiceError::new("no message in request"))?;
+
+        let (context, body) = context_for(&fields)?;
+        if method.server_streaming {
+            let resp_stream = writer
+                .write_request_stream(context, deadline, &success, &body, handler)
+                .await?;
+            Self::return_headers(&resp_stream, &method.return_headers[..], metadata);
+            let messages = self.messages.clone();
+            let output_message = method.output_message.clone();
+
+            // Streamed responses are not cached, each element is framed as it arrives.
+            let resp = resp_stream.body.map(move |resp_fields| {
+                let message = messages.get(&output_message).unwrap();
+                message.bytes_from_fields(&resp_fields?)
+            });
+            return Ok(resp.boxed());
+        }
+
+        let resp = writer
+            .write_request(context, deadline, &success, &body, handler)
+            .await?;
+        Self::return_headers(&resp, &method.return_headers[..], metadata);
+        let resp_fields = Self::fields_from_response(handler, resp)?;
+
+        let buf = {
+            let message = messages.get(&method.output_message).unwrap();
+            message.bytes_from_fields(&resp_fields)?
+        };
         {
             self.broker
                 .publish_cache(&service_name, method.key(), resp_fields, &fields)
                 .await?;
         }
 
-        let buf = buf.into_inner();
-        Ok(bytes::Bytes::copy_from_slice(&buf[..]))
+        Ok(stream::iter(vec![Ok(buf)]).boxed())
     }
 
-    async fn probe_cache(
-        &self,
-        service_name: &String,
-        method: &String,
-        data: &[u8],
-    ) -> ServiceResult<Option<bytes::Bytes>> {
-        let method = self.methods.get_mut(method).unwrap();
-        let messages = self.messages.clone();
-        let message = messages.get(&method.input_message).unwrap();
+    /// Context and body of a call, with the forwarded metadata added as headers.
+    fn context_for(
+        api: &Option<HttpApi>,
+        fields: &Fields,
+        forwarded: &[(String, String)],
+    ) -> ServiceResult<(WriterContext, Fields)> {
+        let (context, body) = Self::context_from_api(api, fields)?;
+        // Headers set in the proto take precedence over forwarded metadata.
+        for (key, value) in forwarded.iter().cloned() {
+            context.entry(key).or_insert(value);
+        }
+        Ok((context, body))
+    }
 
-        let fields = message.fields_from_bytes(data)?;
+    /// Backend response statuses a call treats as success.
+    fn success_status(api: &Option<HttpApi>) -> SuccessStatus {
+        match api {
+            Some(api) => api.success_status.clone(),
+            None => Default::default(),
+        }
+    }
 
-        let primary_key = {
-            let key = method
-                .primary_key
-                .as_ref()
-                .ok_or(ServiceError::new("no primary key for method"))?;
-            let val = fields.map.get(key);
-            let val = val.ok_or(ServiceError::new(
-                format!("error finding entry for primary key: {}", key).as_str(),
-            ))?;
-            let val = val.value().to_owned();
-            val.ok_or(ServiceError::new(
-                format!("no entry for primary key: {}", key).as_str(),
-            ))?
+    fn return_headers<T>(resp: &WriterResponse<T>, names: &[String], metadata: &Metadata) {
+        if let Some(headers) = &resp.headers {
+            metadata.add_response(headers, names);
+        }
+    }
+
+    fn fields_from_response(
+        handler: &Arc<dyn Handler + Send + Sync>,
+        resp: WriterResponse<bytes::Bytes>,
+    ) -> ServiceResult<Fields> {
+        handler.from_response(resp.status, resp.headers.as_ref(), resp.body)
+    }
+}
+
+/// A bidirectional call, which sends each inbound message to the backend as it arrives.
+struct BidiCall {
+    writer: WriterRef,
+    handler: Arc<dyn Handler + Send + Sync>,
+    messages: Arc<DashMap<String, Message>>,
+    api: Option<HttpApi>,
+    input_message: String,
+    output_message: String,
+    forwarded: Vec<(String, String)>,
+    return_headers: Vec<String>,
+    metadata: Metadata,
+}
+
+impl BidiCall {
+    /// Yields the response to each message once its backend call completes.
+    fn send(self, data: MessageStream, deadline: Deadline) -> MessageStream {
+        let call = Arc::new(self);
+        data.then(move |message| {
+            let call = call.clone();
+            async move { call.call(message?, deadline).await }
+        })
+        .boxed()
+    }
+
+    async fn call(&self, message: bytes::Bytes, deadline: Deadline) -> ServiceResult<bytes::Bytes> {
+        let fields = {
+            let input = self.messages.get(&self.input_message).unwrap();
+            input.fields_from_bytes(&message[..])?
         };
-        self.broker
-            .probe_cache(&service_name, method.key(), &primary_key)
+        let (context, body) = Service::context_for(&self.api, &fields, &self.forwarded[..])?;
+        let success = Service::success_status(&self.api);
+        let resp = {
+            let mut writer = self.writer.lock().await;
+            writer
+                .write_request(context, deadline, &success, &body, &self.handler)
+                .await?
+        };
+        Service::return_headers(&resp, &self.return_headers[..], &self.metadata);
+        let resp_fields = Service::fields_from_response(&self.handler, resp)?;
+        let message = self.messages.get(&self.output_message).unwrap();
+        message.bytes_from_fields(&resp_fields)
     }
 }
 
 pub type WriterContext = DashMap<String, String>;
 
+/// What a backend answered a call with, including the status and headers of backends
+/// that have them.
+pub struct WriterResponse<T> {
+    pub body: T,
+    pub status: Option<http::StatusCode>,
+    pub headers: Option<http::HeaderMap>,
+}
+
+impl<T> WriterResponse<T> {
+    /// A response without a status or headers.
+    pub fn new(body: T) -> Self {
+        Self {
+            body,
+            status: None,
+            headers: None,
+        }
+    }
+}
+
+/// Writes calls to a backend. Writers are shared by every call to a service, so what
+/// varies per call is passed in or returned rather than kept on the writer.
 #[async_trait]
 pub trait Writer: Sync + Send {
+    /// Writes a request, failing when the backend answers with a status outside `success`.
     async fn write_request(
         &mut self,
         context: WriterContext,
+        deadline: Deadline,
+        success: &SuccessStatus,
+        fields: &Fields,
+        handler: &Arc<dyn Handler + Send + Sync>,
+    ) -> ServiceResult<WriterResponse<bytes::Bytes>>;
+
+    /// Writes a request for a server-streaming method, yielding each response message.
+    async fn write_request_stream(
+        &mut self,
+        context: WriterContext,
+        deadline: Deadline,
+        success: &SuccessStatus,
         fields: &Fields,
         handler: &Arc<dyn Handler + Send + Sync>,
-    ) -> ServiceResult<bytes::Bytes>;
+    ) -> ServiceResult<WriterResponse<FieldsStream>> {
+        let resp = self
+            .write_request(context, deadline, success, fields, handler)
+            .await?;
+        let resp_fields = handler.from_payload_stream(resp.body)?;
+        Ok(WriterResponse {
+            body: stream::iter(resp_fields.into_iter().map(Ok)).boxed(),
+            status: resp.status,
+            headers: resp.headers,
+        })
+    }
+
+    /// Writes every message of a client-streaming call as a single backend request.
+    async fn write_batch(
+        &mut self,
+        _context: WriterContext,
+        _deadline: Deadline,
+        _success: &SuccessStatus,
+        _fields: &[Fields],
+        _handler: &Arc<dyn Handler + Send + Sync>,
+    ) -> ServiceResult<WriterResponse<bytes::Bytes>> {
+        Err(ServiceError::new(
+            "batched writes are not supported by this writer",
+        ))
+    }
+
+    /// Checks the backend is reachable, for health checking. The check runs without
+    /// borrowing the writer so it is not held locked while waiting on the backend.
+    fn probe(&self) -> BoxFuture<'static, ServiceResult<()>> {
+        Box::pin(futures::future::ok(()))
+    }
 }
 
 pub fn new_config(path: &str) -> config::Config {
@@ -761,60 +1575,217 @@ mod tests {
         fields: Option<Fields>,
     }
 
+    impl FakeWriter {
+        fn new() -> WriterRef {
+            Arc::new(Mutex::new(FakeWriter {
+                context: None,
+                fields: None,
+            }))
+        }
+    }
+
     #[async_trait]
     impl Writer for FakeWriter {
         async fn write_request(
             &mut self,
             context: WriterContext,
+            _: Deadline,
+            _: &SuccessStatus,
             fields: &Fields,
             handler: &Arc<dyn Handler + Send + Sync>,
-        ) -> ServiceResult<bytes::Bytes> {
+        ) -> ServiceResult<WriterResponse<bytes::Bytes>> {
             self.context = Some(context);
             self.fields = Some(fields.clone());
-            Ok(bytes::Bytes::from_static(b"{\"obj\":{\"id\": 1}}"))
+            Ok(WriterResponse::new(bytes::Bytes::from_static(
+                b"{\"obj\":{\"id\": 1}}",
+            )))
         }
     }
 
     #[tokio::test]
     async fn test_send_proto_to_local_http_json() {
         use super::*;
-        let writer = FakeWriter {
-            context: None,
-            fields: None,
-        };
-        let writer_ref = Box::new(Mutex::new(writer));
         let broker = Broker::connect(Default::default(), "".to_string()).unwrap();
         let broker = Arc::new(broker);
-        let mut service = Service::from_file(
+        let service = Service::from_file(
             "./src/proto/examples/example1.proto",
             &["./src/proto"],
-            writer_ref,
+            |_| Ok(FakeWriter::new()),
             broker.clone(),
+            &Default::default(),
         )
-        .unwrap();
+        .unwrap()
+        .remove(0);
         broker
             .sub_service(&"ExampleService".to_string(), &"GetExample".to_string())
             .await
             .unwrap();
-        let buf: &[u8] = &[
-            0, 0, 0, 0, 0, // gRPC header.
+        let buf = bytes::Bytes::from_static(&[
+            0, 0, 0, 0, 3, // gRPC header.
             0x08, 0x96, 0x01, // Field varint
-        ];
+        ]);
         let resp = service
             .send(
                 &"ExampleService".to_string(),
                 &"GetExample".to_string(),
-                buf,
+                &[buf],
+                None,
+                &Metadata::default(),
             )
             .await
             .unwrap();
+        let resp: Vec<bytes::Bytes> = resp.map(|resp| resp.unwrap()).collect().await;
         assert_eq!(
             resp,
-            bytes::Bytes::from_static(&[
-                0, 0, 0, 0, 0, // gRPC header.
+            vec![bytes::Bytes::from_static(&[
+                0, 0, 0, 0, 2, // gRPC header.
                 0x08, 0x01, // Field varint
-            ])
+            ])]
         );
         return;
     }
+
+    #[tokio::test]
+    async fn test_send_stream_bidi() {
+        use super::*;
+        let broker = Broker::connect(Default::default(), "".to_string()).unwrap();
+        let service = Service::from_file(
+            "./src/proto/examples/example1.proto",
+            &["./src/proto"],
+            |_| Ok(FakeWriter::new()),
+            Arc::new(broker),
+            &Default::default(),
+        )
+        .unwrap()
+        .remove(0);
+        let method = "GetExample".to_string();
+        {
+            let mut method = service.methods.get_mut(&method).unwrap();
+            method.client_streaming = true;
+            method.server_streaming = true;
+        }
+        assert!(service.is_bidi(&method));
+
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let mut resp = service
+            .send_stream(
+                &"ExampleService".to_string(),
+                &method,
+                rx.boxed(),
+                None,
+                &Metadata::default(),
+            )
+            .await
+            .unwrap();
+        let buf = bytes::Bytes::from_static(&[
+            0, 0, 0, 0, 3, // gRPC header.
+            0x08, 0x96, 0x01, // Field varint
+        ]);
+        let expected = bytes::Bytes::from_static(&[
+            0, 0, 0, 0, 2, // gRPC header.
+            0x08, 0x01, // Field varint
+        ]);
+        // Each response arrives before the next request is sent.
+        for _ in 0..2 {
+            tx.unbounded_send(Ok(buf.clone())).unwrap();
+            assert_eq!(resp.next().await.unwrap().unwrap(), expected);
+        }
+        drop(tx);
+        assert!(resp.next().await.is_none());
+    }
+
+    #[test]
+    fn test_imported_and_nested_messages() {
+        use super::*;
+        let broker = Broker::connect(Default::default(), "".to_string()).unwrap();
+        let services = Service::from_file(
+            "./src/proto/examples/inventory.proto",
+            &["./src/proto"],
+            |_| Ok(FakeWriter::new()),
+            Arc::new(broker),
+            &Default::default(),
+        )
+        .unwrap();
+        let names: Vec<&str> = services.iter().map(|s| s.proto_name.as_str()).collect();
+        assert_eq!(names, vec!["InventoryService", "StockService"]);
+        // Every service of the file shares its messages.
+        assert!(Arc::ptr_eq(&services[0].messages, &services[1].messages));
+        let service = &services[0];
+
+        let method = service.methods.get("GetItem").unwrap();
+        assert_eq!(method.input_message, "inventory.Lookup.Request");
+        assert_eq!(method.output_message, "common.Item");
+        assert_eq!(method.primary_key, Some("id".to_string()));
+
+        // Same-named messages from different packages are kept apart.
+        let item = service.messages.get("inventory.Item").unwrap();
+        assert!(item.fields_by_name.contains_key("detail"));
+        let common = service.messages.get("common.Item").unwrap();
+        assert!(!common.fields_by_name.contains_key("detail"));
+
+        let fields = item
+            .fields_from_raw(&[
+                0x08, 0x01, // id
+                0x12, 0x02, 0x08, 0x02, // detail.id
+            ])
+            .unwrap();
+        let detail = fields.map.get("detail").unwrap();
+        match detail.value() {
+            Some(Value::Message(detail)) => assert_eq!(
+                detail.map.get("id").unwrap().value(),
+                &Some(Value::from_int(2))
+            ),
+            value => panic!("unexpected detail: {:?}", value),
+        }
+    }
+
+    #[test]
+    fn test_error_status_mappings() {
+        use super::*;
+        use base::StatusCode;
+        let backend = |code: &str| ServiceError::Backend {
+            code: code.to_string(),
+            message: "".to_string(),
+        };
+        assert_eq!(backend("404").status(), StatusCode::NOT_FOUND);
+        assert_eq!(backend("23505").status(), StatusCode::ALREADY_EXISTS);
+        assert_eq!(backend("500").status(), StatusCode::UNKNOWN);
+        assert_eq!(backend("502").status(), StatusCode::UNAVAILABLE);
+        assert_eq!(backend("503").status(), StatusCode::UNAVAILABLE);
+        assert_eq!(backend("504").status(), StatusCode::DEADLINE_EXCEEDED);
+
+        let mut mapping = base::ErrorMapping::new();
+        mapping.backend_code = "404".to_string();
+        mapping.status = protobuf::ProtobufEnumOrUnknown::new(StatusCode::INVALID_ARGUMENT);
+        let err = backend("404").with_mappings(&[mapping]);
+        assert_eq!(err.status(), StatusCode::INVALID_ARGUMENT);
+        assert_eq!(
+            ServiceError::new("").with_mappings(&[]).status(),
+            StatusCode::INTERNAL
+        );
+    }
+
+    #[test]
+    fn test_metadata() {
+        use super::*;
+        let mut request = http::HeaderMap::new();
+        request.insert("authorization", "Bearer token".parse().unwrap());
+        request.insert("x-request-id", "42".parse().unwrap());
+        request.insert("grpc-timeout", "1S".parse().unwrap());
+        let metadata = Metadata::new(request);
+        let keys = vec!["authorization".to_string(), "grpc-timeout".to_string()];
+        assert_eq!(
+            metadata.select(&keys[..]),
+            vec![("authorization".to_string(), "Bearer token".to_string())]
+        );
+        assert_eq!(metadata.forwardable().count(), 2);
+
+        let mut headers = http::HeaderMap::new();
+        headers.insert("x-trace-id", "abc".parse().unwrap());
+        headers.insert("server", "nginx".parse().unwrap());
+        metadata.add_response(&headers, &["x-trace-id".to_string()]);
+        let response = metadata.response();
+        assert_eq!(response.len(), 1);
+        assert_eq!(response.get("x-trace-id").unwrap(), "abc");
+    }
 }
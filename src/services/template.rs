@@ -0,0 +1,556 @@
+use crate::services::{
+    value::{Floating, Value},
+    Fields, ServiceError, ServiceResult,
+};
+
+/// A URI path template in the style of `google.api.http`, such as
+/// `/orgs/{org}/repos/{repo=**}`, filled in from the fields of a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    /// A field path, `a.b` reading field `b` of message field `a`.
+    Variable {
+        field: String,
+        /// Whether the value may span several segments, so `/` is left unescaped.
+        multi_segment: bool,
+    },
+}
+
+impl PathTemplate {
+    pub fn parse(template: &str) -> ServiceResult<Self> {
+        let invalid = |reason: &str| {
+            ServiceError::InvalidArgument(format!(
+                "invalid path template {:?}: {}",
+                template, reason
+            ))
+        };
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            parts.push(Part::Literal(rest[..start].to_string()));
+            let end = start
+                + rest[start..]
+                    .find('}')
+                    .ok_or(invalid("unclosed variable"))?;
+            let variable = &rest[start + 1..end];
+            let (field, segments) = match variable.find('=') {
+                Some(i) => (&variable[..i], &variable[i + 1..]),
+                None => (variable, "*"),
+            };
+            let is_ident = |name: &str| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            };
+            if !field.split('.').all(is_ident) {
+                return Err(invalid("variables must name a field"));
+            }
+            if segments.is_empty() {
+                return Err(invalid("empty variable pattern"));
+            }
+            parts.push(Part::Variable {
+                field: field.to_string(),
+                multi_segment: segments != "*",
+            });
+            rest = &rest[end + 1..];
+        }
+        parts.push(Part::Literal(rest.to_string()));
+        for part in &parts {
+            if let Part::Literal(literal) = part {
+                if literal.contains('}') {
+                    return Err(invalid("unopened variable"));
+                }
+                // Only variables can be filled in when sending a request.
+                if literal.contains('*') {
+                    return Err(invalid("wildcards must be bound to a field"));
+                }
+            }
+        }
+        parts.retain(|part| part != &Part::Literal(String::new()));
+        Ok(Self { parts })
+    }
+
+    /// Field paths the template reads.
+    pub fn fields(&self) -> Vec<String> {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Variable { field, .. } => Some(field.clone()),
+                Part::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    /// Fills in the variables, percent-encoding their values.
+    ///
+    /// Every field in the path must be set to a scalar.
+    pub fn expand(&self, fields: &Fields) -> ServiceResult<String> {
+        let mut path = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => path.push_str(literal),
+                Part::Variable {
+                    field,
+                    multi_segment,
+                } => {
+                    let value = lookup(fields, field).ok_or(ServiceError::InvalidArgument(
+                        format!("path field {} is not set", field),
+                    ))?;
+                    let value = segment(&value).ok_or(ServiceError::InvalidArgument(format!(
+                        "path field {} is not a scalar",
+                        field
+                    )))?;
+                    if is_dot_segment(&value, *multi_segment) {
+                        return Err(ServiceError::InvalidArgument(format!(
+                            "path field {} cannot be a . or .. segment",
+                            field
+                        )));
+                    }
+                    path.push_str(&escape(&value, *multi_segment));
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    /// Copies the fields without those the path consumed, leaving the request body.
+    pub fn body(&self, fields: &Fields) -> Fields {
+        let body = fields.clone();
+        for field in self.fields() {
+            remove(&body, &field);
+        }
+        body
+    }
+}
+
+/// Environment variables header templates may read, set by `headers.env` in the config.
+///
+/// Entries ending in `*` match every variable starting with the rest of the entry. Only
+/// `PANDIT_*` variables are allowed by default, so a proto cannot read arbitrary secrets
+/// of the node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvAllowList {
+    patterns: Vec<String>,
+}
+
+impl Default for EnvAllowList {
+    fn default() -> Self {
+        Self::new(vec!["PANDIT_*".to_string()])
+    }
+}
+
+impl EnvAllowList {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+
+    pub fn from_config(cfg: &config::Config) -> Self {
+        match cfg.get::<Vec<String>>("headers.env") {
+            Ok(patterns) => Self::new(patterns),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            })
+    }
+}
+
+/// A header value such as `Bearer ${PANDIT_API_TOKEN}` or `{tenant.id}`, reading
+/// environment variables and request fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderTemplate {
+    parts: Vec<HeaderPart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum HeaderPart {
+    Literal(String),
+    Field(String),
+    Env(String),
+}
+
+impl HeaderTemplate {
+    /// Parses a template, which may only read the environment variables `allowed` lists.
+    pub fn parse(template: &str, allowed: &EnvAllowList) -> ServiceResult<Self> {
+        let invalid = |reason: &str| {
+            ServiceError::InvalidArgument(format!(
+                "invalid header template {:?}: {}",
+                template, reason
+            ))
+        };
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let env = rest[..start].ends_with('$');
+            let literal = if env {
+                &rest[..start - 1]
+            } else {
+                &rest[..start]
+            };
+            parts.push(HeaderPart::Literal(literal.to_string()));
+            let end = start
+                + rest[start..]
+                    .find('}')
+                    .ok_or(invalid("unclosed variable"))?;
+            let name = &rest[start + 1..end];
+            let is_ident = |name: &str| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            };
+            if env && is_ident(name) {
+                if !allowed.allows(name) {
+                    return Err(invalid(
+                        "environment variable is not allowed by headers.env",
+                    ));
+                }
+                parts.push(HeaderPart::Env(name.to_string()));
+            } else if !env && name.split('.').all(is_ident) {
+                parts.push(HeaderPart::Field(name.to_string()));
+            } else {
+                return Err(invalid(
+                    "variables must name a field or environment variable",
+                ));
+            }
+            rest = &rest[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(invalid("unopened variable"));
+        }
+        parts.push(HeaderPart::Literal(rest.to_string()));
+        parts.retain(|part| part != &HeaderPart::Literal(String::new()));
+        Ok(Self { parts })
+    }
+
+    /// Fills in the variables, `None` when a field it reads is unset so the header is
+    /// left out.
+    ///
+    /// Environment variables are read on every call, and must be set.
+    pub fn expand(&self, fields: &Fields) -> ServiceResult<Option<String>> {
+        let mut value = String::new();
+        for part in &self.parts {
+            match part {
+                HeaderPart::Literal(literal) => value.push_str(literal),
+                HeaderPart::Field(field) => {
+                    let field_value = match lookup(fields, field) {
+                        Some(field_value) => field_value,
+                        None => return Ok(None),
+                    };
+                    let field_value =
+                        segment(&field_value).ok_or(ServiceError::InvalidArgument(format!(
+                            "header field {} is not a scalar",
+                            field
+                        )))?;
+                    value.push_str(&field_value);
+                }
+                HeaderPart::Env(name) => {
+                    let env = std::env::var(name).map_err(|_| {
+                        ServiceError::Internal(format!("environment variable {} is not set", name))
+                    })?;
+                    value.push_str(&env);
+                }
+            }
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Backend response statuses that count as success, from a list such as `200,204` or
+/// `2xx,404`. Any 2xx status is a success when the list is empty.
+#[derive(Debug, Clone, Default)]
+pub struct SuccessStatus {
+    patterns: Vec<String>,
+}
+
+impl SuccessStatus {
+    /// Parses the list, failing unless every pattern is three digits or `x`.
+    pub fn parse(statuses: &str) -> ServiceResult<Self> {
+        let mut patterns = Vec::new();
+        if statuses.trim().is_empty() {
+            return Ok(Self { patterns });
+        }
+        for pattern in statuses.split(',') {
+            let pattern = pattern.trim().to_lowercase();
+            if pattern.len() != 3 || !pattern.chars().all(|c| c.is_ascii_digit() || c == 'x') {
+                return Err(ServiceError::InvalidArgument(format!(
+                    "invalid success status {:?}",
+                    pattern
+                )));
+            }
+            patterns.push(pattern);
+        }
+        Ok(Self { patterns })
+    }
+
+    /// Whether a backend response with this status is a success.
+    pub fn matches(&self, status: http::StatusCode) -> bool {
+        if self.patterns.is_empty() {
+            return status.is_success();
+        }
+        let code = status.as_u16().to_string();
+        self.patterns.iter().any(|pattern| {
+            pattern
+                .chars()
+                .zip(code.chars())
+                .all(|(p, c)| p == 'x' || p == c)
+        })
+    }
+}
+
+fn lookup(fields: &Fields, path: &str) -> Option<Value> {
+    let mut names = path.splitn(2, '.');
+    let value = fields.map.get(names.next()?)?.value().clone()?;
+    match (names.next(), value) {
+        (None, Value::None) => None,
+        (None, value) => Some(value),
+        (Some(rest), Value::Message(inner)) => lookup(&inner, rest),
+        (Some(_), _) => None,
+    }
+}
+
+fn remove(fields: &Fields, path: &str) {
+    let mut names = path.splitn(2, '.');
+    let name = names.next().unwrap_or_default();
+    match names.next() {
+        None => {
+            fields.map.remove(name);
+            fields.oneofs.retain(|_, member| member != name);
+        }
+        Some(rest) => {
+            if let Some(entry) = fields.map.get(name) {
+                if let Some(Value::Message(inner)) = entry.value() {
+                    remove(inner, rest);
+                }
+            }
+        }
+    }
+}
+
+/// Encodes fields as URL query parameters, sorted by name.
+///
+/// Repeated fields repeat their key, and nested messages and maps use dotted names such as
+/// `filter.owner=me`. Unset fields are left out.
+pub fn query_string(fields: &Fields) -> String {
+    let mut params = Vec::new();
+    message_params("", fields, &mut params);
+    params.join("&")
+}
+
+fn message_params(prefix: &str, fields: &Fields, params: &mut Vec<String>) {
+    let mut names: Vec<String> = fields.map.iter().map(|entry| entry.key().clone()).collect();
+    names.sort();
+    for name in names {
+        if let Some(value) = fields
+            .map
+            .get(&name)
+            .and_then(|entry| entry.value().clone())
+        {
+            query_params(&format!("{}{}", prefix, name), &value, params);
+        }
+    }
+}
+
+fn query_params(key: &str, value: &Value, params: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                query_params(key, item, params);
+            }
+        }
+        Value::Map(entries) => {
+            for (name, value) in entries {
+                query_params(&format!("{}.{}", key, name), value, params);
+            }
+        }
+        Value::Message(inner) => message_params(&format!("{}.", key), inner, params),
+        value => {
+            if let Some(value) = segment(value) {
+                params.push(format!("{}={}", escape(key, false), escape(&value, false)));
+            }
+        }
+    }
+}
+
+/// Text form of a scalar as it appears in a URL, `None` for messages, lists and maps.
+fn segment(value: &Value) -> Option<String> {
+    match value {
+        // Widening a float to f64 would print `0.1` as `0.10000000149011612`.
+        Value::Float(Floating::F32(v)) => Some(v.to_string()),
+        Value::Float(Floating::F64(v)) => Some(v.to_string()),
+        Value::Bytes(v) => Some(base64::encode(v)),
+        Value::Enum(v) => Some(protobuf::ProtobufEnum::value(v).to_string()),
+        value => value.to_map_key(),
+    }
+}
+
+/// Whether the value holds a `.` or `..` segment, which would change the path it is
+/// filled into.
+fn is_dot_segment(value: &str, multi_segment: bool) -> bool {
+    let is_dot = |segment: &str| segment == "." || segment == "..";
+    match multi_segment {
+        true => value.split('/').any(is_dot),
+        false => is_dot(value),
+    }
+}
+
+/// Percent-encodes all but the RFC 3986 unreserved characters, and `/` when the value
+/// spans segments.
+fn escape(value: &str, multi_segment: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        let unreserved = byte.is_ascii_alphanumeric() || b"-._~".contains(&byte);
+        if unreserved || (multi_segment && byte == b'/') {
+            out.push(char::from(byte));
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+mod tests {
+    #[test]
+    fn test_expand() {
+        use super::*;
+        use crate::services::FieldsMap;
+
+        let owner = FieldsMap::new();
+        owner.insert(
+            "org".to_string(),
+            Some(Value::from_string("acme co".to_string())),
+        );
+        let fields = FieldsMap::new();
+        fields.insert(
+            "owner".to_string(),
+            Some(Value::Message(Fields::new(owner))),
+        );
+        fields.insert(
+            "repo".to_string(),
+            Some(Value::from_string("tools/pandit".to_string())),
+        );
+        fields.insert("id".to_string(), Some(Value::from_int(7)));
+        let fields = Fields::new(fields);
+
+        let template = PathTemplate::parse("/orgs/{owner.org}/repos/{repo=**}:sync").unwrap();
+        assert_eq!(
+            template.expand(&fields).unwrap(),
+            "/orgs/acme%20co/repos/tools/pandit:sync"
+        );
+        let body = template.body(&fields);
+        assert!(!body.map.contains_key("repo"));
+        assert!(body.map.contains_key("id"));
+        match body.map.get("owner").unwrap().value() {
+            Some(Value::Message(owner)) => assert!(owner.map.is_empty()),
+            _ => panic!("owner should be kept"),
+        }
+        assert!(fields.map.contains_key("repo"));
+
+        let template = PathTemplate::parse("/users/{repo}").unwrap();
+        assert_eq!(template.expand(&fields).unwrap(), "/users/tools%2Fpandit");
+        let template = PathTemplate::parse("/users/{name}").unwrap();
+        assert!(template.expand(&fields).is_err());
+
+        fields.map.insert(
+            "tag".to_string(),
+            Some(Value::Array(vec![
+                Value::from_string("a&b".to_string()),
+                Value::from_string("c".to_string()),
+            ])),
+        );
+        let template = PathTemplate::parse("/users/{repo}").unwrap();
+        assert_eq!(
+            query_string(&template.body(&fields)),
+            "id=7&owner.org=acme%20co&tag=a%26b&tag=c"
+        );
+
+        fields
+            .map
+            .insert("ratio".to_string(), Some(Value::from_float(0.1f32)));
+        let template = PathTemplate::parse("/ratios/{ratio}").unwrap();
+        assert_eq!(template.expand(&fields).unwrap(), "/ratios/0.1");
+
+        let template = PathTemplate::parse("/repos/{repo=**}/tags/{tag}").unwrap();
+        for (repo, tag) in &[("tools/..", "v1"), ("./tools", "v1"), ("tools", "..")] {
+            let fields = FieldsMap::new();
+            fields.insert(
+                "repo".to_string(),
+                Some(Value::from_string(repo.to_string())),
+            );
+            fields.insert("tag".to_string(), Some(Value::from_string(tag.to_string())));
+            assert!(template.expand(&Fields::new(fields)).is_err());
+        }
+        let fields = FieldsMap::new();
+        fields.insert(
+            "repo".to_string(),
+            Some(Value::from_string("a..b/.c".to_string())),
+        );
+        fields.insert(
+            "tag".to_string(),
+            Some(Value::from_string("...".to_string())),
+        );
+        assert_eq!(
+            template.expand(&Fields::new(fields)).unwrap(),
+            "/repos/a..b/.c/tags/..."
+        );
+
+        assert!(PathTemplate::parse("/users/{id").is_err());
+        assert!(PathTemplate::parse("/users/*").is_err());
+        assert_eq!(PathTemplate::parse("/example").unwrap().fields().len(), 0);
+    }
+
+    #[test]
+    fn test_header_template() {
+        use super::*;
+        use crate::services::FieldsMap;
+
+        let fields = FieldsMap::new();
+        fields.insert(
+            "tenant".to_string(),
+            Some(Value::from_string("acme co".to_string())),
+        );
+        let fields = Fields::new(fields);
+
+        let allowed = EnvAllowList::default();
+        std::env::set_var("PANDIT_TEST_TOKEN", "secret");
+        let header =
+            HeaderTemplate::parse("Bearer ${PANDIT_TEST_TOKEN} for {tenant}", &allowed).unwrap();
+        assert_eq!(
+            header.expand(&fields).unwrap(),
+            Some("Bearer secret for acme co".to_string())
+        );
+        let header = HeaderTemplate::parse("{name}", &allowed).unwrap();
+        assert_eq!(header.expand(&fields).unwrap(), None);
+        let header = HeaderTemplate::parse("${PANDIT_TEST_UNSET}", &allowed).unwrap();
+        assert!(header.expand(&fields).is_err());
+        assert!(HeaderTemplate::parse("${a.b}", &allowed).is_err());
+
+        // Variables outside the allow-list are refused when the template is parsed.
+        assert!(HeaderTemplate::parse("${HOME}", &allowed).is_err());
+        let allowed = EnvAllowList::new(vec!["HOME".to_string(), "API_*".to_string()]);
+        assert!(HeaderTemplate::parse("${HOME}", &allowed).is_ok());
+        assert!(HeaderTemplate::parse("${API_TOKEN}", &allowed).is_ok());
+        assert!(HeaderTemplate::parse("${PANDIT_TEST_TOKEN}", &allowed).is_err());
+    }
+
+    #[test]
+    fn test_success_status() {
+        use super::*;
+        let parse = |statuses| SuccessStatus::parse(statuses).unwrap();
+        assert!(parse("").matches(http::StatusCode::NO_CONTENT));
+        assert!(!parse("").matches(http::StatusCode::NOT_FOUND));
+        assert!(parse("2xx, 404").matches(http::StatusCode::NOT_FOUND));
+        assert!(!parse("200,204").matches(http::StatusCode::CREATED));
+        assert!(!parse("2XX").matches(http::StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(SuccessStatus::parse("2xxx").is_err());
+        // Every pattern is checked, not only those before the first match.
+        assert!(SuccessStatus::parse("200,2xxx").is_err());
+        assert!(SuccessStatus::parse("200,").is_err());
+    }
+}
@@ -1,161 +1,172 @@
 use std::{
     cmp::Ordering,
-    collections::hash_map::DefaultHasher,
-    convert::TryInto,
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    convert::{TryFrom, TryInto},
     hash::{Hash, Hasher},
     io::ErrorKind,
     str::Utf8Error,
-    sync::Arc,
 };
 
 use protobuf::reflect::{runtime_types::RuntimeTypeEnum, ProtobufValue};
 use redis::{FromRedisValue, ToRedisArgs};
 use serde::{de::Visitor, Deserialize, Serialize};
 
-use super::{Fields, FieldsMap};
+use super::{Fields, FieldsMap, ServiceError, ServiceResult};
 
-pub trait Integer: Sync + Send + std::fmt::Debug {
-    fn to_i32(&self) -> i32;
-    fn to_i64(&self) -> i64;
-    fn to_u32(&self) -> u32;
-    fn to_u64(&self) -> u64;
+/// An integer kept exactly as decoded, signed or unsigned, so 64-bit values round trip.
+#[derive(Debug, Clone, Copy)]
+pub enum Integer {
+    Signed(i64),
+    Unsigned(u64),
 }
 
-impl Integer for i32 {
-    fn to_i32(&self) -> i32 {
-        self.clone()
-    }
-
-    fn to_i64(&self) -> i64 {
-        self.clone().into()
+impl Integer {
+    fn to_i128(&self) -> i128 {
+        match self {
+            Integer::Signed(v) => i128::from(*v),
+            Integer::Unsigned(v) => i128::from(*v),
+        }
     }
 
-    fn to_u32(&self) -> u32 {
-        self.clone().try_into().unwrap_or(0)
+    pub fn to_i32(&self) -> ServiceResult<i32> {
+        self.narrow("int32")
     }
 
-    fn to_u64(&self) -> u64 {
-        self.clone().try_into().unwrap_or(0)
+    pub fn to_i64(&self) -> ServiceResult<i64> {
+        self.narrow("int64")
     }
-}
 
-impl Integer for i64 {
-    fn to_i32(&self) -> i32 {
-        self.clone().try_into().unwrap_or(0)
+    pub fn to_u32(&self) -> ServiceResult<u32> {
+        self.narrow("uint32")
     }
 
-    fn to_i64(&self) -> i64 {
-        self.clone()
+    pub fn to_u64(&self) -> ServiceResult<u64> {
+        self.narrow("uint64")
     }
 
-    fn to_u32(&self) -> u32 {
-        self.clone().try_into().unwrap_or(0)
+    /// Nearest double, as JSON numbers hold integers.
+    pub fn to_f64(&self) -> f64 {
+        self.to_i128() as f64
     }
 
-    fn to_u64(&self) -> u64 {
-        self.clone().try_into().unwrap_or(0)
+    /// Converts to a narrower type, failing rather than changing the value.
+    fn narrow<T: TryFrom<i128>>(&self, type_name: &str) -> ServiceResult<T> {
+        self.to_i128().try_into().map_err(|_| {
+            ServiceError::InvalidArgument(format!("{} is out of range for {}", self, type_name))
+        })
     }
 }
 
-impl Integer for u32 {
-    fn to_i32(&self) -> i32 {
-        self.clone().try_into().unwrap_or(0)
-    }
-
-    fn to_i64(&self) -> i64 {
-        self.clone().try_into().unwrap_or(0)
-    }
-
-    fn to_u32(&self) -> u32 {
-        self.clone()
-    }
-
-    fn to_u64(&self) -> u64 {
-        self.clone().into()
+impl From<i32> for Integer {
+    fn from(v: i32) -> Self {
+        Integer::Signed(v.into())
     }
 }
 
-impl Integer for u64 {
-    fn to_i32(&self) -> i32 {
-        self.clone().try_into().unwrap_or(0)
+impl From<i64> for Integer {
+    fn from(v: i64) -> Self {
+        Integer::Signed(v)
     }
+}
 
-    fn to_i64(&self) -> i64 {
-        self.clone().try_into().unwrap_or(0)
+impl From<u32> for Integer {
+    fn from(v: u32) -> Self {
+        Integer::Unsigned(v.into())
     }
+}
 
-    fn to_u32(&self) -> u32 {
-        self.clone().try_into().unwrap_or(0)
+impl From<u64> for Integer {
+    fn from(v: u64) -> Self {
+        Integer::Unsigned(v)
     }
+}
 
-    fn to_u64(&self) -> u64 {
-        self.clone()
+impl std::fmt::Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Integer::Signed(v) => v.fmt(f),
+            Integer::Unsigned(v) => v.fmt(f),
+        }
     }
 }
 
-impl Hash for dyn Integer {
+impl Hash for Integer {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        state.write_i64(self.to_i64())
+        state.write_i128(self.to_i128())
     }
 }
 
-impl PartialEq for dyn Integer {
+impl PartialEq for Integer {
     fn eq(&self, other: &Self) -> bool {
-        self.to_i64() == other.to_i64()
+        self.to_i128() == other.to_i128()
     }
 }
 
-impl Eq for dyn Integer {}
+impl Eq for Integer {}
 
-pub trait Floating: Sync + Send + std::fmt::Debug {
-    fn to_f32(&self) -> f32;
-    fn to_f64(&self) -> f64;
+/// A float kept at the width it was decoded with.
+#[derive(Debug, Clone, Copy)]
+pub enum Floating {
+    F32(f32),
+    F64(f64),
 }
 
-impl Floating for f32 {
-    fn to_f32(&self) -> f32 {
-        self.clone()
+impl Floating {
+    pub fn to_f32(&self) -> ServiceResult<f32> {
+        match self {
+            Floating::F32(v) => Ok(*v),
+            Floating::F64(v) => num::ToPrimitive::to_f32(v).ok_or_else(|| {
+                ServiceError::InvalidArgument(format!("{} is out of range for float", v))
+            }),
+        }
     }
 
-    fn to_f64(&self) -> f64 {
-        self.clone().into()
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Floating::F32(v) => f64::from(*v),
+            Floating::F64(v) => *v,
+        }
     }
 }
 
-impl Floating for f64 {
-    fn to_f32(&self) -> f32 {
-        num::ToPrimitive::to_f32(self).unwrap_or(0f32)
+impl From<f32> for Floating {
+    fn from(v: f32) -> Self {
+        Floating::F32(v)
     }
+}
 
-    fn to_f64(&self) -> f64 {
-        self.clone()
+impl From<f64> for Floating {
+    fn from(v: f64) -> Self {
+        Floating::F64(v)
     }
 }
 
-impl Hash for dyn Floating {
+impl Hash for Floating {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         state.write(&self.to_f64().to_be_bytes())
     }
 }
 
-impl PartialEq for dyn Floating {
+impl PartialEq for Floating {
     fn eq(&self, other: &Self) -> bool {
         self.to_f64().to_bits() == other.to_f64().to_bits()
     }
 }
 
-impl Eq for dyn Floating {}
+impl Eq for Floating {}
 
 #[derive(Debug, Clone, Hash, Eq)]
 pub enum Value {
     String(String),
     Bytes(Vec<u8>),
-    Int(Arc<dyn Integer>),
-    Float(Arc<dyn Floating>),
+    Int(Integer),
+    Float(Floating),
     Bool(bool),
     Enum(ProtoEnum),
     Message(Fields),
     Array(Vec<Value>),
+    /// Entries of a `map<K, V>` field, keyed by the key in its JSON string form.
+    Map(BTreeMap<String, Value>),
     None,
 }
 
@@ -166,21 +177,45 @@ impl Value {
 
     pub fn from_int<T>(val: T) -> Self
     where
-        T: Integer + 'static,
+        T: Into<Integer>,
     {
-        Self::Int(Arc::new(val))
+        Self::Int(val.into())
     }
 
     pub fn from_float<T>(val: T) -> Self
     where
-        T: Floating + 'static,
+        T: Into<Floating>,
     {
-        Self::Float(Arc::new(val))
+        Self::Float(val.into())
     }
 
     pub fn from_message(fields: Fields) -> Self {
         Self::Message(fields)
     }
+
+    /// Renders a scalar as a map key, `None` for types that cannot key a map.
+    pub fn to_map_key(&self) -> Option<String> {
+        match self {
+            Value::String(v) => Some(v.clone()),
+            Value::Int(v) => Some(v.to_string()),
+            Value::Bool(v) => Some(v.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Whether a scalar holds the default of its type, which proto3 leaves off the wire for
+    /// fields without presence.
+    pub fn is_default(&self) -> bool {
+        match self {
+            Value::String(v) => v.is_empty(),
+            Value::Bytes(v) => v.is_empty(),
+            Value::Int(v) => *v == Integer::Signed(0),
+            Value::Float(v) => v.to_f64().to_bits() == 0,
+            Value::Bool(v) => !v,
+            Value::Enum(v) => *v == ProtoEnum::default(),
+            _ => false,
+        }
+    }
 }
 
 impl ToRedisArgs for Value {
@@ -281,12 +316,15 @@ impl Serialize for Value {
         match self {
             Value::String(v) => sr.serialize_str(v.as_str()),
             Value::Bytes(v) => sr.serialize_bytes(&v[..]),
-            Value::Int(v) => sr.serialize_i64(v.to_i64()),
-            Value::Float(v) => sr.serialize_f64(v.to_f64()),
+            Value::Int(Integer::Signed(v)) => sr.serialize_i64(*v),
+            Value::Int(Integer::Unsigned(v)) => sr.serialize_u64(*v),
+            Value::Float(Floating::F32(v)) => sr.serialize_f32(*v),
+            Value::Float(Floating::F64(v)) => sr.serialize_f64(*v),
             Value::Bool(v) => sr.serialize_bool(*v),
             Value::Enum(v) => sr.serialize_i32(v.value()),
             Value::Message(v) => v.serialize(sr),
             Value::Array(v) => serialize_seq(sr, &v),
+            Value::Map(v) => v.serialize(sr),
             Value::None => sr.serialize_none(),
         }
     }
@@ -397,7 +435,7 @@ impl PartialEq for Value {
         match (self, other) {
             (Self::String(l0), Self::String(r0)) => l0 == r0,
             (Self::Bytes(l0), Self::Bytes(r0)) => l0 == r0,
-            (Self::Int(l0), Self::Int(r0)) => l0.to_i64() == r0.to_i64(),
+            (Self::Int(l0), Self::Int(r0)) => l0 == r0,
             (Self::Float(l0), Self::Float(r0)) => l0.to_f64() == r0.to_f64(),
             (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
             (Self::Enum(l0), Self::Enum(r0)) => l0 == r0,
@@ -407,6 +445,7 @@ impl PartialEq for Value {
             (Self::Array(l0), Self::Array(r0)) => {
                 l0.len() == r0.len() && l0.iter().zip(r0).all(|(l, r)| l == r)
             }
+            (Self::Map(l0), Self::Map(r0)) => l0 == r0,
             _ => false,
         }
     }
@@ -0,0 +1,370 @@
+use std::{convert::TryFrom, sync::Arc};
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use dashmap::DashMap;
+use protobuf::well_known_types as wkt;
+
+use super::{
+    message::Message,
+    value::{ProtoEnum, Value},
+    Fields, FieldsMap, Service, ServiceError, ServiceResult,
+};
+
+/// Package the well-known types are registered under.
+const PACKAGE: &str = "google.protobuf.";
+
+/// Well-known types whose JSON mapping is not a plain object of their fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WellKnown {
+    Timestamp,
+    Duration,
+    /// `StringValue`, `Int64Value` and the other wrappers, mapped to the value they wrap.
+    Wrapper,
+    Struct,
+    Value,
+    ListValue,
+    Any,
+}
+
+impl WellKnown {
+    pub fn from_type_name(type_name: &str) -> Option<Self> {
+        let name = type_name.trim_start_matches('.').strip_prefix(PACKAGE)?;
+        match name {
+            "Timestamp" => Some(WellKnown::Timestamp),
+            "Duration" => Some(WellKnown::Duration),
+            "DoubleValue" | "FloatValue" | "Int64Value" | "UInt64Value" | "Int32Value"
+            | "UInt32Value" | "BoolValue" | "StringValue" | "BytesValue" => {
+                Some(WellKnown::Wrapper)
+            }
+            "Struct" => Some(WellKnown::Struct),
+            "Value" => Some(WellKnown::Value),
+            "ListValue" => Some(WellKnown::ListValue),
+            "Any" => Some(WellKnown::Any),
+            _ => None,
+        }
+    }
+
+    /// Whether the type maps to free-form JSON, stored as `jsonb`.
+    pub fn is_json(&self) -> bool {
+        matches!(
+            self,
+            WellKnown::Struct | WellKnown::Value | WellKnown::ListValue | WellKnown::Any
+        )
+    }
+
+    /// Converts a decoded message of this type into the value of its JSON mapping.
+    ///
+    /// Messages nested in a `Struct`, `Value` or `ListValue` are already converted, as
+    /// fields are decoded from the inside out.
+    pub fn to_json(&self, message: &Message, fields: &Fields) -> ServiceResult<Value> {
+        let get = |name: &str| fields.map.get(name).and_then(|value| value.value().clone());
+        Ok(match self {
+            WellKnown::Timestamp => Value::String(format_timestamp(
+                int(get("seconds"))?,
+                i32::try_from(int(get("nanos"))?)?,
+            )?),
+            WellKnown::Duration => Value::String(format_duration(
+                int(get("seconds"))?,
+                i32::try_from(int(get("nanos"))?)?,
+            )),
+            WellKnown::Wrapper => get("value").unwrap_or_else(|| default_value(message, "value")),
+            WellKnown::Struct => match get("fields") {
+                Some(Value::Map(fields)) => Value::Map(fields),
+                _ => Value::Map(Default::default()),
+            },
+            WellKnown::Value => match fields.oneofs.get("kind").map(|kind| kind.value().clone()) {
+                Some(kind) if kind != "null_value" => get(&kind).unwrap_or(Value::None),
+                _ => Value::None,
+            },
+            WellKnown::ListValue => match get("values") {
+                Some(Value::Array(values)) => Value::Array(values),
+                _ => Value::Array(Vec::new()),
+            },
+            WellKnown::Any => any_to_json(message, get("type_url"), get("value"))?,
+        })
+    }
+
+    /// Converts a value in the JSON mapping of this type back into the fields of the message.
+    pub fn from_json(&self, message: &Message, value: Value) -> ServiceResult<Fields> {
+        let fields = FieldsMap::new();
+        let set = |name: &str, value: Value| {
+            fields.insert(name.to_string(), Some(value));
+        };
+        match (self, value) {
+            (WellKnown::Timestamp, Value::String(value)) => {
+                let (seconds, nanos) = parse_timestamp(&value)?;
+                set("seconds", Value::from_int(seconds));
+                set("nanos", Value::from_int(nanos));
+            }
+            (WellKnown::Duration, Value::String(value)) => {
+                let (seconds, nanos) = parse_duration(&value)?;
+                set("seconds", Value::from_int(seconds));
+                set("nanos", Value::from_int(nanos));
+            }
+            (WellKnown::Wrapper, value) => set("value", value),
+            (WellKnown::Struct, Value::Map(object)) => set("fields", Value::Map(object)),
+            (WellKnown::Struct, Value::Message(object)) => {
+                let object = object
+                    .map
+                    .iter()
+                    .filter_map(|kv| Some((kv.key().clone(), kv.value().clone()?)))
+                    .collect();
+                set("fields", Value::Map(object));
+            }
+            (WellKnown::Value, value) => match value {
+                Value::None => set("null_value", Value::Enum(ProtoEnum::Val(0))),
+                Value::Int(v) => set("number_value", Value::from_float(v.to_f64())),
+                Value::Float(_) => set("number_value", value),
+                Value::String(_) => set("string_value", value),
+                Value::Bool(_) => set("bool_value", value),
+                Value::Message(_) | Value::Map(_) => set("struct_value", value),
+                Value::Array(_) => set("list_value", value),
+                value => {
+                    return Err(ServiceError::InvalidArgument(format!(
+                        "google.protobuf.Value cannot hold {:?}",
+                        value
+                    )))
+                }
+            },
+            (WellKnown::ListValue, Value::Array(values)) => set("values", Value::Array(values)),
+            (WellKnown::Any, Value::Message(object)) => return any_from_json(message, object),
+            (kind, value) => {
+                return Err(ServiceError::InvalidArgument(format!(
+                    "invalid JSON for {:?}: {:?}",
+                    kind, value
+                )))
+            }
+        }
+        Ok(Fields::new(fields))
+    }
+}
+
+/// Registers descriptors of the well-known types under their full names, so fields using them
+/// resolve without the types being loaded from the service's own file.
+pub fn register(messages: &Arc<DashMap<String, Message>>) {
+    use protobuf::Message as _;
+    let descriptors = vec![
+        wkt::Any::descriptor_static().get_proto().clone(),
+        wkt::Duration::descriptor_static().get_proto().clone(),
+        wkt::Empty::descriptor_static().get_proto().clone(),
+        wkt::FieldMask::descriptor_static().get_proto().clone(),
+        wkt::Struct::descriptor_static().get_proto().clone(),
+        wkt::Value::descriptor_static().get_proto().clone(),
+        wkt::ListValue::descriptor_static().get_proto().clone(),
+        wkt::Timestamp::descriptor_static().get_proto().clone(),
+        wkt::DoubleValue::descriptor_static().get_proto().clone(),
+        wkt::FloatValue::descriptor_static().get_proto().clone(),
+        wkt::Int64Value::descriptor_static().get_proto().clone(),
+        wkt::UInt64Value::descriptor_static().get_proto().clone(),
+        wkt::Int32Value::descriptor_static().get_proto().clone(),
+        wkt::UInt32Value::descriptor_static().get_proto().clone(),
+        wkt::BoolValue::descriptor_static().get_proto().clone(),
+        wkt::StringValue::descriptor_static().get_proto().clone(),
+        wkt::BytesValue::descriptor_static().get_proto().clone(),
+    ];
+    for (name, descriptor) in Service::nested_messages(PACKAGE, &descriptors) {
+        let message =
+            Message::new(descriptor, String::new(), messages.clone()).with_syntax("proto3");
+        messages.insert(name, message);
+    }
+}
+
+/// Formats a `Timestamp` as RFC 3339 in UTC, with 0, 3, 6 or 9 fractional digits.
+pub fn format_timestamp(seconds: i64, nanos: i32) -> ServiceResult<String> {
+    let time =
+        NaiveDateTime::from_timestamp_opt(seconds, u32::try_from(nanos).unwrap_or(0)).ok_or(
+            ServiceError::InvalidArgument(format!("timestamp out of range: {}s", seconds)),
+        )?;
+    Ok(DateTime::<Utc>::from_utc(time, Utc).to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+pub fn parse_timestamp(value: &str) -> ServiceResult<(i64, i32)> {
+    let time = DateTime::parse_from_rfc3339(value).map_err(|err| {
+        ServiceError::InvalidArgument(format!("invalid timestamp {:?}: {}", value, err))
+    })?;
+    Ok((time.timestamp(), time.timestamp_subsec_nanos() as i32))
+}
+
+/// Formats a `Duration` as seconds with an `s` suffix, such as `1.500s`.
+pub fn format_duration(seconds: i64, nanos: i32) -> String {
+    let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+    let mut out = format!("{}{}", sign, seconds.abs());
+    if nanos != 0 {
+        let mut fraction = format!("{:09}", nanos.abs());
+        while fraction.ends_with("000") {
+            fraction.truncate(fraction.len() - 3);
+        }
+        out = format!("{}.{}", out, fraction);
+    }
+    out + "s"
+}
+
+pub fn parse_duration(value: &str) -> ServiceResult<(i64, i32)> {
+    let invalid = || ServiceError::InvalidArgument(format!("invalid duration: {:?}", value));
+    let digits = value.strip_suffix('s').ok_or_else(invalid)?;
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, digits),
+    };
+    let mut parts = digits.splitn(2, '.');
+    let seconds: i64 = parts
+        .next()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|_| invalid())?;
+    let nanos: i32 = match parts.next() {
+        Some(fraction) if fraction.len() <= 9 && fraction.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("{:0<9}", fraction).parse().map_err(|_| invalid())?
+        }
+        Some(_) => return Err(invalid()),
+        None => 0,
+    };
+    Ok(if negative {
+        (-seconds, -nanos)
+    } else {
+        (seconds, nanos)
+    })
+}
+
+fn int(value: Option<Value>) -> ServiceResult<i64> {
+    match value {
+        Some(Value::Int(v)) => v.to_i64(),
+        _ => Ok(0),
+    }
+}
+
+/// Zero value of a scalar field, which is left out when encoded.
+fn default_value(message: &Message, name: &str) -> Value {
+    use protobuf::descriptor::field_descriptor_proto::Type::*;
+    let field_type = message
+        .fields_by_name
+        .get(name)
+        .map(|field| field.descriptor.get_field_type());
+    match field_type {
+        Some(TYPE_STRING) => Value::String(String::new()),
+        Some(TYPE_BYTES) => Value::Bytes(Vec::new()),
+        Some(TYPE_BOOL) => Value::Bool(false),
+        Some(TYPE_DOUBLE) | Some(TYPE_FLOAT) => Value::from_float(0f64),
+        _ => Value::from_int(0i64),
+    }
+}
+
+/// Maps an `Any` to `{"@type": url, ...fields}`, or `{"@type": url, "value": ...}` when it holds
+/// a well-known type.
+fn any_to_json(
+    message: &Message,
+    type_url: Option<Value>,
+    value: Option<Value>,
+) -> ServiceResult<Value> {
+    let type_url = match type_url {
+        Some(Value::String(type_url)) => type_url,
+        _ => return Ok(Value::None),
+    };
+    let buf = match value {
+        Some(Value::Bytes(buf)) => buf,
+        _ => Vec::new(),
+    };
+    let object = FieldsMap::new();
+    object.insert("@type".to_string(), Some(Value::String(type_url.clone())));
+    let type_name = type_url.rsplit('/').next().unwrap_or_default();
+    match message.message_type(type_name) {
+        Some(inner) => {
+            let fields = inner.fields_from_raw(&buf[..])?;
+            match WellKnown::from_type_name(type_name) {
+                Some(kind) => {
+                    object.insert("value".to_string(), Some(kind.to_json(&inner, &fields)?));
+                }
+                None => {
+                    for kv in fields.map.iter() {
+                        object.insert(kv.key().clone(), kv.value().clone());
+                    }
+                }
+            }
+        }
+        // Without a descriptor the payload can only be passed on as is.
+        None => {
+            object.insert(
+                "value".to_string(),
+                Some(Value::String(base64::encode(&buf))),
+            );
+        }
+    }
+    Ok(Value::Message(Fields::new(object)))
+}
+
+fn any_from_json(message: &Message, object: Fields) -> ServiceResult<Fields> {
+    let type_url = match object.map.remove("@type") {
+        Some((_, Some(Value::String(type_url)))) => type_url,
+        _ => {
+            return Err(ServiceError::InvalidArgument(
+                "google.protobuf.Any without @type".to_string(),
+            ))
+        }
+    };
+    let type_name = type_url.rsplit('/').next().unwrap_or_default();
+    let buf = match message.message_type(type_name) {
+        Some(inner) => {
+            let fields = match WellKnown::from_type_name(type_name) {
+                Some(kind) => {
+                    let value = object.map.remove("value").and_then(|(_, value)| value);
+                    kind.from_json(&inner, value.unwrap_or(Value::None))?
+                }
+                None => object,
+            };
+            inner.raw_bytes_from_fields(&fields)?
+        }
+        None => match object.map.remove("value") {
+            Some((_, Some(Value::String(value)))) => base64::decode(&value).map_err(|err| {
+                ServiceError::InvalidArgument(format!("invalid google.protobuf.Any value: {}", err))
+            })?,
+            _ => Vec::new(),
+        },
+    };
+    let fields = FieldsMap::new();
+    fields.insert("type_url".to_string(), Some(Value::String(type_url)));
+    fields.insert("value".to_string(), Some(Value::Bytes(buf)));
+    Ok(Fields::new(fields))
+}
+
+mod tests {
+    #[test]
+    fn test_json_mappings() {
+        use super::*;
+        assert_eq!(
+            format_timestamp(63_115_200, 21_000_000).unwrap(),
+            "1972-01-01T12:00:00.021Z"
+        );
+        assert_eq!(
+            parse_timestamp("1972-01-01T10:00:00.021-02:00").unwrap(),
+            (63_115_200, 21_000_000)
+        );
+        assert_eq!(format_duration(1, 500_000_000), "1.500s");
+        assert_eq!(format_duration(-3, 0), "-3s");
+        assert_eq!(parse_duration("-1.000340012s").unwrap(), (-1, -340_012));
+        assert!(parse_duration("1.5").is_err());
+        assert_eq!(
+            WellKnown::from_type_name(".google.protobuf.StringValue"),
+            Some(WellKnown::Wrapper)
+        );
+        assert_eq!(WellKnown::from_type_name(".pkg.Timestamp"), None);
+    }
+
+    #[test]
+    fn test_register() {
+        use super::*;
+        let messages = Arc::new(DashMap::new());
+        register(&messages);
+        let timestamp = messages.get("google.protobuf.Timestamp").unwrap();
+        let fields = WellKnown::Timestamp
+            .from_json(
+                &timestamp,
+                Value::String("1970-01-01T00:00:01Z".to_string()),
+            )
+            .unwrap();
+        assert_eq!(
+            WellKnown::Timestamp.to_json(&timestamp, &fields).unwrap(),
+            Value::String("1970-01-01T00:00:01Z".to_string())
+        );
+        assert!(messages.contains_key("google.protobuf.Struct.FieldsEntry"));
+    }
+}
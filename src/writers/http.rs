@@ -1,36 +1,120 @@
 use std::{str::FromStr, sync::Arc, convert::TryInto};
 
 use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{self, StreamExt};
+use hyper::client::HttpConnector;
 use hyper::{body::HttpBody, client::conn};
+use hyper_openssl::HttpsConnector;
+use openssl::ssl::{SslConnector, SslFiletype, SslMethod};
 use tokio::sync::{Mutex, RwLock};
 
 use crate::{
-    proto::gen::format::http::HTTPVersion,
-    services::{Fields, Handler, ServiceError, ServiceResult, Writer, WriterContext},
+    proto::gen::format::http::{HTTPService, HTTPVersion},
+    server::tls::ALPN_H2,
+    services::{
+        template::{self, SuccessStatus},
+        with_deadline, Deadline, Fields, FieldsStream, Handler, ServiceError, ServiceResult,
+        Writer, WriterContext, WriterResponse,
+    },
 };
 
 use super::LoadBalancer;
 
+/// Largest response body read when the service doesn't set `max_response_size`.
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 4 * 1024 * 1024;
+
+/// ALPN protocol list offering HTTP/1.1 only.
+const ALPN_HTTP_11: &[u8] = b"\x08http/1.1";
+
 pub struct HttpWriter {
-    client: hyper::Client<hyper::client::HttpConnector>,
+    client: hyper::Client<HttpsConnector<HttpConnector>>,
     version: http::Version,
+    /// `https` when the service is reached over TLS.
+    scheme: &'static str,
     lb: LoadBalancer,
+    max_response_size: usize,
 }
 
 impl HttpWriter {
-    pub fn new(lb: LoadBalancer, version: HTTPVersion) -> HttpWriter {
-        let version = match version {
+    pub fn new(lb: LoadBalancer, service: &HTTPService) -> ServiceResult<HttpWriter> {
+        let version = match service.version.unwrap() {
             HTTPVersion::VERSION_1_0 => http::Version::HTTP_10,
             HTTPVersion::VERSION_1_1 => http::Version::HTTP_11,
             HTTPVersion::VERSION_2_0 => http::Version::HTTP_2,
         };
-        let client = hyper::Client::new();
-        Self {
+        let client = hyper::Client::builder().build(connector(service, version)?);
+        let scheme = match service.tls.as_ref() {
+            Some(tls) if tls.enable => "https",
+            _ => "http",
+        };
+        let max_response_size = match service.max_response_size {
+            0 => DEFAULT_MAX_RESPONSE_SIZE,
+            size => size as usize,
+        };
+        Ok(Self {
             client,
             version,
+            scheme,
             lb,
+            max_response_size,
+        })
+    }
+}
+
+/// Builds the connector for a service, which only speaks TLS to `https` URIs.
+///
+/// HTTP/2 is offered through ALPN only for `VERSION_2_0`, so an HTTP/1 service isn't
+/// upgraded behind the configured version's back.
+fn connector(
+    service: &HTTPService,
+    version: http::Version,
+) -> ServiceResult<HttpsConnector<HttpConnector>> {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    let mut builder = SslConnector::builder(SslMethod::tls())?;
+    let tls = match service.tls.as_ref() {
+        Some(tls) if tls.enable => tls,
+        _ => return Ok(HttpsConnector::with_connector(http, builder)?),
+    };
+    if !tls.ca.is_empty() {
+        builder.set_ca_file(&tls.ca)?;
+    }
+    match (tls.cert.is_empty(), tls.key.is_empty()) {
+        (true, true) => {}
+        (false, false) => {
+            builder.set_certificate_chain_file(&tls.cert)?;
+            builder.set_private_key_file(&tls.key, SslFiletype::PEM)?;
+            builder.check_private_key()?;
+        }
+        _ => {
+            return Err(ServiceError::InvalidArgument(
+                "tls.cert and tls.key must be set together".to_string(),
+            ))
         }
     }
+    let alpn = match version {
+        http::Version::HTTP_2 => ALPN_H2,
+        _ => ALPN_HTTP_11,
+    };
+    builder.set_alpn_protos(alpn)?;
+    let mut connector = HttpsConnector::with_connector(http, builder)?;
+
+    let server_name = match tls.server_name.as_str() {
+        "" => service.hostname.clone(),
+        server_name => server_name.to_string(),
+    };
+    if !server_name.is_empty() {
+        // Requests are sent to an address from the load balancer, so the name to present
+        // and verify is set here rather than taken from the URI.
+        connector.set_callback(move |config, _| {
+            config.set_use_server_name_indication(false);
+            config.set_verify_hostname(false);
+            config.set_hostname(&server_name)?;
+            config.param_mut().set_host(&server_name)
+        });
+    }
+    Ok(connector)
 }
 
 #[async_trait]
@@ -38,31 +122,231 @@ impl Writer for HttpWriter {
     async fn write_request(
         &mut self,
         context: WriterContext,
+        deadline: Deadline,
+        success: &SuccessStatus,
+        fields: &Fields,
+        handler: &Arc<dyn Handler + Send + Sync>,
+    ) -> ServiceResult<WriterResponse<bytes::Bytes>> {
+        let payload = payload(&context, fields, handler).await?;
+        with_deadline(deadline, async {
+            let resp = self.send_request(context, success, payload).await?;
+            self.read_response(resp).await
+        })
+        .await
+    }
+
+    async fn write_batch(
+        &mut self,
+        context: WriterContext,
+        deadline: Deadline,
+        success: &SuccessStatus,
+        fields: &[Fields],
+        handler: &Arc<dyn Handler + Send + Sync>,
+    ) -> ServiceResult<WriterResponse<bytes::Bytes>> {
+        // A batch has no single set of fields to put in the query string.
+        context.remove("fields");
+        let payload = handler.to_payload_batch(fields).await?;
+        with_deadline(deadline, async {
+            let resp = self.send_request(context, success, payload).await?;
+            self.read_response(resp).await
+        })
+        .await
+    }
+
+    fn probe(&self) -> BoxFuture<'static, ServiceResult<()>> {
+        let lb = self.lb.clone();
+        async move {
+            let addr = lb.get_addr().await;
+            tokio::net::TcpStream::connect(&addr)
+                .await
+                .map_err(|err| ServiceError::Unavailable(format!("{}: {}", addr, err)))?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    async fn write_request_stream(
+        &mut self,
+        context: WriterContext,
+        deadline: Deadline,
+        success: &SuccessStatus,
         fields: &Fields,
         handler: &Arc<dyn Handler + Send + Sync>,
-    ) -> ServiceResult<bytes::Bytes> {
-        let payload = handler.to_payload(fields).await?;
+    ) -> ServiceResult<WriterResponse<FieldsStream>> {
+        let payload = payload(&context, fields, handler).await?;
+        // The caller bounds the rest of the stream by the same deadline.
+        let resp = with_deadline(deadline, self.send_request(context, success, payload)).await?;
+        let status = Some(resp.status());
+        let headers = Some(resp.headers().clone());
+        let ndjson = resp
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .map(|v| v.as_bytes().starts_with(b"application/x-ndjson"))
+            .unwrap_or_default();
+        let handler = handler.clone();
+        let limit = self.max_response_size;
+        if !ndjson {
+            let body = with_deadline(deadline, read_body(resp, limit)).await?;
+            let resp_fields = handler.from_payload_stream(body)?;
+            return Ok(WriterResponse {
+                body: stream::iter(resp_fields.into_iter().map(Ok)).boxed(),
+                status,
+                headers,
+            });
+        }
+        let body = resp.into_body();
+
+        // Emit a message per line as the backend flushes them.
+        let lines = stream::unfold(
+            (body, bytes::BytesMut::new(), false),
+            move |(mut body, mut buf, mut done)| async move {
+                loop {
+                    if let Some(pos) = buf.iter().position(|byte| *byte == b'\n') {
+                        let line = buf.split_to(pos + 1).freeze();
+                        return Some((Ok(line), (body, buf, done)));
+                    }
+                    if done {
+                        if buf.is_empty() {
+                            return None;
+                        }
+                        let line = buf.split().freeze();
+                        return Some((Ok(line), (body, buf, done)));
+                    }
+                    if buf.len() > limit {
+                        let err = ServiceError::ResourceExhausted(format!(
+                            "streamed response line exceeds {} bytes",
+                            limit
+                        ));
+                        return Some((Err(err), (body, bytes::BytesMut::new(), true)));
+                    }
+                    match body.data().await {
+                        Some(Ok(chunk)) => buf.extend_from_slice(&chunk[..]),
+                        Some(Err(e)) => {
+                            let err = ServiceError::Unavailable(format!(
+                                "error reading streamed body: {}",
+                                e
+                            ));
+                            return Some((Err(err), (body, bytes::BytesMut::new(), true)));
+                        }
+                        None => done = true,
+                    }
+                }
+            },
+        );
+        let resp = lines.filter_map(move |line| {
+            let resp_fields = match line {
+                Ok(line) if line.iter().all(u8::is_ascii_whitespace) => None,
+                Ok(line) => Some(handler.from_payload(line)),
+                Err(err) => Some(Err(err)),
+            };
+            futures::future::ready(resp_fields)
+        });
+        Ok(WriterResponse {
+            body: resp.boxed(),
+            status,
+            headers,
+        })
+    }
+}
+
+impl HttpWriter {
+    async fn send_request(
+        &mut self,
+        context: WriterContext,
+        success: &SuccessStatus,
+        payload: bytes::Bytes,
+    ) -> ServiceResult<hyper::Response<hyper::Body>> {
         let addr = self.lb.get_addr().await;
-        let request = request_from_context(self.version.clone(), context, payload, addr.clone())?;
+        let request = request_from_context(
+            self.version.clone(),
+            self.scheme,
+            context,
+            payload,
+            addr.clone(),
+        )?;
         log::info!("sending HTTP request to {}", &addr);
-        let mut resp = self.client.request(request).await?;
+        let resp = self
+            .client
+            .request(request)
+            .await
+            .map_err(|err| ServiceError::Unavailable(err.to_string()))?;
+        if !success.matches(resp.status()) {
+            let code = resp.status().as_u16().to_string();
+            let body = read_body(resp, self.max_response_size)
+                .await
+                .unwrap_or_default();
+            return Err(ServiceError::Backend {
+                code,
+                message: String::from_utf8_lossy(&body[..]).to_string(),
+            });
+        }
+        Ok(resp)
+    }
 
-        let body = resp.body_mut();
-        let body = match body.data().await {
-            Some(body) => body,
-            None => return Err(ServiceError::new("no body in response")),
-        };
-        match body {
-            Ok(body) => Ok(body),
-            Err(e) => Err(ServiceError::new(
-                format!("error parsing body: {}", e).as_str(),
-            )),
+    /// Reads the body of a response, keeping its status and headers.
+    async fn read_response(
+        &self,
+        resp: hyper::Response<hyper::Body>,
+    ) -> ServiceResult<WriterResponse<bytes::Bytes>> {
+        let status = Some(resp.status());
+        let headers = Some(resp.headers().clone());
+        let body = read_body(resp, self.max_response_size).await?;
+        Ok(WriterResponse {
+            body,
+            status,
+            headers,
+        })
+    }
+}
+
+/// Encodes the fields as the request body, or in query mode appends them to the URI and
+/// sends no body.
+async fn payload(
+    context: &WriterContext,
+    fields: &Fields,
+    handler: &Arc<dyn Handler + Send + Sync>,
+) -> ServiceResult<bytes::Bytes> {
+    match context.remove("fields") {
+        Some((_, mode)) if mode == "query" => {
+            let query = template::query_string(fields);
+            if !query.is_empty() {
+                if let Some(mut uri) = context.get_mut("uri") {
+                    let separator = if uri.contains('?') { '&' } else { '?' };
+                    uri.push(separator);
+                    uri.push_str(&query);
+                }
+            }
+            Ok(bytes::Bytes::new())
+        }
+        _ => handler.to_payload(fields).await,
+    }
+}
+
+/// Reads the whole response body, failing once it grows past `limit` bytes.
+async fn read_body(
+    mut resp: hyper::Response<hyper::Body>,
+    limit: usize,
+) -> ServiceResult<bytes::Bytes> {
+    let body = resp.body_mut();
+    let mut buf = bytes::BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| {
+            ServiceError::Unavailable(format!("error reading response body: {}", e))
+        })?;
+        buf.extend_from_slice(&chunk[..]);
+        if buf.len() > limit {
+            return Err(ServiceError::ResourceExhausted(format!(
+                "response body exceeds {} bytes",
+                limit
+            )));
         }
     }
+    Ok(buf.freeze())
 }
 
 fn request_from_context(
     version: http::Version,
+    scheme: &str,
     context: WriterContext,
     body: bytes::Bytes,
     addr: String,
@@ -70,7 +354,7 @@ fn request_from_context(
     let len = body.len();
     let body = hyper::Body::from(body);
     let mut builder = http::Request::builder();
-    let mut uri = http::Uri::builder().scheme("http");
+    let mut uri = http::Uri::builder().scheme(scheme);
 
     for (k, v) in context {
         match k.as_str() {
@@ -102,3 +386,24 @@ fn request_from_context(
     let request = builder.body(body)?;
     Ok(request)
 }
+
+mod tests {
+    #[test]
+    fn test_connector() {
+        use super::*;
+        use crate::proto::gen::format::http::HTTPTLS;
+        let mut service = HTTPService::new();
+        assert!(connector(&service, http::Version::HTTP_11).is_ok());
+
+        let mut tls = HTTPTLS::new();
+        tls.enable = true;
+        tls.cert = "client.pem".to_string();
+        service.tls = protobuf::MessageField::some(tls.clone());
+        assert!(connector(&service, http::Version::HTTP_2).is_err());
+
+        tls.cert.clear();
+        tls.server_name = "api.internal".to_string();
+        service.tls = protobuf::MessageField::some(tls);
+        assert!(connector(&service, http::Version::HTTP_2).is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::{HashSet, VecDeque};
-use std::path::PathBuf;
 use std::sync::Arc;
 
+use protobuf::descriptor::ServiceDescriptorProto;
 use tokio::sync::{Mutex, RwLock};
 
 use crate::proto::gen::format;
@@ -14,43 +14,31 @@ use self::http::HttpWriter;
 pub mod http;
 pub mod postgres;
 
-pub fn writer_from_proto(
-    proto_path: PathBuf,
-    includes: &[PathBuf],
+/// Builds the writer for a service from its format options.
+pub fn writer_from_service(
+    service: &ServiceDescriptorProto,
     hosts: HashSet<String>,
     port: i32,
 ) -> ServiceResult<WriterRef> {
-    let parsed = protobuf_parse::pure::parse_and_typecheck(includes, &[proto_path.clone()])?;
-    let filename = proto_path.file_name().unwrap().to_str().unwrap();
-    let file = parsed
-        .file_descriptors
-        .iter()
-        .find(|&x| {
-            let name = x.get_name().to_string();
-            name.ends_with(filename)
-        })
-        .unwrap();
-    let service = file.service.first().unwrap();
-
     let lb = LoadBalancer::new(hosts, port);
 
     // Generate writer.
     let options = service.options.as_ref().unwrap_or_default();
     match http_service.get(&options) {
         Some(service) => {
-            let version = service.version.unwrap();
-            return Ok(Box::new(Mutex::new(HttpWriter::new(lb, version))));
+            return Ok(Arc::new(Mutex::new(HttpWriter::new(lb, &service)?)));
         }
         None => {}
     };
     match postgres_service.get(&options) {
-        Some(_) => return Ok(Box::new(Mutex::new(postgres::PostgresWriter::new(lb)?))),
+        Some(_) => return Ok(Arc::new(Mutex::new(postgres::PostgresWriter::new(lb)?))),
         None => {}
     };
 
     Err(ServiceError::new("no format defined in service"))
 }
 
+#[derive(Clone)]
 pub struct LoadBalancer { 
     ips: Arc<RwLock<VecDeque<String>>>,
     port: i32,
@@ -5,10 +5,14 @@ use std::{
 };
 
 use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
 
 use crate::{
     handlers::sql::SQLValue,
-    services::{Fields, Handler, ServiceResult, Writer, WriterContext},
+    services::{
+        template::SuccessStatus, with_deadline, Deadline, Fields, Handler, ServiceError,
+        ServiceResult, Writer, WriterContext, WriterResponse,
+    },
 };
 use postgres_types::{FromSql, IsNull, ToSql};
 
@@ -31,10 +35,45 @@ impl PostgresWriter {
 impl Writer for PostgresWriter {
     async fn write_request(
         &mut self,
-        _: WriterContext,
+        context: WriterContext,
+        deadline: Deadline,
+        _success: &SuccessStatus,
         fields: &Fields,
         handler: &std::sync::Arc<dyn Handler + Send + Sync>,
-    ) -> ServiceResult<bytes::Bytes> {
+    ) -> ServiceResult<WriterResponse<bytes::Bytes>> {
+        let queries = handler.to_payload(fields).await?;
+        let resp = with_deadline(deadline, self.execute(context, queries, deadline)).await?;
+        Ok(WriterResponse::new(resp))
+    }
+
+    async fn write_batch(
+        &mut self,
+        context: WriterContext,
+        deadline: Deadline,
+        _success: &SuccessStatus,
+        fields: &[Fields],
+        handler: &std::sync::Arc<dyn Handler + Send + Sync>,
+    ) -> ServiceResult<WriterResponse<bytes::Bytes>> {
+        let queries = handler.to_payload_batch(fields).await?;
+        let resp = with_deadline(deadline, self.execute(context, queries, deadline)).await?;
+        Ok(WriterResponse::new(resp))
+    }
+
+    fn probe(&self) -> BoxFuture<'static, ServiceResult<()>> {
+        let writer = Self {
+            lb: self.lb.clone(),
+        };
+        async move {
+            let client = writer.connect().await?;
+            client.simple_query("SELECT 1").await?;
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+impl PostgresWriter {
+    async fn connect(&self) -> ServiceResult<Client> {
         let addr = self.lb.get_addr().await;
         let addr: SocketAddr = addr.parse()?;
         // Authentication configuration not currently supported.
@@ -43,19 +82,62 @@ impl Writer for PostgresWriter {
             addr.ip(),
             addr.port()
         );
-        let (client, conn) = tokio_postgres::connect(&config, NoTls).await?;
+        let (client, conn) = tokio_postgres::connect(&config, NoTls)
+            .await
+            .map_err(|err| ServiceError::Unavailable(err.to_string()))?;
         tokio::spawn(async move {
             if let Err(e) = conn.await {
                 log::error!("connection error: {}", e);
             }
         });
-        let queries = handler.to_payload(fields).await?;
+        Ok(client)
+    }
+
+    async fn execute(
+        &self,
+        context: WriterContext,
+        queries: bytes::Bytes,
+        deadline: Deadline,
+    ) -> ServiceResult<bytes::Bytes> {
+        let client = self.connect().await?;
+        // Forwarded metadata is readable in queries as current_setting('pandit.<key>').
+        let settings: Vec<(String, String)> = context
+            .iter()
+            .map(|entry| {
+                (
+                    format!("pandit.{}", entry.key().replace('-', "_")),
+                    entry.value().clone(),
+                )
+            })
+            .collect();
+        for (name, value) in settings.iter() {
+            client
+                .execute("SELECT set_config($1, $2, false)", &[name, value])
+                .await?;
+        }
+        // Have the server cancel queries that outlive the deadline.
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let statement_timeout = format!(
+                "SET statement_timeout = {}",
+                remaining.as_millis().max(1)
+            );
+            client.batch_execute(&statement_timeout).await?;
+        }
         use bytes::Buf;
         let queries: Vec<(String, String)> = serde_json::from_reader(queries.reader())?;
         let mut out_rows = Vec::<(String, HashMap<String, SQLValue>)>::with_capacity(queries.len());
         for (name, query) in queries {
             log::info!("pg: executing query '{}'", query);
-            let rows = client.query_opt(&query, &[]).await?;
+            let rows = client.query_opt(&query, &[]).await.map_err(|err| {
+                match err.code() {
+                    Some(state) => ServiceError::Backend {
+                        code: state.code().to_string(),
+                        message: err.to_string(),
+                    },
+                    None => err.into(),
+                }
+            })?;
             let row = match rows {
                 Some(v) => v,
                 None => continue,